@@ -0,0 +1,14 @@
+-- Add down migration script here
+ALTER TABLE join_applications DROP COLUMN reason;
+-- an enum value can not be dropped, so the type is recreated without it, the withdrawn applications are forgotten
+DELETE FROM join_applications WHERE status = 'Withdrawn';
+ALTER TABLE join_applications ALTER COLUMN status DROP DEFAULT;
+ALTER TYPE ApplicationStatus RENAME TO ApplicationStatus_old;
+CREATE TYPE ApplicationStatus AS ENUM (
+    'Pending',
+    'Approved',
+    'Rejected'
+);
+ALTER TABLE join_applications ALTER COLUMN status TYPE ApplicationStatus USING status::TEXT::ApplicationStatus;
+ALTER TABLE join_applications ALTER COLUMN status SET DEFAULT 'Pending';
+DROP TYPE ApplicationStatus_old;
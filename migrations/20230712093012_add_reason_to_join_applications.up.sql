@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TYPE ApplicationStatus ADD VALUE IF NOT EXISTS 'Withdrawn';
+ALTER TABLE join_applications ADD COLUMN reason TEXT;
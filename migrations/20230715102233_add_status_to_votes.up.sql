@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE votes ADD COLUMN status VARCHAR NOT NULL DEFAULT 'Open';
+UPDATE votes SET status = 'Closed' WHERE deadline < CURRENT_DATE;
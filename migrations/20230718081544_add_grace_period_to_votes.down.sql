@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE votes DROP COLUMN grace_period;
+ALTER TABLE answers DROP COLUMN late;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE votes ADD COLUMN grace_period INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE answers ADD COLUMN late BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE questions DROP COLUMN min_selections, DROP COLUMN max_selections, DROP COLUMN required;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE questions
+    ADD COLUMN min_selections INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN max_selections INTEGER,
+    ADD COLUMN required BOOLEAN NOT NULL DEFAULT false;
+UPDATE questions SET max_selections = 1 WHERE UPPER(type_) = 'SINGLE';
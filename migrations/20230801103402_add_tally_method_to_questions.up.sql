@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE questions ADD COLUMN tally_method VARCHAR NOT NULL DEFAULT 'InstantRunoff';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE value_answers;
+
+ALTER TABLE questions DROP COLUMN number_min, DROP COLUMN number_max, DROP COLUMN number_step;
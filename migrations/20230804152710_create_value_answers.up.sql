@@ -0,0 +1,15 @@
+-- Add up migration script here
+ALTER TABLE questions ADD COLUMN number_min DOUBLE PRECISION, ADD COLUMN number_max DOUBLE PRECISION, ADD COLUMN number_step DOUBLE PRECISION;
+
+CREATE TABLE value_answers (
+    id SERIAL NOT NULL,
+    question_id INTEGER NOT NULL REFERENCES questions (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    text TEXT,
+    number DOUBLE PRECISION,
+    date DATE,
+    late BOOLEAN NOT NULL DEFAULT false,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_value_answers_question_id_user_id UNIQUE (question_id, user_id),
+    CONSTRAINT check_value_answers_single_value CHECK (num_nonnulls(text, number, date) = 1)
+);
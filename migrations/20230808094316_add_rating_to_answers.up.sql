@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE answers ADD COLUMN rating INTEGER;
@@ -0,0 +1,11 @@
+-- Add down migration script here
+DELETE FROM value_answers WHERE user_id IS NULL;
+ALTER TABLE value_answers DROP CONSTRAINT check_value_answers_respondent, DROP CONSTRAINT unique_value_answers_question_id_ballot, DROP COLUMN ballot, ALTER COLUMN user_id SET NOT NULL;
+
+DELETE FROM answers WHERE user_id IS NULL;
+ALTER TABLE answers DROP CONSTRAINT check_answers_respondent, DROP CONSTRAINT unique_answers_ballot_option_id, DROP COLUMN ballot, ALTER COLUMN user_id SET NOT NULL;
+
+DROP TABLE ballots;
+DROP TABLE vote_participations;
+
+ALTER TABLE votes DROP COLUMN anonymous;
@@ -0,0 +1,28 @@
+-- Add up migration script here
+ALTER TABLE votes ADD COLUMN anonymous BOOLEAN NOT NULL DEFAULT false;
+
+-- who has voted in an anonymous vote, deliberately without any reference to the ballot
+CREATE TABLE vote_participations (
+    vote_id INTEGER NOT NULL REFERENCES votes (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (vote_id, user_id)
+);
+
+-- ballots are identified by the hash of the token held by the voter
+CREATE TABLE ballots (
+    token_hash VARCHAR NOT NULL,
+    vote_id INTEGER NOT NULL REFERENCES votes (id) ON DELETE CASCADE,
+    PRIMARY KEY (token_hash)
+);
+
+ALTER TABLE answers
+    ALTER COLUMN user_id DROP NOT NULL,
+    ADD COLUMN ballot VARCHAR REFERENCES ballots (token_hash) ON DELETE CASCADE,
+    ADD CONSTRAINT unique_answers_ballot_option_id UNIQUE (ballot, option_id),
+    ADD CONSTRAINT check_answers_respondent CHECK (num_nonnulls(user_id, ballot) = 1);
+
+ALTER TABLE value_answers
+    ALTER COLUMN user_id DROP NOT NULL,
+    ADD COLUMN ballot VARCHAR REFERENCES ballots (token_hash) ON DELETE CASCADE,
+    ADD CONSTRAINT unique_value_answers_question_id_ballot UNIQUE (question_id, ballot),
+    ADD CONSTRAINT check_value_answers_respondent CHECK (num_nonnulls(user_id, ballot) = 1);
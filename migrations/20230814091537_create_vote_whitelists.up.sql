@@ -0,0 +1,8 @@
+-- Add up migration script here
+CREATE TABLE vote_whitelists (
+    id SERIAL NOT NULL,
+    vote_id INTEGER NOT NULL REFERENCES votes (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_vote_whitelists_vote_id_user_id UNIQUE (vote_id, user_id)
+);
@@ -0,0 +1,17 @@
+-- Add down migration script here
+DELETE FROM value_answers WHERE guest_id IS NOT NULL;
+ALTER TABLE value_answers
+    DROP CONSTRAINT check_value_answers_respondent,
+    DROP CONSTRAINT unique_value_answers_question_id_guest_id,
+    DROP COLUMN guest_id,
+    ADD CONSTRAINT check_value_answers_respondent CHECK (num_nonnulls(user_id, ballot) = 1);
+
+DELETE FROM answers WHERE guest_id IS NOT NULL;
+ALTER TABLE answers
+    DROP CONSTRAINT check_answers_respondent,
+    DROP CONSTRAINT unique_answers_guest_id_option_id,
+    DROP COLUMN guest_id,
+    ADD CONSTRAINT check_answers_respondent CHECK (num_nonnulls(user_id, ballot) = 1);
+
+DROP TABLE guests;
+DROP TABLE share_links;
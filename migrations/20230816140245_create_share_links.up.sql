@@ -0,0 +1,33 @@
+-- Add up migration script here
+CREATE TABLE share_links (
+    id SERIAL NOT NULL,
+    vote_id INTEGER NOT NULL REFERENCES votes (id) ON DELETE CASCADE,
+    token_hash VARCHAR NOT NULL,
+    creator INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    rate_limit INTEGER NOT NULL,
+    revoked BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CONSTRAINT unique_share_links_token_hash UNIQUE (token_hash)
+);
+
+CREATE TABLE guests (
+    id SERIAL NOT NULL,
+    share_link_id INTEGER NOT NULL REFERENCES share_links (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    submitted_at TIMESTAMPTZ,
+    PRIMARY KEY (id)
+);
+
+ALTER TABLE answers
+    ADD COLUMN guest_id INTEGER REFERENCES guests (id) ON DELETE CASCADE,
+    ADD CONSTRAINT unique_answers_guest_id_option_id UNIQUE (guest_id, option_id),
+    DROP CONSTRAINT check_answers_respondent,
+    ADD CONSTRAINT check_answers_respondent CHECK (num_nonnulls(user_id, ballot, guest_id) = 1);
+
+ALTER TABLE value_answers
+    ADD COLUMN guest_id INTEGER REFERENCES guests (id) ON DELETE CASCADE,
+    ADD CONSTRAINT unique_value_answers_question_id_guest_id UNIQUE (question_id, guest_id),
+    DROP CONSTRAINT check_value_answers_respondent,
+    ADD CONSTRAINT check_value_answers_respondent CHECK (num_nonnulls(user_id, ballot, guest_id) = 1);
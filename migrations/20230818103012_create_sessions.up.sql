@@ -0,0 +1,16 @@
+-- Add up migration script here
+CREATE TABLE sessions (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    refresh_token_hash VARCHAR NOT NULL,
+    -- kept to detect a rotated refresh token being reused
+    previous_token_hash VARCHAR,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_sessions_refresh_token_hash UNIQUE (refresh_token_hash)
+);
+
+CREATE INDEX sessions_previous_token_hash_idx ON sessions (previous_token_hash);
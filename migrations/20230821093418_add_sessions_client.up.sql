@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE sessions ADD COLUMN device VARCHAR NOT NULL DEFAULT 'Unknown';
+ALTER TABLE sessions ADD COLUMN ip VARCHAR;
+ALTER TABLE sessions ADD COLUMN user_agent VARCHAR;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+UPDATE users SET salt = '' WHERE salt IS NULL;
+ALTER TABLE users ALTER COLUMN salt SET NOT NULL;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- argon2 hashes are PHC strings which carry their own salt
+ALTER TABLE users ALTER COLUMN salt DROP NOT NULL;
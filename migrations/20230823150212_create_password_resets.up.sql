@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE TABLE password_resets (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    token_hash VARCHAR NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CONSTRAINT unique_password_resets_token_hash UNIQUE (token_hash)
+);
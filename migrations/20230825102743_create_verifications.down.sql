@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE verifications;
+ALTER TABLE users DROP COLUMN email_verified;
+ALTER TABLE users DROP COLUMN phone_verified;
@@ -0,0 +1,18 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN phone_verified BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE users ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE verifications (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    channel VARCHAR NOT NULL,
+    target VARCHAR NOT NULL,
+    code_hash VARCHAR NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    used_at TIMESTAMPTZ,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX verifications_user_id_channel_idx ON verifications (user_id, channel);
@@ -0,0 +1,11 @@
+-- Add down migration script here
+-- the codes used up are deleted, as signing up used to do
+DELETE FROM invite_codes WHERE uses >= max_uses OR revoked;
+ALTER TABLE invite_codes DROP CONSTRAINT unique_invite_codes_code;
+ALTER TABLE invite_codes DROP COLUMN created_at;
+ALTER TABLE invite_codes DROP COLUMN revoked;
+ALTER TABLE invite_codes DROP COLUMN uses;
+ALTER TABLE invite_codes DROP COLUMN max_uses;
+ALTER TABLE invite_codes DROP COLUMN expires_at;
+ALTER TABLE invite_codes DROP COLUMN organization_id;
+ALTER TABLE invite_codes DROP COLUMN issuer;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+ALTER TABLE invite_codes ADD COLUMN issuer INTEGER REFERENCES users (id) ON DELETE SET NULL;
+ALTER TABLE invite_codes ADD COLUMN organization_id INTEGER REFERENCES organizations (id) ON DELETE CASCADE;
+ALTER TABLE invite_codes ADD COLUMN expires_at TIMESTAMPTZ;
+ALTER TABLE invite_codes ADD COLUMN max_uses INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE invite_codes ADD COLUMN uses INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE invite_codes ADD COLUMN revoked BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE invite_codes ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE invite_codes ADD CONSTRAINT unique_invite_codes_code UNIQUE (code);
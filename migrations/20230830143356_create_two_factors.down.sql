@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE organizations DROP COLUMN require_manager_two_factor;
+DROP TABLE two_factor_challenges;
+DROP TABLE recovery_codes;
+DROP TABLE two_factors;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+CREATE TABLE two_factors (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    secret VARCHAR NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT false,
+    last_counter BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id)
+);
+
+CREATE TABLE recovery_codes (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash VARCHAR NOT NULL,
+    used_at TIMESTAMPTZ,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_recovery_codes_user_id_code_hash UNIQUE (user_id, code_hash)
+);
+
+CREATE TABLE two_factor_challenges (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    token_hash VARCHAR NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CONSTRAINT unique_two_factor_challenges_token_hash UNIQUE (token_hash)
+);
+
+ALTER TABLE organizations ADD COLUMN require_manager_two_factor BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+CREATE TABLE personal_tokens (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    token_hash VARCHAR NOT NULL,
+    scopes VARCHAR[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_personal_tokens_token_hash UNIQUE (token_hash)
+);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE oidc_logins;
+DROP TABLE user_identities;
+ALTER TABLE users ALTER COLUMN phone SET NOT NULL, ALTER COLUMN email SET NOT NULL;
@@ -0,0 +1,23 @@
+-- Add up migration script here
+ALTER TABLE users ALTER COLUMN phone DROP NOT NULL, ALTER COLUMN email DROP NOT NULL;
+
+CREATE TABLE user_identities (
+    id SERIAL NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    issuer VARCHAR NOT NULL,
+    subject VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CONSTRAINT unique_user_identities_issuer_subject UNIQUE (issuer, subject)
+);
+
+CREATE TABLE oidc_logins (
+    id SERIAL NOT NULL,
+    state_hash VARCHAR NOT NULL,
+    nonce VARCHAR NOT NULL,
+    code_verifier VARCHAR NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CONSTRAINT unique_oidc_logins_state_hash UNIQUE (state_hash)
+);
@@ -0,0 +1,6 @@
+-- Add up migration script here
+ALTER TABLE organizations ADD COLUMN owner_id INTEGER REFERENCES users (id) ON DELETE SET NULL;
+
+-- the first manager of an organization is taken as its owner
+UPDATE organizations AS o
+SET owner_id = (SELECT m.user_id FROM organization_managers AS m WHERE m.organization_id = o.id ORDER BY m.id LIMIT 1);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE organization_members DROP COLUMN role;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- the owner and the managers are not stored here, see organizations.owner_id and organization_managers
+ALTER TABLE organization_members ADD COLUMN role VARCHAR NOT NULL DEFAULT 'Voter';
+ALTER TABLE organization_members ADD CONSTRAINT check_organization_members_role CHECK (role IN ('Editor', 'Voter', 'Observer'));
+
+-- every member could create votes before the roles
+UPDATE organization_members SET role = 'Editor';
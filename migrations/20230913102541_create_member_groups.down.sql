@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP VIEW vote_audiences;
+DROP TABLE vote_groups;
+DROP TABLE member_group_users;
+DROP TABLE member_groups;
@@ -0,0 +1,39 @@
+-- Add up migration script here
+CREATE TABLE member_groups (
+    id SERIAL NOT NULL,
+    organization_id INTEGER NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (id),
+    CONSTRAINT unique_member_groups_organization_id_name UNIQUE (organization_id, name)
+);
+
+CREATE TABLE member_group_users (
+    id SERIAL NOT NULL,
+    group_id INTEGER NOT NULL REFERENCES member_groups (id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_member_group_users_group_id_user_id UNIQUE (group_id, user_id)
+);
+
+-- the groups a vote is targeted at, a vote without any is targeted at the whole organization
+CREATE TABLE vote_groups (
+    id SERIAL NOT NULL,
+    vote_id INTEGER NOT NULL REFERENCES votes (id) ON DELETE CASCADE,
+    group_id INTEGER NOT NULL REFERENCES member_groups (id) ON DELETE CASCADE,
+    PRIMARY KEY (id),
+    CONSTRAINT unique_vote_groups_vote_id_group_id UNIQUE (vote_id, group_id)
+);
+
+-- the members expected to take part in a vote, the participation of the reports is taken against them
+CREATE VIEW vote_audiences AS
+SELECT v.id AS vote_id, m.user_id
+FROM votes AS v
+JOIN organization_members AS m ON v.organization_id = m.organization_id
+WHERE NOT EXISTS (SELECT 1 FROM vote_groups AS vg WHERE vg.vote_id = v.id)
+OR EXISTS (
+    SELECT 1
+    FROM vote_groups AS vg
+    JOIN member_group_users AS gu ON vg.group_id = gu.group_id
+    WHERE vg.vote_id = v.id AND gu.user_id = m.user_id
+);
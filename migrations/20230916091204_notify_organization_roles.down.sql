@@ -0,0 +1,14 @@
+-- Add down migration script here
+DROP TRIGGER notify_organizations_roles ON organizations;
+DROP TRIGGER notify_organization_managers_roles ON organization_managers;
+DROP TRIGGER notify_organization_members_roles ON organization_members;
+DROP FUNCTION notify_organization_roles();
+
+INSERT INTO casbin_rules (ptype, v0, v1, v2)
+SELECT 'g', m.user_id::VARCHAR, CASE
+        WHEN o.owner_id = m.user_id THEN 'Owner'
+        WHEN EXISTS(SELECT * FROM organization_managers AS om WHERE om.organization_id = o.id AND om.user_id = m.user_id) THEN 'Manager'
+        ELSE m.role
+    END, m.organization_id::VARCHAR
+FROM organization_members AS m
+JOIN organizations AS o ON m.organization_id = o.id;
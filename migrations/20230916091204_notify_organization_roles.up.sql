@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- the roles are read from the memberships, the enforcers reload the roles of an organization when they are notified
+DELETE FROM casbin_rules WHERE ptype = 'g';
+
+CREATE FUNCTION notify_organization_roles() RETURNS TRIGGER AS $$
+DECLARE
+    changed RECORD;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        changed := OLD;
+    ELSE
+        changed := NEW;
+    END IF;
+    IF TG_TABLE_NAME = 'organizations' THEN
+        PERFORM pg_notify('organization_roles', changed.id::TEXT);
+    ELSE
+        PERFORM pg_notify('organization_roles', changed.organization_id::TEXT);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notify_organization_members_roles AFTER INSERT OR UPDATE OF role OR DELETE ON organization_members
+FOR EACH ROW EXECUTE FUNCTION notify_organization_roles();
+
+CREATE TRIGGER notify_organization_managers_roles AFTER INSERT OR DELETE ON organization_managers
+FOR EACH ROW EXECUTE FUNCTION notify_organization_roles();
+
+CREATE TRIGGER notify_organizations_roles AFTER UPDATE OF owner_id OR DELETE ON organizations
+FOR EACH ROW EXECUTE FUNCTION notify_organization_roles();
@@ -0,0 +1,39 @@
+-- Add down migration script here
+CREATE TABLE casbin_rules (
+    id SERIAL PRIMARY KEY,
+    ptype VARCHAR NOT NULL,
+    v0 VARCHAR NOT NULL DEFAULT '',
+    v1 VARCHAR NOT NULL DEFAULT '',
+    v2 VARCHAR NOT NULL DEFAULT '',
+    v3 VARCHAR NOT NULL DEFAULT '',
+    v4 VARCHAR NOT NULL DEFAULT '',
+    v5 VARCHAR NOT NULL DEFAULT '',
+    CONSTRAINT unique_casbin_rules_rule UNIQUE (ptype, v0, v1, v2, v3, v4, v5)
+);
+
+-- the permissions of the roles are the same in every organization
+INSERT INTO casbin_rules (ptype, v0, v1, v2) VALUES
+    ('p', 'Owner', 'organization', 'read'),
+    ('p', 'Owner', 'organization', 'write'),
+    ('p', 'Owner', 'vote', 'read'),
+    ('p', 'Owner', 'vote', 'write'),
+    ('p', 'Owner', 'question', 'read'),
+    ('p', 'Owner', 'question', 'write'),
+    ('p', 'Manager', 'organization', 'read'),
+    ('p', 'Manager', 'organization', 'write'),
+    ('p', 'Manager', 'vote', 'read'),
+    ('p', 'Manager', 'vote', 'write'),
+    ('p', 'Manager', 'question', 'read'),
+    ('p', 'Manager', 'question', 'write'),
+    ('p', 'Editor', 'organization', 'read'),
+    ('p', 'Editor', 'vote', 'read'),
+    ('p', 'Editor', 'vote', 'write'),
+    ('p', 'Editor', 'question', 'read'),
+    ('p', 'Editor', 'question', 'write'),
+    ('p', 'Voter', 'organization', 'read'),
+    ('p', 'Voter', 'vote', 'read'),
+    ('p', 'Voter', 'question', 'read'),
+    ('p', 'Observer', 'organization', 'read'),
+    ('p', 'Observer', 'vote', 'read'),
+    ('p', 'Observer', 'question', 'read');
+
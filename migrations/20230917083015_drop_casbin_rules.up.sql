@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- the permissions of the roles are generated from the permission matrix of the roles, nothing is stored anymore
+DROP TABLE casbin_rules;
@@ -85,7 +85,7 @@ impl Authorizer for PgAuthorizer {
                 JOIN organization_members AS uo ON u.id = uo.user_id
                 JOIN organizations AS o ON uo.organization_id = o.id
                 JOIN votes AS v ON v.organization_id = o.id
-                JOIN questions AS q ON v.question_id = q.id
+                JOIN questions AS q ON q.vote_id = v.id
                 WHERE u.id = $1 AND q.id = $2
             )"#,
         )
@@ -105,7 +105,7 @@ impl Authorizer for PgAuthorizer {
                 JOIN organization_members AS uo ON u.id = uo.user_id
                 JOIN organizations AS o ON uo.organization_id = o.id
                 JOIN votes AS v ON v.organization_id = o.id
-                JOIN questions AS q ON v.question_id = q.id
+                JOIN questions AS q ON q.vote_id = v.id
                 WHERE u.id = $1 AND q.id = $2
             )"#,
         )
@@ -1,10 +1,13 @@
 use crate::actix_web::FromRequest;
 use crate::actix_web::{self, Error, HttpMessage};
+use serde::{Deserialize, Serialize};
 use std::future::{ready, Ready};
 
 #[derive(Debug, Clone)]
 pub struct UserInfo {
     pub id: i32,
+    // the session the request is made with, none for the personal access tokens
+    pub session_id: Option<i32>,
 }
 
 impl FromRequest for UserInfo {
@@ -19,3 +22,23 @@ impl FromRequest for UserInfo {
         }
     }
 }
+
+// participant joined through a share link, only allowed to answer the shared vote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestInfo {
+    pub id: i32,
+    pub vote_id: i32,
+    pub share_link_id: i32,
+}
+
+impl FromRequest for GuestInfo {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        if let Some(guest) = req.extensions().get::<Self>() {
+            ready(Ok(guest.clone()))
+        } else {
+            ready(Err(actix_web::error::ErrorUnauthorized("")))
+        }
+    }
+}
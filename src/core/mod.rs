@@ -1,3 +1,5 @@
 pub mod models;
 pub mod ports;
 pub mod services;
+pub mod tally;
+pub mod totp;
@@ -1,15 +1,85 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+// answer of a question which has no options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Value {
+    Text(String),
+    Number(f64),
+    Date(NaiveDate),
+}
+
+// rating of a matrix row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rating {
+    pub option_id: i32,
+    pub rating: i32,
+}
+
+// who an answer is stored for, the answers of an anonymous vote only keep the hash of the ballot token
+#[derive(Debug, Clone)]
+pub enum Respondent {
+    User(i32),
+    Ballot(String),
+    // participant without an account, joined through a share link
+    Guest(i32),
+}
+
+impl Respondent {
+    pub fn user_id(&self) -> Option<i32> {
+        match self {
+            Respondent::User(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn ballot(&self) -> Option<String> {
+        match self {
+            Respondent::Ballot(hash) => Some(hash.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn guest_id(&self) -> Option<i32> {
+        match self {
+            Respondent::Guest(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+// guest answers are reported apart from the ones of the users
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Source {
+    #[default]
+    Users,
+    Guests,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Submit {
     pub question_id: i32,
+    #[serde(default)]
     pub option_ids: Vec<i32>,
+    #[serde(default)]
+    pub value: Option<Value>,
+    #[serde(default)]
+    pub ratings: Vec<Rating>,
+}
+
+impl Submit {
+    // the chosen options, or the rated rows of a matrix question
+    pub fn selected_option_ids(&self) -> Vec<i32> {
+        self.option_ids.iter().copied().chain(self.ratings.iter().map(|r| r.option_id)).collect()
+    }
 }
 
 pub struct BulkSubmit {
     pub vote_id: i32,
     pub submissions: Vec<Submit>,
+    pub ballot_token: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, FromRow)]
@@ -19,13 +89,44 @@ pub struct Answer {
     option_id: i32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct Insert {
-    pub user_id: i32,
+    pub respondent: Respondent,
     pub option_id: i32,
+    pub late: bool,
+    // 1 based preference order of ranked questions
+    pub position: Option<i32>,
+    // rating of the row of matrix questions
+    pub rating: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValueInsert {
+    pub question_id: i32,
+    pub respondent: Respondent,
+    pub value: Value,
+    pub late: bool,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TextAnswer {
+    pub id: i32,
+    // none for anonymous votes
+    pub user_id: Option<i32>,
+    pub text: String,
+    pub late: bool,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DateCount {
+    pub date: NaiveDate,
+    pub count: i64,
 }
 
+#[derive(Debug, Clone)]
 pub struct Query {
     pub question_id_eq: Option<i32>,
     pub user_id_eq: Option<i32>,
+    pub ballot_eq: Option<String>,
+    pub guest_id_eq: Option<i32>,
 }
@@ -1,19 +1,21 @@
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Type};
 
-#[derive(Type, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type, Serialize, Deserialize)]
 pub enum ApplicationStatus {
     Pending,
     Approved,
     Rejected,
+    Withdrawn,
 }
 
-#[derive(FromRow, Serialize)]
+#[derive(Debug, FromRow, Serialize)]
 pub struct JoinApplication {
     pub id: i32,
     pub user_id: i32,
     pub organization_id: i32,
     pub status: ApplicationStatus,
+    pub reason: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -21,3 +23,14 @@ pub struct JoinApplicationInsert {
     pub organization_id: i32,
 }
 
+#[derive(Debug, Default)]
+pub struct Query {
+    pub user_id_eq: Option<i32>,
+    pub organization_id_eq: Option<i32>,
+    pub status_eq: Option<ApplicationStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Reject {
+    pub reason: String,
+}
@@ -1,3 +1,5 @@
+use crate::error::Error;
+
 pub struct Pagination {
     limit: i64,
     offset: Option<i64>,
@@ -8,6 +10,15 @@ impl Pagination {
         Self { limit, offset }
     }
 
+    // the pages are numbered from 1
+    pub fn page(page: i64, size: i64) -> Result<Self, Error> {
+        if page < 1 || size < 1 {
+            return Err(Error::BusinessError("page and size must be positive".into()));
+        }
+        let offset = (page - 1).checked_mul(size).ok_or(Error::BusinessError("page out of range".into()))?;
+        Ok(Self::new(size, Some(offset)))
+    }
+
     pub fn to_sql_clause(&self) -> String {
         let mut stmt = format!("LIMIT {} ", self.limit);
         if let Some(offset) = self.offset {
@@ -16,3 +27,17 @@ impl Pagination {
         stmt
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_page() {
+        assert_eq!(Pagination::page(1, 10).unwrap().to_sql_clause(), "LIMIT 10 OFFSET 0");
+        assert_eq!(Pagination::page(3, 10).unwrap().to_sql_clause(), "LIMIT 10 OFFSET 20");
+        assert!(Pagination::page(0, 10).is_err());
+        assert!(Pagination::page(1, 0).is_err());
+        assert!(Pagination::page(i64::MAX, 10).is_err());
+    }
+}
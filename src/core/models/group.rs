@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Deserialize)]
+pub struct GroupCreate {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupUpdate {
+    pub name: String,
+}
+
+// a team of members inside an organization, votes can be targeted at groups instead of the whole organization
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct MemberGroup {
+    pub id: i32,
+    pub organization_id: i32,
+    pub name: String,
+    pub member_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct GroupMember {
+    pub user_id: i32,
+    pub nickname: String,
+    pub avatar: Option<String>,
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+// a single sign-on in progress, between the redirect to the provider and its callback
+#[derive(Debug, Clone, FromRow)]
+pub struct OidcLogin {
+    pub id: i32,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcLoginInsert {
+    // only the hash of the state is stored
+    pub state_hash: String,
+    pub nonce: String,
+    pub code_verifier: String,
+    pub expires_at: DateTime<Utc>,
+}
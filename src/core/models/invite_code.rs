@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::error::Error;
+
+// The users allowed to issue the codes which only allow to sign up, configured by `INVITE_CODE_ADMINS` as a comma
+// separated list of user ids. Nobody can issue them when it is not set.
+#[derive(Debug, Clone, Default)]
+pub struct Admins(Vec<i32>);
+
+impl Admins {
+    pub fn parse(ids: &str) -> Result<Self, Error> {
+        let ids = ids.split(',').map(str::trim).filter(|id| !id.is_empty()).map(str::parse).collect::<Result<_, _>>()?;
+        Ok(Self(ids))
+    }
+
+    pub fn contains(&self, uid: i32) -> bool {
+        self.0.contains(&uid)
+    }
+}
+
+fn default_max_uses() -> i32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InviteCodeCreate {
+    pub expires_at: Option<DateTime<Utc>>,
+    // how many users can sign up with the code
+    #[serde(default = "default_max_uses")]
+    pub max_uses: i32,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct InviteCode {
+    pub id: i32,
+    pub code: String,
+    // the codes created by SQL before the API have no issuer
+    pub issuer: Option<i32>,
+    // users signing up with an organization's code join the organization
+    pub organization_id: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub code: String,
+    pub issuer: i32,
+    pub organization_id: Option<i32>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_uses: i32,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_admins() {
+        let admins = Admins::parse("1, 2,").unwrap();
+        assert!(admins.contains(1));
+        assert!(admins.contains(2));
+        assert!(!admins.contains(3));
+        assert!(!Admins::parse("").unwrap().contains(1));
+        assert!(Admins::parse("1,admin").is_err());
+    }
+}
@@ -2,9 +2,19 @@ pub mod answer;
 pub mod application;
 pub mod common;
 pub mod date;
+pub mod group;
+pub mod identity;
+pub mod invite_code;
 pub mod option;
 pub mod organization;
+pub mod password_reset;
+pub mod personal_token;
 pub mod question;
+pub mod role;
+pub mod session;
+pub mod share_link;
+pub mod two_factor;
 pub mod upload_file;
 pub mod user;
+pub mod verification;
 pub mod vote;
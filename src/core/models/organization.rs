@@ -8,6 +8,19 @@ pub struct Organization {
     pub name: String,
     pub version: i64,
     pub description: String,
+    // the managers must have two-factor authentication enabled
+    pub require_manager_two_factor: bool,
+    // always one of the managers, unset only for the organizations which had no manager
+    pub owner_id: Option<i32>,
+}
+
+// what becomes of the answers of a member who leaves the organization
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub enum AnswerRetention {
+    #[default]
+    Keep,
+    // the answers are moved to ballots, so they still count but no longer tell who gave them
+    Anonymize,
 }
 
 #[derive(Debug, Clone, Serialize, FromRow, Default)]
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PasswordReset {
+    pub id: i32,
+    pub user_id: i32,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl PasswordReset {
+    // a reset token can only be used once
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && now < self.expires_at
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub user_id: i32,
+    // only the hash of the reset token is stored
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    // GET and HEAD requests
+    Read,
+    // all the requests
+    Write,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "Read",
+            Scope::Write => "Write",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PersonalTokenCreate {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PersonalToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl PersonalToken {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map(|expires_at| now < expires_at).unwrap_or(true)
+    }
+
+    // the Write scope includes the Read one
+    pub fn allows(&self, write: bool) -> bool {
+        self.scopes.iter().any(|s| s == Scope::Write.as_str() || (!write && s == Scope::Read.as_str()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub user_id: i32,
+    pub name: String,
+    // only the hash of the token is stored, the token itself is handed out once
+    pub token_hash: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn token(scopes: &[Scope]) -> PersonalToken {
+        PersonalToken {
+            id: 1,
+            user_id: 1,
+            name: "ci".into(),
+            scopes: scopes.iter().map(|s| s.as_str().to_owned()).collect(),
+            created_at: Utc::now(),
+            last_used_at: None,
+            expires_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn test_allows() {
+        assert!(token(&[Scope::Read]).allows(false));
+        assert!(!token(&[Scope::Read]).allows(true));
+        assert!(token(&[Scope::Write]).allows(false));
+        assert!(token(&[Scope::Write]).allows(true));
+        assert!(!token(&[]).allows(false));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::core::models::option::{Opt, OptCreate};
+use crate::error::Error;
 use juju_macros::ToTuple;
 use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "UPPERCASE")]
@@ -8,6 +10,172 @@ pub enum QuestionType {
     #[default]
     Single,
     Multi,
+    // options are ordered by preference, the first one is the most preferred
+    Ranked,
+    // free-text, numeric and date questions have no options, the answer is a single value
+    Text,
+    Number,
+    Date,
+    // a single rating on a 1-5 agreement scale
+    Likert,
+    // Net Promoter Score, a single rating from 0 to 10
+    Nps,
+    // every option is a row rated on the Likert scale
+    Matrix,
+}
+
+impl QuestionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionType::Single => "Single",
+            QuestionType::Multi => "Multi",
+            QuestionType::Ranked => "Ranked",
+            QuestionType::Text => "Text",
+            QuestionType::Number => "Number",
+            QuestionType::Date => "Date",
+            QuestionType::Likert => "Likert",
+            QuestionType::Nps => "Nps",
+            QuestionType::Matrix => "Matrix",
+        }
+    }
+
+    pub fn is_option_based(&self) -> bool {
+        matches!(self, QuestionType::Single | QuestionType::Multi | QuestionType::Ranked | QuestionType::Matrix)
+    }
+
+    pub fn rating_scale(&self) -> Option<RangeInclusive<i32>> {
+        match self {
+            QuestionType::Likert | QuestionType::Matrix => Some(1..=5),
+            QuestionType::Nps => Some(0..=10),
+            _ => None,
+        }
+    }
+
+    // SINGLE always means exactly one option. MULTI and RANKED have no upper limit by default and need at least one
+    // option when they are required, an optional one can be left empty.
+    pub fn selection_bounds(&self, min: Option<i32>, max: Option<i32>, required: bool) -> Result<(i32, Option<i32>), Error> {
+        match self {
+            QuestionType::Single => {
+                if min.unwrap_or(1) != 1 || max.unwrap_or(1) != 1 {
+                    return Err(Error::BusinessError("single choice question must select exactly one option".into()));
+                }
+                Ok((1, Some(1)))
+            }
+            QuestionType::Multi | QuestionType::Ranked | QuestionType::Matrix => {
+                let least = if required { 1 } else { 0 };
+                let min = min.unwrap_or(least);
+                if min < least {
+                    return Err(Error::BusinessError(format!("min selections must be at least {}", least)));
+                }
+                if let Some(max) = max {
+                    if max < min.max(1) {
+                        return Err(Error::BusinessError("max selections must be at least 1 and not less than min selections".into()));
+                    }
+                }
+                Ok((min, max))
+            }
+            QuestionType::Text | QuestionType::Number | QuestionType::Date | QuestionType::Likert | QuestionType::Nps => {
+                if min.unwrap_or(1) != 1 || max.unwrap_or(1) != 1 {
+                    return Err(Error::BusinessError(format!("{} question accepts exactly one answer", self.as_str().to_lowercase())));
+                }
+                Ok((1, Some(1)))
+            }
+        }
+    }
+
+    // only NUMBER questions keep a range, it is ignored for the other types
+    pub fn number_range(&self, range: NumberRange) -> Result<NumberRange, Error> {
+        match self {
+            QuestionType::Number => {
+                range.validate()?;
+                Ok(range)
+            }
+            _ => Ok(NumberRange::default()),
+        }
+    }
+}
+
+impl TryFrom<&str> for QuestionType {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_uppercase().as_str() {
+            "SINGLE" => Ok(QuestionType::Single),
+            "MULTI" => Ok(QuestionType::Multi),
+            "RANKED" => Ok(QuestionType::Ranked),
+            "TEXT" => Ok(QuestionType::Text),
+            "NUMBER" => Ok(QuestionType::Number),
+            "DATE" => Ok(QuestionType::Date),
+            "LIKERT" => Ok(QuestionType::Likert),
+            "NPS" => Ok(QuestionType::Nps),
+            "MATRIX" => Ok(QuestionType::Matrix),
+            _ => Err(Error::BusinessError(format!("invalid question type: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct NumberRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    // accepted values are min + n * step (or n * step without min)
+    pub step: Option<f64>,
+}
+
+impl NumberRange {
+    pub fn validate(&self) -> Result<(), Error> {
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if max < min {
+                return Err(Error::BusinessError("max must not be less than min".into()));
+            }
+        }
+        if let Some(step) = self.step {
+            if step.is_nan() || step <= 0.0 {
+                return Err(Error::BusinessError("step must be positive".into()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn accepts(&self, value: f64) -> bool {
+        if !value.is_finite() || self.min.map_or(false, |min| value < min) || self.max.map_or(false, |max| value > max) {
+            return false;
+        }
+        match self.step {
+            Some(step) => {
+                let n = (value - self.min.unwrap_or(0.0)) / step;
+                (n - n.round()).abs() < 1e-9
+            }
+            None => true,
+        }
+    }
+}
+
+// which tally result of a ranked question is the official one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TallyMethod {
+    #[default]
+    InstantRunoff,
+    Schulze,
+}
+
+impl TallyMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TallyMethod::InstantRunoff => "InstantRunoff",
+            TallyMethod::Schulze => "Schulze",
+        }
+    }
+}
+
+impl TryFrom<&str> for TallyMethod {
+    type Error = Error;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "InstantRunoff" => Ok(TallyMethod::InstantRunoff),
+            "Schulze" => Ok(TallyMethod::Schulze),
+            _ => Err(Error::BusinessError(format!("invalid tally method: {}", s))),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -15,6 +183,14 @@ pub struct QuestionCreate {
     pub description: String,
     pub type_: QuestionType,
     pub options: Vec<OptCreate>,
+    pub min_selections: Option<i32>,
+    pub max_selections: Option<i32>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub tally_method: TallyMethod,
+    #[serde(default)]
+    pub number_range: NumberRange,
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
@@ -30,6 +206,11 @@ pub struct Question {
     pub options: Vec<Opt>,
     pub likes: i32,
     pub dislikes: i32,
+    pub min_selections: i32,
+    pub max_selections: Option<i32>,
+    pub required: bool,
+    pub tally_method: String,
+    pub number_range: NumberRange,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -38,6 +219,11 @@ pub struct Insert {
     pub vote_id: i32,
     pub type_: String,
     pub version: i64,
+    pub min_selections: i32,
+    pub max_selections: Option<i32>,
+    pub required: bool,
+    pub tally_method: String,
+    pub number_range: NumberRange,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,6 +232,28 @@ pub struct Create {
     pub type_: String,
     pub version: i64,
     pub options: Vec<OptCreate>,
+    pub min_selections: Option<i32>,
+    pub max_selections: Option<i32>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub tally_method: TallyMethod,
+    #[serde(default)]
+    pub number_range: NumberRange,
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectionConstraint {
+    pub min_selections: i32,
+    pub max_selections: Option<i32>,
+    pub required: bool,
+}
+
+impl SelectionConstraint {
+    pub fn accepts(&self, selections: usize) -> bool {
+        let n = selections as i32;
+        n >= self.min_selections && self.max_selections.map_or(true, |max| n <= max)
+    }
 }
 
 pub struct Query {
@@ -77,3 +285,41 @@ pub struct FavoriteQuestionQuery {
     pub question_id_eq: Option<i32>,
     pub attitude_eq: Option<i32>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_selection_bounds() {
+        assert_eq!(QuestionType::Multi.selection_bounds(None, None, true).unwrap(), (1, None));
+        assert_eq!(QuestionType::Multi.selection_bounds(None, None, false).unwrap(), (0, None));
+        assert_eq!(QuestionType::Ranked.selection_bounds(Some(0), Some(3), false).unwrap(), (0, Some(3)));
+        assert!(QuestionType::Matrix.selection_bounds(Some(0), None, true).is_err());
+        assert!(QuestionType::Multi.selection_bounds(Some(-1), None, false).is_err());
+        assert!(QuestionType::Multi.selection_bounds(Some(2), Some(1), false).is_err());
+        assert!(QuestionType::Multi.selection_bounds(Some(0), Some(0), false).is_err());
+        assert_eq!(QuestionType::Single.selection_bounds(None, None, false).unwrap(), (1, Some(1)));
+        assert!(QuestionType::Single.selection_bounds(Some(0), None, false).is_err());
+    }
+
+    #[test]
+    fn test_selection_constraint() {
+        let constraint = SelectionConstraint {
+            min_selections: 1,
+            max_selections: Some(2),
+            required: true,
+        };
+        assert!(!constraint.accepts(0));
+        assert!(constraint.accepts(1));
+        assert!(constraint.accepts(2));
+        assert!(!constraint.accepts(3));
+        let optional = SelectionConstraint {
+            min_selections: 0,
+            max_selections: None,
+            required: false,
+        };
+        assert!(optional.accepts(0));
+        assert!(optional.accepts(10));
+    }
+}
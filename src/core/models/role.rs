@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    decode::Decode,
+    encode::{Encode, IsNull},
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+    Postgres, Type,
+};
+
+// The role of a member in an organization. Only the editors, voters and observers are stored on the membership,
+// the owner and the managers are known from the organization and its managers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    Owner,
+    Manager,
+    Editor,
+    Voter,
+    Observer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    // every member can read the votes and their results, the permissions below are on top of it
+    Answer,
+    CreateVote,
+    EditVote,
+    // publishing, closing, visibility, whitelist and deletion of a vote
+    ManageVote,
+    ManageMembers,
+    ManageManagers,
+    UpdateOrganization,
+    DeleteOrganization,
+    TransferOwnership,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [Role::Owner, Role::Manager, Role::Editor, Role::Voter, Role::Observer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "Owner",
+            Role::Manager => "Manager",
+            Role::Editor => "Editor",
+            Role::Voter => "Voter",
+            Role::Observer => "Observer",
+        }
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        use Permission::*;
+        match self {
+            Role::Owner => true,
+            Role::Manager => !matches!(permission, DeleteOrganization | TransferOwnership),
+            Role::Editor => matches!(permission, Answer | CreateVote | EditVote),
+            Role::Voter => matches!(permission, Answer),
+            Role::Observer => false,
+        }
+    }
+
+    // the roles which are assigned to a member directly instead of by promotion
+    pub fn is_assignable(&self) -> bool {
+        matches!(self, Role::Editor | Role::Voter | Role::Observer)
+    }
+}
+
+impl TryFrom<&str> for Role {
+    type Error = String;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "Owner" => Ok(Role::Owner),
+            "Manager" => Ok(Role::Manager),
+            "Editor" => Ok(Role::Editor),
+            "Voter" => Ok(Role::Voter),
+            "Observer" => Ok(Role::Observer),
+            _ => Err(format!("invalid role: {}", s)),
+        }
+    }
+}
+
+// organization_members.role is a VARCHAR column
+impl Type<Postgres> for Role {
+    fn type_info() -> PgTypeInfo {
+        <&str as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <&str as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for Role {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Role {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let s = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(Role::try_from(s)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_permission_matrix() {
+        use Permission::*;
+        assert!(Role::Owner.can(DeleteOrganization));
+        assert!(Role::Owner.can(TransferOwnership));
+        assert!(Role::Manager.can(ManageManagers));
+        assert!(Role::Manager.can(ManageVote));
+        assert!(!Role::Manager.can(DeleteOrganization));
+        assert!(!Role::Manager.can(TransferOwnership));
+        assert!(Role::Editor.can(CreateVote));
+        assert!(Role::Editor.can(EditVote));
+        assert!(!Role::Editor.can(ManageVote));
+        assert!(!Role::Editor.can(ManageMembers));
+        assert!(Role::Voter.can(Answer));
+        assert!(!Role::Voter.can(CreateVote));
+        assert!(!Role::Observer.can(Answer));
+        assert!(!Role::Observer.can(CreateVote));
+    }
+
+    #[test]
+    fn test_assignable() {
+        assert!(Role::Editor.is_assignable());
+        assert!(Role::Observer.is_assignable());
+        assert!(!Role::Manager.is_assignable());
+        assert!(!Role::Owner.is_assignable());
+        assert_eq!(Role::try_from("Voter"), Ok(Role::Voter));
+        assert!(Role::try_from("Admin").is_err());
+    }
+}
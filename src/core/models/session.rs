@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub device: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && now < self.expires_at
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub user_id: i32,
+    // only the hash of the refresh token is stored
+    pub refresh_token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub client: Client,
+}
+
+// where a session is used from, updated on every refresh
+#[derive(Debug, Clone, Default)]
+pub struct Client {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Client {
+    // a rough name of the device for the users to tell their sessions apart
+    pub fn device(&self) -> String {
+        let ua = match &self.user_agent {
+            Some(ua) => ua.to_lowercase(),
+            None => return "Unknown".into(),
+        };
+        let device = if ua.contains("iphone") {
+            "iPhone"
+        } else if ua.contains("ipad") {
+            "iPad"
+        } else if ua.contains("android") {
+            "Android"
+        } else if ua.contains("windows") {
+            "Windows"
+        } else if ua.contains("mac os") || ua.contains("macintosh") {
+            "Mac"
+        } else if ua.contains("linux") {
+            "Linux"
+        } else {
+            "Unknown"
+        };
+        device.into()
+    }
+}
+
+// session listed to its user
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    #[serde(flatten)]
+    pub session: Session,
+    // the session of the request
+    pub current: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn client(ua: &str) -> Client {
+        Client {
+            ip: None,
+            user_agent: Some(ua.into()),
+        }
+    }
+
+    #[test]
+    fn test_device() {
+        assert_eq!(client("Mozilla/5.0 (iPhone; CPU iPhone OS 16_5 like Mac OS X)").device(), "iPhone");
+        assert_eq!(client("Mozilla/5.0 (Linux; Android 13; Pixel 7)").device(), "Android");
+        assert_eq!(client("Mozilla/5.0 (Windows NT 10.0; Win64; x64)").device(), "Windows");
+        assert_eq!(client("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7)").device(), "Mac");
+        assert_eq!(client("curl/8.1.2").device(), "Unknown");
+        assert_eq!(Client::default().device(), "Unknown");
+    }
+}
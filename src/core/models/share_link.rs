@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+fn default_rate_limit() -> i32 {
+    60
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShareLinkCreate {
+    pub expires_at: DateTime<Utc>,
+    // max guest joins and submissions accepted through the link per hour
+    #[serde(default = "default_rate_limit")]
+    pub rate_limit: i32,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ShareLink {
+    pub id: i32,
+    pub vote_id: i32,
+    pub creator: i32,
+    pub expires_at: DateTime<Utc>,
+    pub rate_limit: i32,
+    pub revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ShareLink {
+    pub fn is_valid(&self, now: DateTime<Utc>) -> bool {
+        !self.revoked && now < self.expires_at
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub vote_id: i32,
+    // only the hash of the token is stored, the token itself is handed out once
+    pub token_hash: String,
+    pub creator: i32,
+    pub expires_at: DateTime<Utc>,
+    pub rate_limit: i32,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Duration;
+
+    fn link(expires_at: DateTime<Utc>, revoked: bool) -> ShareLink {
+        ShareLink {
+            id: 1,
+            vote_id: 1,
+            creator: 1,
+            expires_at,
+            rate_limit: 60,
+            revoked,
+            created_at: expires_at - Duration::days(1),
+        }
+    }
+
+    #[test]
+    fn test_expiry() {
+        let now = Utc::now();
+        assert!(link(now + Duration::seconds(1), false).is_valid(now));
+        assert!(!link(now, false).is_valid(now));
+        assert!(!link(now - Duration::seconds(1), false).is_valid(now));
+    }
+
+    #[test]
+    fn test_revocation() {
+        let now = Utc::now();
+        assert!(!link(now + Duration::days(1), true).is_valid(now));
+    }
+}
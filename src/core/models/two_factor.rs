@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TwoFactor {
+    pub user_id: i32,
+    // base32 encoded, stored in plaintext as it is needed to compute the codes: the database must be protected
+    // like the credentials themselves, and a leaked dump requires every user to enroll again
+    pub secret: String,
+    // a secret is not used before the user has confirmed a code of it
+    pub enabled: bool,
+    // the last accepted time step, a code can not be used twice
+    pub last_counter: Option<i64>,
+}
+
+impl TwoFactor {
+    // the counter of a code must be past the last accepted one
+    pub fn accepts_counter(&self, counter: i64) -> bool {
+        self.last_counter.map(|last| counter > last).unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Enrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Challenge {
+    pub id: i32,
+    pub user_id: i32,
+    pub attempts: i32,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl Challenge {
+    pub fn is_valid(&self, now: DateTime<Utc>, max_attempts: i32) -> bool {
+        self.used_at.is_none() && now < self.expires_at && self.attempts < max_attempts
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChallengeInsert {
+    pub user_id: i32,
+    // only the hash of the challenge token is stored
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
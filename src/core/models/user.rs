@@ -12,21 +12,34 @@ use sqlx_insert::{table_name, Insertable};
 pub struct User {
     pub id: i32,
     pub nickname: String,
-    pub phone: String,
-    pub email: String,
+    // not known for the users signed up by single sign-on
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    // empty for the users signed up by single sign-on, so it never verifies
     pub password: String,
-    pub salt: String,
+    // only set for legacy SHA-256 hashes
+    pub salt: Option<String>,
     pub avatar: Option<String>,
+    pub phone_verified: bool,
+    pub email_verified: bool,
+}
+
+impl User {
+    // the username is either the phone or the email
+    pub fn is_verified(&self, username: &str) -> bool {
+        (self.phone.as_deref() == Some(username) && self.phone_verified) || (self.email.as_deref() == Some(username) && self.email_verified)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Insertable)]
 #[table_name("users")]
 pub struct UserInsertion {
     pub nickname: String,
-    pub email: String,
-    pub phone: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
     pub password: String,
-    pub salt: String,
+    // only set for legacy SHA-256 hashes
+    pub salt: Option<String>,
     pub avatar: Option<String>,
 }
 
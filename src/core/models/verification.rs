@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Channel {
+    Email,
+    Phone,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Email => "Email",
+            Channel::Phone => "Phone",
+        }
+    }
+}
+
+// whether unverified phones and emails can be used, configured by the environment
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Policy {
+    // log in only by a verified phone or email
+    pub login: bool,
+    // find users only by a verified phone
+    pub search: bool,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Verification {
+    pub id: i32,
+    pub user_id: i32,
+    // the phone or email the code has been sent to
+    pub target: String,
+    pub code_hash: String,
+    pub attempts: i32,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl Verification {
+    pub fn is_valid(&self, now: DateTime<Utc>, max_attempts: i32) -> bool {
+        self.used_at.is_none() && now < self.expires_at && self.attempts < max_attempts
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    pub user_id: i32,
+    pub channel: Channel,
+    pub target: String,
+    // only the hash of the code is stored
+    pub code_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -1,15 +1,115 @@
 use crate::core::models::question::QuestionCreate;
-use chrono::NaiveDate;
+use chrono::{Duration, NaiveDate};
 use juju_macros::ToTuple;
 use serde::{Deserialize, Serialize};
+use sqlx::{
+    decode::Decode,
+    encode::{Encode, IsNull},
+    error::BoxDynError,
+    postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef},
+    FromRow, Postgres, Type,
+};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Organization votes are accessible to the members, WhiteList votes only to the whitelisted users
+// and Public votes can also be read by any user
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum VoteVisibility {
     Public,
     Organization,
     WhiteList,
 }
 
+impl VoteVisibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VoteVisibility::Public => "Public",
+            VoteVisibility::Organization => "Organization",
+            VoteVisibility::WhiteList => "WhiteList",
+        }
+    }
+}
+
+// Draft -> Open <-> Closed -> Archived, a draft can also be archived directly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoteStatus {
+    Draft,
+    Open,
+    Closed,
+    Archived,
+}
+
+impl VoteStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VoteStatus::Draft => "Draft",
+            VoteStatus::Open => "Open",
+            VoteStatus::Closed => "Closed",
+            VoteStatus::Archived => "Archived",
+        }
+    }
+
+    pub fn can_transit_to(&self, to: VoteStatus) -> bool {
+        matches!(
+            (self, to),
+            (VoteStatus::Draft, VoteStatus::Open)
+                | (VoteStatus::Open, VoteStatus::Closed)
+                | (VoteStatus::Closed, VoteStatus::Open)
+                | (VoteStatus::Closed, VoteStatus::Archived)
+                | (VoteStatus::Draft, VoteStatus::Archived)
+        )
+    }
+
+    pub fn accepts_answers(&self) -> bool {
+        *self == VoteStatus::Open
+    }
+
+    // questions and options can only be changed before the vote is opened, once it is open the answers point to them
+    pub fn is_editable(&self) -> bool {
+        *self == VoteStatus::Draft
+    }
+}
+
+impl TryFrom<&str> for VoteStatus {
+    type Error = String;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "Draft" => Ok(VoteStatus::Draft),
+            "Open" => Ok(VoteStatus::Open),
+            "Closed" => Ok(VoteStatus::Closed),
+            "Archived" => Ok(VoteStatus::Archived),
+            _ => Err(format!("invalid vote status: {}", s)),
+        }
+    }
+}
+
+// votes.status is a VARCHAR column, so the status is (de)serialized as plain text
+impl Type<Postgres> for VoteStatus {
+    fn type_info() -> PgTypeInfo {
+        <&str as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <&str as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for VoteStatus {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for VoteStatus {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let s = <&str as Decode<Postgres>>::decode(value)?;
+        Ok(VoteStatus::try_from(s)?)
+    }
+}
+
+fn default_draft() -> bool {
+    true
+}
+
 #[derive(Debug, Deserialize)]
 pub struct VoteCreate {
     pub name: String,
@@ -17,6 +117,14 @@ pub struct VoteCreate {
     pub visibility: VoteVisibility,
     pub questions: Vec<QuestionCreate>,
     pub organization_id: i32,
+    // a vote is created as a draft unless told otherwise, its questions can only be changed until it is published
+    #[serde(default = "default_draft")]
+    pub draft: bool,
+    #[serde(default)]
+    pub grace_period: i32,
+    // secret ballot, the answers are not linked to the users
+    #[serde(default)]
+    pub anonymous: bool,
 }
 
 #[derive(Debug, Default)]
@@ -37,9 +145,11 @@ pub struct Vote {
     pub visibility: String,
     pub likes: i32,
     pub dislikes: i32,
-    pub status: String,
+    pub status: VoteStatus,
     pub has_updated: bool,
     pub num_of_questions: i64,
+    pub grace_period: i32,
+    pub anonymous: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -48,6 +158,41 @@ pub struct Insert {
     pub deadline: Option<NaiveDate>,
     pub organization_id: i32,
     pub visibility: String,
+    pub status: VoteStatus,
+    pub grace_period: i32,
+    pub anonymous: bool,
+}
+
+// everything needed to decide whether an answer can still be submitted
+#[derive(Debug, Clone)]
+pub struct SubmissionWindow {
+    pub status: VoteStatus,
+    pub deadline: Option<NaiveDate>,
+    // days after the deadline in which answers are still accepted but flagged as late
+    pub grace_period: i32,
+}
+
+pub enum Submission {
+    OnTime,
+    Late,
+    Rejected,
+}
+
+impl SubmissionWindow {
+    pub fn check(&self, today: NaiveDate) -> Submission {
+        if !self.status.accepts_answers() {
+            return Submission::Rejected;
+        }
+        match self.deadline {
+            None => Submission::OnTime,
+            Some(deadline) if today <= deadline => Submission::OnTime,
+            // a deadline too close to the end of the calendar to add the grace period to is never passed
+            Some(deadline) => match deadline.checked_add_signed(Duration::days(self.grace_period as i64)) {
+                Some(end) if today > end => Submission::Rejected,
+                _ => Submission::Late,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +215,13 @@ pub struct ReadMarkInsert {
     pub version: i64,
 }
 
+#[derive(Debug, Serialize, FromRow)]
+pub struct WhiteListed {
+    pub user_id: i32,
+    pub nickname: String,
+    pub avatar: Option<String>,
+}
+
 #[derive(Debug, ToTuple)]
 pub struct FavoriteVote {
     pub user_id: i32,
@@ -83,3 +235,47 @@ pub struct FavoriteVoteQuery {
     pub vote_id_eq: Option<i32>,
     pub attitude_eq: Option<i32>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn window(deadline: NaiveDate, grace_period: i32) -> SubmissionWindow {
+        SubmissionWindow {
+            status: VoteStatus::Open,
+            deadline: Some(deadline),
+            grace_period,
+        }
+    }
+
+    #[test]
+    fn test_submission_window() {
+        let deadline = NaiveDate::from_ymd_opt(2023, 9, 1).unwrap();
+        assert!(matches!(window(deadline, 2).check(deadline), Submission::OnTime));
+        assert!(matches!(window(deadline, 2).check(deadline + Duration::days(2)), Submission::Late));
+        assert!(matches!(window(deadline, 2).check(deadline + Duration::days(3)), Submission::Rejected));
+        assert!(matches!(window(NaiveDate::MAX, 365).check(NaiveDate::MAX), Submission::OnTime));
+        assert!(matches!(window(NaiveDate::MAX - Duration::days(1), 365).check(NaiveDate::MAX), Submission::Late));
+    }
+
+    #[test]
+    fn test_created_as_draft() {
+        let create: VoteCreate = serde_json::from_str(r#"{"name": "v", "deadline": null, "visibility": "Organization", "questions": [], "organization_id": 1}"#).unwrap();
+        assert!(create.draft);
+        let create: VoteCreate = serde_json::from_str(r#"{"name": "v", "deadline": null, "visibility": "Organization", "questions": [], "organization_id": 1, "draft": false}"#).unwrap();
+        assert!(!create.draft);
+    }
+
+    #[test]
+    fn test_editable_after_transition() {
+        let status = VoteStatus::Draft;
+        assert!(status.is_editable());
+        assert!(status.can_transit_to(VoteStatus::Open));
+        assert!(!VoteStatus::Open.is_editable());
+        assert!(VoteStatus::Open.can_transit_to(VoteStatus::Closed));
+        assert!(!VoteStatus::Closed.is_editable());
+        assert!(VoteStatus::Closed.can_transit_to(VoteStatus::Open));
+        assert!(!VoteStatus::Archived.is_editable());
+        assert!(!VoteStatus::Open.can_transit_to(VoteStatus::Draft));
+    }
+}
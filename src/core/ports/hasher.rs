@@ -0,0 +1,10 @@
+use crate::error::Error;
+
+// hashing is slow on purpose, the implementations must not block the async workers with it
+pub trait PasswordHasher {
+    async fn hash(&self, password: &str) -> Result<String, Error>;
+    // the salt is only used by hashes made before PHC strings were stored
+    async fn verify(&self, password: &str, hash: &str, salt: Option<&str>) -> Result<bool, Error>;
+    // whether the hash should be replaced by a fresh one after a successful login
+    fn needs_rehash(&self, hash: &str) -> bool;
+}
@@ -0,0 +1,19 @@
+use crate::error::Error;
+
+// the user as told by the identity provider, `subject` only identifies it within the issuer
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+// an OpenID Connect provider used with the authorization code flow
+pub trait IdentityProvider {
+    // where the browser is sent to sign in
+    fn authorization_url(&self, state: &str, nonce: &str, code_challenge: &str) -> String;
+    // the code is redeemed with the PKCE verifier, the ID token must carry the nonce of the login
+    async fn exchange(&self, code: &str, code_verifier: &str, nonce: &str) -> Result<Identity, Error>;
+}
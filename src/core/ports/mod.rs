@@ -1,2 +1,5 @@
+pub mod hasher;
+pub mod identity;
+pub mod notifier;
 pub mod repository;
 pub mod tokener;
@@ -0,0 +1,15 @@
+use crate::core::models::verification::Channel;
+use crate::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub channel: Channel,
+    // an email address or a phone number
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Notifier {
+    async fn notify(&self, notification: Notification) -> Result<(), Error>;
+}
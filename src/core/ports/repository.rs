@@ -1,15 +1,28 @@
 use crate::core::models::{
-    answer::{Insert as AnswerInsert, Query as AnswerQuery},
+    answer::{DateCount, Insert as AnswerInsert, Query as AnswerQuery, Source, TextAnswer, ValueInsert},
+    application::{ApplicationStatus, JoinApplication, Query as ApplicationQuery},
     common::Pagination,
+    group::{GroupMember, MemberGroup},
+    identity::{OidcLogin, OidcLoginInsert},
+    invite_code::{Insert as InviteCodeInsert, InviteCode},
     option::{Insert as OptionInsert, Opt, Query as OptionQuery},
     organization::{Insert as OrganizationInsert, Organization, OrganizationWithVoteInfo, Query as OrganizationQuery, Update as OrganizationUpdate},
+    password_reset::{Insert as PasswordResetInsert, PasswordReset},
+    personal_token::{Insert as PersonalTokenInsert, PersonalToken},
     question::{
-        FavoriteQuestion, FavoriteQuestionQuery, Insert as QuestionInsert, Query as QuestionQuery, Question, ReadMarkInsert as QuestionReadMarkInsert, ReadMarkUpdate as QuestionReadMarkUpdate,
+        FavoriteQuestion, FavoriteQuestionQuery, Insert as QuestionInsert, NumberRange, Query as QuestionQuery, Question, QuestionType, ReadMarkInsert as QuestionReadMarkInsert,
+        ReadMarkUpdate as QuestionReadMarkUpdate, SelectionConstraint,
     },
-    user::{Patch as UserPatch, User},
-    vote::{FavoriteVote, FavoriteVoteQuery, Insert as VoteInsert, Query as VoteQuery, ReadMarkInsert as VoteReadMarkInsert, Vote},
+    role::Role,
+    session::{Client, Insert as SessionInsert, Session},
+    share_link::{Insert as ShareLinkInsert, ShareLink},
+    two_factor::{Challenge, ChallengeInsert, TwoFactor},
+    user::{Patch as UserPatch, User, UserInsertion},
+    verification::{Channel, Insert as VerificationInsert, Verification},
+    vote::{FavoriteVote, FavoriteVoteQuery, Insert as VoteInsert, Query as VoteQuery, ReadMarkInsert as VoteReadMarkInsert, SubmissionWindow, Vote, VoteStatus, WhiteListed},
 };
 use crate::error::Error;
+use chrono::{DateTime, NaiveDate, Utc};
 use std::future::Future;
 use std::pin::Pin;
 
@@ -18,13 +31,37 @@ pub trait VoteCommon {
     async fn query(&mut self, query: &VoteQuery, pagination: Option<Pagination>) -> Result<Vec<Vote>, Error>;
     async fn count(&mut self, query: &VoteQuery) -> Result<i64, Error>;
     async fn get(&mut self, uid: i32, id: i32) -> Result<Vote, Error>;
+    // the vote as seen by a guest, who has no read marks
+    async fn get_for_guest(&mut self, id: i32) -> Result<Vote, Error>;
     async fn update_read_mark_version(&mut self, uid: i32, id: i32, version: i64) -> Result<(), Error>;
     async fn insert_favorite(&mut self, favorite: FavoriteVote) -> Result<(), Error>;
     async fn exists_favorite(&mut self, query: FavoriteVoteQuery) -> Result<bool, Error>;
+    async fn get_organization_id(&mut self, id: i32) -> Result<i32, Error>;
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error>;
+    async fn get_status(&mut self, id: i32) -> Result<VoteStatus, Error>;
+    async fn get_status_for_update(&mut self, id: i32) -> Result<VoteStatus, Error>;
+    async fn update_status(&mut self, id: i32, status: VoteStatus) -> Result<(), Error>;
+    async fn get_submission_window(&mut self, id: i32) -> Result<SubmissionWindow, Error>;
+    async fn update_grace_period(&mut self, id: i32, grace_period: i32) -> Result<(), Error>;
+    async fn is_anonymous(&mut self, id: i32) -> Result<bool, Error>;
+    async fn update_visibility(&mut self, id: i32, visibility: String) -> Result<(), Error>;
+    async fn update(&mut self, id: i32, name: String, deadline: Option<NaiveDate>) -> Result<i32, Error>;
+    async fn delete(&mut self, id: i32) -> Result<i32, Error>;
+}
+
+pub trait VoteWhiteListCommon {
+    async fn insert(&mut self, vote_id: i32, uids: Vec<i32>) -> Result<(), Error>;
+    async fn delete(&mut self, vote_id: i32, uid: i32) -> Result<i32, Error>;
+    async fn query(&mut self, vote_id: i32, pagination: Option<Pagination>) -> Result<Vec<WhiteListed>, Error>;
+    async fn count(&mut self, vote_id: i32) -> Result<i64, Error>;
+    // only the whitelists of the votes with the WhiteList visibility are in effect
+    async fn is_whitelisted(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error>;
 }
 
 pub trait VoteReadMarkCommon {
     async fn insert(&mut self, mark: VoteReadMarkInsert) -> Result<i32, Error>;
+    async fn insert_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error>;
+    async fn delete_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error>;
 }
 
 pub trait OrganizationCommon {
@@ -34,14 +71,27 @@ pub trait OrganizationCommon {
     async fn count(&mut self, param: OrganizationQuery) -> Result<i64, Error>;
     async fn delete(&mut self, id: i32) -> Result<(), Error>;
     async fn exists(&mut self, name: &str) -> Result<bool, Error>;
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error>;
     async fn add_member(&mut self, id: i32, uid: i32) -> Result<(), Error>;
     async fn add_user_version(&mut self, id: i32, uid: i32) -> Result<(), Error>;
     async fn update_user_version(&mut self, id: i32, uid: i32, version: i32) -> Result<(), Error>;
     async fn add_manager(&mut self, id: i32, uid: i32) -> Result<(), Error>;
+    // the manager role and the read mark of the organization go along with the membership
+    async fn remove_member(&mut self, id: i32, uid: i32) -> Result<(), Error>;
+    async fn remove_manager(&mut self, id: i32, uid: i32) -> Result<(), Error>;
+    async fn count_managers(&mut self, id: i32) -> Result<i64, Error>;
+    async fn set_owner(&mut self, id: i32, uid: i32) -> Result<(), Error>;
     async fn get(&mut self, id: i32) -> Result<Organization, Error>;
     async fn get_for_update(&mut self, id: i32) -> Result<Organization, Error>;
     async fn is_member(&mut self, id: i32, uid: i32) -> Result<bool, Error>;
     async fn is_manager(&mut self, id: i32, uid: i32) -> Result<bool, Error>;
+    // none for the users out of the organization
+    async fn role(&mut self, id: i32, uid: i32) -> Result<Option<Role>, Error>;
+    async fn set_role(&mut self, id: i32, uid: i32, role: Role) -> Result<(), Error>;
+    async fn set_manager_two_factor_required(&mut self, id: i32, required: bool) -> Result<(), Error>;
+    async fn count_managers_without_two_factor(&mut self, id: i32) -> Result<i64, Error>;
+    // whether the user manages any organization requiring two-factor authentication
+    async fn manages_two_factor_required(&mut self, uid: i32) -> Result<bool, Error>;
 }
 
 pub trait QuestionCommon {
@@ -49,8 +99,17 @@ pub trait QuestionCommon {
     async fn query(&mut self, uid: i32, query: QuestionQuery, pagination: Option<Pagination>) -> Result<Vec<Question>, Error>;
     async fn count(&mut self, query: QuestionQuery) -> Result<i64, Error>;
     async fn get(&mut self, uid: i32, id: i32) -> Result<Question, Error>;
+    // the questions of the vote along with the answers of the guest
+    async fn query_for_guest(&mut self, guest_id: i32, vote_id: i32) -> Result<Vec<Question>, Error>;
     async fn delete(&mut self, id: i32) -> Result<(), Error>;
     async fn get_organization_id(&mut self, question_id: i32) -> Result<i32, Error>;
+    async fn get_vote_id(&mut self, question_id: i32) -> Result<i32, Error>;
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error>;
+    async fn get_selection_constraint(&mut self, question_id: i32) -> Result<SelectionConstraint, Error>;
+    async fn get_type(&mut self, question_id: i32) -> Result<QuestionType, Error>;
+    async fn get_number_range(&mut self, question_id: i32) -> Result<NumberRange, Error>;
+    async fn required_ids(&mut self, vote_id: i32) -> Result<Vec<i32>, Error>;
+    async fn update_tally_method(&mut self, id: i32, method: String) -> Result<(), Error>;
     async fn is_owner(&mut self, uid: i32, id: i32) -> Result<bool, Error>;
     async fn is_belongs_to_vote(&mut self, vote_id: i32, ids: Vec<i32>) -> Result<bool, Error>;
     async fn insert_favorite(&mut self, favorite: FavoriteQuestion) -> Result<(), Error>;
@@ -60,12 +119,20 @@ pub trait QuestionCommon {
 pub trait QuestionReadMarkCommon {
     async fn insert(&mut self, mark: QuestionReadMarkInsert) -> Result<i32, Error>;
     async fn update(&mut self, update: QuestionReadMarkUpdate) -> Result<(), Error>;
+    async fn insert_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error>;
+    async fn delete_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error>;
 }
 
 pub trait UserCommon {
+    async fn insert(&mut self, user: UserInsertion) -> Result<i32, Error>;
     async fn get_by_phone(&mut self, phone: String) -> Result<Option<User>, Error>;
+    // the username is either the phone or the email
+    async fn get_by_username(&mut self, username: String) -> Result<Option<User>, Error>;
     async fn get(&mut self, id: i32) -> Result<User, Error>;
     async fn patch(&mut self, id: i32, user: UserPatch) -> Result<(), Error>;
+    // replace the hash of the same password, the sessions are kept
+    async fn rehash_password(&mut self, id: i32, hash: String) -> Result<(), Error>;
+    async fn set_verified(&mut self, id: i32, channel: Channel) -> Result<(), Error>;
 }
 
 pub trait OptionCommon {
@@ -74,15 +141,187 @@ pub trait OptionCommon {
     async fn count(&mut self, query: OptionQuery) -> Result<i64, Error>;
     async fn count_question(&mut self, query: OptionQuery) -> Result<i64, Error>;
     async fn is_belongs_to_question(&mut self, question_id: i32, ids: Vec<i32>) -> Result<bool, Error>;
+    // none when there is no such option
+    async fn get_question_id(&mut self, id: i32) -> Result<Option<i32>, Error>;
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error>;
 }
 
 pub trait AnswerCommon {
     async fn insert(&mut self, answer: AnswerInsert) -> Result<i32, Error>;
     async fn bulk_insert(&mut self, answers: Vec<AnswerInsert>) -> Result<(), Error>;
     async fn delete(&mut self, query: AnswerQuery) -> Result<i32, Error>;
+    async fn delete_values(&mut self, query: AnswerQuery) -> Result<i32, Error>;
+    async fn ranked_ballots(&mut self, question_id: i32, source: Source) -> Result<Vec<Vec<i32>>, Error>;
+    // an user has at most one value answer per question, a new one replaces the old one
+    async fn upsert_value(&mut self, answer: ValueInsert) -> Result<(), Error>;
+    async fn count_values(&mut self, question_id: i32, source: Source) -> Result<i64, Error>;
+    async fn texts(&mut self, question_id: i32, source: Source, pagination: Option<Pagination>) -> Result<Vec<TextAnswer>, Error>;
+    async fn numbers(&mut self, question_id: i32, source: Source) -> Result<Vec<f64>, Error>;
+    async fn dates(&mut self, question_id: i32, source: Source) -> Result<Vec<DateCount>, Error>;
+    // (option id, rating) of the rated rows
+    async fn matrix_ratings(&mut self, question_id: i32, source: Source) -> Result<Vec<(i32, i32)>, Error>;
+    // the votes of the organization the user has answered
+    async fn answered_votes(&mut self, organization_id: i32, uid: i32) -> Result<Vec<i32>, Error>;
+    async fn move_to_ballot(&mut self, vote_id: i32, uid: i32, ballot: String) -> Result<(), Error>;
+    // the available dates told by the user in the date votes of the organization
+    async fn delete_dates(&mut self, organization_id: i32, uid: i32) -> Result<(), Error>;
+}
+
+pub trait ApplicationCommon {
+    async fn insert(&mut self, uid: i32, organization_id: i32) -> Result<i32, Error>;
+    async fn get(&mut self, id: i32) -> Result<JoinApplication, Error>;
+    async fn get_for_update(&mut self, id: i32) -> Result<JoinApplication, Error>;
+    async fn query(&mut self, query: &ApplicationQuery, pagination: Option<Pagination>) -> Result<Vec<JoinApplication>, Error>;
+    async fn count(&mut self, query: &ApplicationQuery) -> Result<i64, Error>;
+    async fn update_status(&mut self, id: i32, status: ApplicationStatus, reason: Option<String>) -> Result<(), Error>;
+}
+
+// participations and ballots of anonymous votes are kept in separate tables, nothing links them
+pub trait BallotCommon {
+    // false if the user has already participated in the vote
+    async fn insert_participation(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error>;
+    async fn has_participated(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error>;
+    async fn insert_ballot(&mut self, vote_id: i32, token_hash: String) -> Result<(), Error>;
+    async fn exists_ballot(&mut self, vote_id: i32, token_hash: String) -> Result<bool, Error>;
+}
+
+pub trait ShareLinkCommon {
+    async fn insert(&mut self, link: ShareLinkInsert) -> Result<i32, Error>;
+    async fn get(&mut self, id: i32) -> Result<Option<ShareLink>, Error>;
+    async fn get_by_token_hash(&mut self, token_hash: String) -> Result<Option<ShareLink>, Error>;
+    // the lock serializes the joins and the submissions of the link, so the rate limit is not exceeded concurrently
+    async fn get_for_update(&mut self, id: i32) -> Result<Option<ShareLink>, Error>;
+    async fn get_by_token_hash_for_update(&mut self, token_hash: String) -> Result<Option<ShareLink>, Error>;
+    async fn query(&mut self, vote_id: i32) -> Result<Vec<ShareLink>, Error>;
+    async fn revoke(&mut self, vote_id: i32, id: i32) -> Result<i32, Error>;
+    async fn insert_guest(&mut self, share_link_id: i32) -> Result<i32, Error>;
+    // guests of the link joined in the last hour
+    async fn count_recent_guests(&mut self, share_link_id: i32) -> Result<i64, Error>;
+    // guests of the link submitted in the last hour, except the given one
+    async fn count_recent_submissions(&mut self, share_link_id: i32, guest_id: i32) -> Result<i64, Error>;
+    async fn update_guest_submitted_at(&mut self, guest_id: i32) -> Result<(), Error>;
+}
+
+pub trait InviteCodeCommon {
+    async fn insert(&mut self, code: InviteCodeInsert) -> Result<i32, Error>;
+    async fn get(&mut self, id: i32) -> Result<Option<InviteCode>, Error>;
+    // counts an use of the code, nothing is returned when the code is not valid anymore
+    async fn redeem(&mut self, code: String) -> Result<Option<InviteCode>, Error>;
+    // the codes of the user not bound to any organization
+    async fn query_by_issuer(&mut self, uid: i32) -> Result<Vec<InviteCode>, Error>;
+    async fn query_by_organization(&mut self, org_id: i32) -> Result<Vec<InviteCode>, Error>;
+    async fn revoke(&mut self, id: i32) -> Result<(), Error>;
+}
+
+pub trait PasswordResetCommon {
+    async fn insert(&mut self, reset: PasswordResetInsert) -> Result<i32, Error>;
+    async fn get_by_token_hash(&mut self, hash: String) -> Result<Option<PasswordReset>, Error>;
+    // the tokens not used yet are invalidated once a new one is issued or the password is reset
+    async fn invalidate(&mut self, uid: i32) -> Result<(), Error>;
+}
+
+pub trait VerificationCommon {
+    async fn insert(&mut self, verification: VerificationInsert) -> Result<i32, Error>;
+    // the latest code sent to the user by the channel
+    async fn latest(&mut self, uid: i32, channel: Channel) -> Result<Option<Verification>, Error>;
+    async fn increase_attempts(&mut self, id: i32) -> Result<(), Error>;
+    async fn mark_used(&mut self, id: i32) -> Result<(), Error>;
 }
 
-pub trait Common: VoteCommon + OrganizationCommon + UserCommon + QuestionCommon + OptionCommon + VoteReadMarkCommon + QuestionReadMarkCommon + AnswerCommon {}
+pub trait TwoFactorCommon {
+    async fn get(&mut self, uid: i32) -> Result<Option<TwoFactor>, Error>;
+    // a new secret replaces the one not confirmed yet
+    async fn upsert_secret(&mut self, uid: i32, secret: String) -> Result<(), Error>;
+    async fn enable(&mut self, uid: i32) -> Result<(), Error>;
+    async fn delete(&mut self, uid: i32) -> Result<(), Error>;
+    // false when a code of the counter or a later one has already been accepted
+    async fn advance_last_counter(&mut self, uid: i32, counter: i64) -> Result<bool, Error>;
+    async fn replace_recovery_codes(&mut self, uid: i32, hashes: Vec<String>) -> Result<(), Error>;
+    // false when there is no such code or it has been used
+    async fn use_recovery_code(&mut self, uid: i32, hash: String) -> Result<bool, Error>;
+    async fn insert_challenge(&mut self, challenge: ChallengeInsert) -> Result<i32, Error>;
+    async fn get_challenge_by_token_hash(&mut self, hash: String) -> Result<Option<Challenge>, Error>;
+    async fn increase_challenge_attempts(&mut self, id: i32) -> Result<(), Error>;
+    async fn mark_challenge_used(&mut self, id: i32) -> Result<(), Error>;
+}
+
+pub trait PersonalTokenCommon {
+    async fn insert(&mut self, token: PersonalTokenInsert) -> Result<i32, Error>;
+    async fn get(&mut self, id: i32) -> Result<PersonalToken, Error>;
+    async fn get_by_token_hash(&mut self, hash: String) -> Result<Option<PersonalToken>, Error>;
+    // the tokens of the user not revoked yet
+    async fn query(&mut self, uid: i32) -> Result<Vec<PersonalToken>, Error>;
+    async fn revoke(&mut self, uid: i32, id: i32) -> Result<i32, Error>;
+    async fn touch(&mut self, id: i32) -> Result<(), Error>;
+}
+
+pub trait IdentityCommon {
+    async fn insert_login(&mut self, login: OidcLoginInsert) -> Result<i32, Error>;
+    // the login is deleted, so its state can only be used once
+    async fn take_login(&mut self, state_hash: String) -> Result<Option<OidcLogin>, Error>;
+    async fn get_user_id(&mut self, issuer: String, subject: String) -> Result<Option<i32>, Error>;
+    async fn link(&mut self, uid: i32, issuer: String, subject: String) -> Result<(), Error>;
+}
+
+pub trait SessionCommon {
+    async fn insert(&mut self, session: SessionInsert) -> Result<i32, Error>;
+    async fn get_by_refresh_token_hash(&mut self, hash: String) -> Result<Option<Session>, Error>;
+    // the session whose refresh token was rotated away from the given one
+    async fn get_by_previous_token_hash(&mut self, hash: String) -> Result<Option<Session>, Error>;
+    async fn rotate(&mut self, id: i32, hash: String, expires_at: DateTime<Utc>, client: Client) -> Result<(), Error>;
+    async fn is_active(&mut self, id: i32) -> Result<bool, Error>;
+    // active sessions of the user
+    async fn query(&mut self, uid: i32) -> Result<Vec<Session>, Error>;
+    async fn revoke(&mut self, id: i32) -> Result<(), Error>;
+    async fn revoke_of_user(&mut self, uid: i32, id: i32) -> Result<i32, Error>;
+    async fn revoke_all(&mut self, uid: i32) -> Result<(), Error>;
+    async fn revoke_others(&mut self, uid: i32, id: i32) -> Result<i32, Error>;
+}
+
+pub trait MemberGroupCommon {
+    async fn insert(&mut self, organization_id: i32, name: String) -> Result<i32, Error>;
+    async fn get(&mut self, id: i32) -> Result<Option<MemberGroup>, Error>;
+    async fn query(&mut self, organization_id: i32) -> Result<Vec<MemberGroup>, Error>;
+    async fn exists_name(&mut self, organization_id: i32, name: &str) -> Result<bool, Error>;
+    async fn rename(&mut self, id: i32, name: String) -> Result<(), Error>;
+    async fn delete(&mut self, id: i32) -> Result<(), Error>;
+    // only the members of the organization of the group are added
+    async fn insert_members(&mut self, id: i32, uids: Vec<i32>) -> Result<(), Error>;
+    async fn delete_member(&mut self, id: i32, uid: i32) -> Result<i32, Error>;
+    async fn members(&mut self, id: i32, pagination: Option<Pagination>) -> Result<Vec<GroupMember>, Error>;
+    async fn count_members(&mut self, id: i32) -> Result<i64, Error>;
+    // removes the user from all the groups of the organization
+    async fn delete_member_of_organization(&mut self, organization_id: i32, uid: i32) -> Result<(), Error>;
+    // replaces the groups the vote is targeted at
+    async fn set_vote_groups(&mut self, vote_id: i32, ids: Vec<i32>) -> Result<(), Error>;
+    async fn vote_groups(&mut self, vote_id: i32) -> Result<Vec<MemberGroup>, Error>;
+    async fn is_in_audience(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error>;
+}
+
+// the grouping rules of the authorizer, which follow the roles of the members
+pub trait Common:
+    VoteCommon
+    + OrganizationCommon
+    + UserCommon
+    + QuestionCommon
+    + OptionCommon
+    + VoteReadMarkCommon
+    + QuestionReadMarkCommon
+    + AnswerCommon
+    + ApplicationCommon
+    + BallotCommon
+    + VoteWhiteListCommon
+    + ShareLinkCommon
+    + SessionCommon
+    + PasswordResetCommon
+    + VerificationCommon
+    + InviteCodeCommon
+    + TwoFactorCommon
+    + PersonalTokenCommon
+    + IdentityCommon
+    + MemberGroupCommon
+{
+}
 
 pub trait DB: Common {
     type Manager: 'static;
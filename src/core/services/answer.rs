@@ -1,12 +1,193 @@
-use crate::core::models::answer::{BulkSubmit as AnswerBulkSubmit, Insert as AnswerInsert, Query as AnswerQuery, Submit as AnswerSubmit};
-use crate::core::ports::repository::{AnswerCommon, OptionCommon, QuestionCommon, TxStore};
+use crate::core::models::answer::{
+    BulkSubmit as AnswerBulkSubmit, DateCount, Insert as AnswerInsert, Query as AnswerQuery, Respondent, Source, Submit as AnswerSubmit, TextAnswer, Value as AnswerValue, ValueInsert,
+};
+use crate::core::models::common::Pagination;
+use crate::core::models::option::Query as OptionQuery;
+use crate::core::models::question::{QuestionType, SelectionConstraint};
+use crate::core::models::role::Permission;
+use crate::core::ports::repository::{AnswerCommon, BallotCommon, OptionCommon, QuestionCommon, Store, TxStore, VoteCommon};
+use crate::core::services::permission::{authorize_answer, authorize_question};
+use crate::core::services::vote::check_submission;
+use crate::core::tally::numeric::{summarize, Summary};
+use crate::core::tally::rating::{self, net_promoter_score};
 use crate::error::Error;
+use hex::ToHex;
+use itertools::Itertools;
+use rand::{thread_rng, Rng};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
-pub async fn submit<S>(store: &mut S, user_id: i32, submit: AnswerSubmit) -> Result<(), Error>
+const HISTOGRAM_BUCKETS: usize = 10;
+
+// returns the type of the answered question
+pub async fn check_selections<S>(store: &mut S, submit: &AnswerSubmit) -> Result<QuestionType, Error>
+where
+    S: Store,
+{
+    let type_ = QuestionCommon::get_type(store, submit.question_id).await?;
+    if !type_.is_option_based() {
+        check_value(store, &type_, submit).await?;
+        return Ok(type_);
+    }
+    let constraint = QuestionCommon::get_selection_constraint(store, submit.question_id).await?;
+    check_options(&type_, &constraint, submit)?;
+    Ok(type_)
+}
+
+// the options chosen, or the rows rated in a matrix question, are checked against the constraint of the question
+fn check_options(type_: &QuestionType, constraint: &SelectionConstraint, submit: &AnswerSubmit) -> Result<(), Error> {
+    if submit.value.is_some() {
+        return Err(Error::BusinessError(format!("question only accepts options(id: {})", submit.question_id)));
+    }
+    if let QuestionType::Matrix = type_ {
+        if !submit.option_ids.is_empty() {
+            return Err(Error::BusinessError(format!("matrix question only accepts ratings(id: {})", submit.question_id)));
+        }
+        if let Some(r) = submit.ratings.iter().find(|r| !type_.rating_scale().map_or(false, |scale| scale.contains(&r.rating))) {
+            return Err(Error::BusinessError(format!("rating out of scale(question id: {}, rating: {})", submit.question_id, r.rating)));
+        }
+    } else if !submit.ratings.is_empty() {
+        return Err(Error::BusinessError(format!("question does not accept ratings(id: {})", submit.question_id)));
+    }
+    let option_ids = submit.selected_option_ids();
+    let selections = option_ids.iter().unique().count();
+    if matches!(type_, QuestionType::Ranked | QuestionType::Matrix) && selections != option_ids.len() {
+        return Err(Error::BusinessError(format!("an option can be ranked or rated only once(question id: {})", submit.question_id)));
+    }
+    if !constraint.accepts(selections) {
+        return Err(Error::BusinessError(format!(
+            "invalid number of selections for question(id: {}, selected: {}, min: {}, max: {:?})",
+            submit.question_id, selections, constraint.min_selections, constraint.max_selections
+        )));
+    }
+    Ok(())
+}
+
+async fn check_value<S>(store: &mut S, type_: &QuestionType, submit: &AnswerSubmit) -> Result<(), Error>
+where
+    S: Store,
+{
+    if !submit.option_ids.is_empty() || !submit.ratings.is_empty() {
+        return Err(Error::BusinessError(format!("question has no options(id: {})", submit.question_id)));
+    }
+    let valid = match (type_, &submit.value) {
+        (QuestionType::Text, Some(AnswerValue::Text(text))) => !text.trim().is_empty(),
+        (QuestionType::Number, Some(AnswerValue::Number(number))) => QuestionCommon::get_number_range(store, submit.question_id).await?.accepts(*number),
+        (QuestionType::Date, Some(AnswerValue::Date(_))) => true,
+        (QuestionType::Likert | QuestionType::Nps, Some(AnswerValue::Number(number))) => number.fract() == 0.0 && type_.rating_scale().map_or(false, |scale| scale.contains(&(*number as i32))),
+        _ => false,
+    };
+    if !valid {
+        return Err(Error::BusinessError(format!(
+            "invalid answer for {} question(id: {})",
+            type_.as_str().to_lowercase(),
+            submit.question_id
+        )));
+    }
+    Ok(())
+}
+
+fn hash_ballot_token(token: &str) -> String {
+    Sha256::digest(token).encode_hex()
+}
+
+// Decides who the answers are stored for. The first submission to an anonymous vote records the participation
+// and issues a ballot token, which is returned to the client and must be presented to change the answers later.
+// Only the hash of the token is stored and the participation is kept apart from the ballot. The observers of
+// the organization and the members out of the groups the vote is targeted at are not allowed to answer, unless
+// they are whitelisted.
+pub async fn respondent<S>(store: &mut S, vote_id: i32, uid: i32, ballot_token: Option<String>) -> Result<(Respondent, Option<String>), Error>
+where
+    S: Store,
+{
+    authorize_answer(store, vote_id, uid).await?;
+    if !VoteCommon::is_anonymous(store, vote_id).await? {
+        return Ok((Respondent::User(uid), None));
+    }
+    if let Some(token) = ballot_token {
+        let hash = hash_ballot_token(&token);
+        if !BallotCommon::has_participated(store, vote_id, uid).await? || !BallotCommon::exists_ballot(store, vote_id, hash.clone()).await? {
+            return Err(Error::BusinessError("invalid ballot token".into()));
+        }
+        return Ok((Respondent::Ballot(hash), None));
+    }
+    if !BallotCommon::insert_participation(store, vote_id, uid).await? {
+        return Err(Error::BusinessError("already voted, the ballot token is required to change the answers".into()));
+    }
+    let token: String = thread_rng().gen::<[u8; 32]>().encode_hex();
+    let hash = hash_ballot_token(&token);
+    BallotCommon::insert_ballot(store, vote_id, hash.clone()).await?;
+    Ok((Respondent::Ballot(hash), Some(token)))
+}
+
+// value answers are stored apart from the option answers
+pub fn value_insert(respondent: &Respondent, submit: &AnswerSubmit, late: bool) -> Option<ValueInsert> {
+    submit.value.clone().map(|value| ValueInsert {
+        question_id: submit.question_id,
+        respondent: respondent.clone(),
+        value,
+        late,
+    })
+}
+
+pub fn answer_inserts(respondent: &Respondent, submit: AnswerSubmit, type_: &QuestionType, late: bool) -> Vec<AnswerInsert> {
+    if let QuestionType::Matrix = type_ {
+        return submit
+            .ratings
+            .into_iter()
+            .unique_by(|r| r.option_id)
+            .map(|r| AnswerInsert {
+                respondent: respondent.clone(),
+                option_id: r.option_id,
+                late,
+                position: None,
+                rating: Some(r.rating),
+            })
+            .collect();
+    }
+    submit
+        .option_ids
+        .into_iter()
+        .unique()
+        .enumerate()
+        .map(|(i, option_id)| AnswerInsert {
+            respondent: respondent.clone(),
+            option_id,
+            late,
+            position: match type_ {
+                QuestionType::Ranked => Some(i as i32 + 1),
+                _ => None,
+            },
+            rating: None,
+        })
+        .collect()
+}
+
+pub async fn check_required<S>(store: &mut S, vote_id: i32, submissions: &[AnswerSubmit]) -> Result<(), Error>
+where
+    S: Store,
+{
+    let required_ids = QuestionCommon::required_ids(store, vote_id).await?;
+    if let Some(missing) = required_ids.into_iter().find(|id| !submissions.iter().any(|s| s.question_id == *id)) {
+        return Err(Error::BusinessError(format!("required question is not answered(id: {})", missing)));
+    }
+    Ok(())
+}
+
+// returns the ballot token issued by the first submission to an anonymous vote
+pub async fn submit<S>(store: &mut S, user_id: i32, submit: AnswerSubmit, ballot_token: Option<String>) -> Result<Option<String>, Error>
 where
     S: TxStore,
 {
-    let is_valid = OptionCommon::is_belongs_to_question(store, submit.question_id, submit.option_ids.clone()).await?;
+    let vote_id = QuestionCommon::get_vote_id(store, submit.question_id).await?;
+    let late = check_submission(store, vote_id).await?;
+    let type_ = check_selections(store, &submit).await?;
+    let (respondent, issued) = respondent(store, vote_id, user_id, ballot_token).await?;
+    if let Some(value) = value_insert(&respondent, &submit, late) {
+        AnswerCommon::upsert_value(store, value).await?;
+        return Ok(issued);
+    }
+    let is_valid = OptionCommon::is_belongs_to_question(store, submit.question_id, submit.selected_option_ids()).await?;
     if !is_valid {
         return Err(Error::BusinessError("options not belongs to exactly one question".into()));
     }
@@ -14,15 +195,17 @@ where
         store,
         AnswerQuery {
             question_id_eq: Some(submit.question_id),
-            user_id_eq: Some(user_id),
+            user_id_eq: respondent.user_id(),
+            ballot_eq: respondent.ballot(),
+            guest_id_eq: respondent.guest_id(),
         },
     )
     .await?;
-    AnswerCommon::bulk_insert(store, submit.option_ids.into_iter().map(|oid| AnswerInsert { user_id: user_id, option_id: oid }).collect()).await?;
-    Ok(())
+    AnswerCommon::bulk_insert(store, answer_inserts(&respondent, submit, &type_, late)).await?;
+    Ok(issued)
 }
 
-pub async fn bulk_submit<S>(mut store: S, user_id: i32, bulk_submit: AnswerBulkSubmit) -> Result<(), Error>
+pub async fn bulk_submit<S>(mut store: S, user_id: i32, bulk_submit: AnswerBulkSubmit) -> Result<Option<String>, Error>
 where
     S: TxStore,
 {
@@ -30,9 +213,152 @@ where
     if !QuestionCommon::is_belongs_to_vote(&mut store, bulk_submit.vote_id, question_ids).await? {
         return Err(Error::BusinessError("questions not belongs to exactly one vote".into()));
     }
+    check_required(&mut store, bulk_submit.vote_id, &bulk_submit.submissions).await?;
+    let mut ballot_token = bulk_submit.ballot_token;
+    let mut issued = None;
     for s in bulk_submit.submissions {
-        submit(&mut store, user_id, s).await?;
+        // the token issued by the first submission is used by the rest of them
+        if let Some(token) = submit(&mut store, user_id, s, ballot_token.clone()).await? {
+            ballot_token = Some(token.clone());
+            issued = Some(token);
+        }
     }
     store.commit().await?;
-    Ok(())
+    Ok(issued)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ValueReport {
+    // the texts themselves are listed page by page, see `text_answers`
+    Text { answered: i64 },
+    Number(Summary),
+    Date { answered: i64, dates: Vec<DateCount> },
+    Likert(rating::Summary),
+    Nps(rating::Summary),
+    // one summary per row
+    Matrix(Vec<RowReport>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RowReport {
+    pub option_id: i32,
+    pub option: String,
+    pub summary: rating::Summary,
+}
+
+// none for the single, multi and ranked questions
+pub async fn value_report<S>(store: &mut S, question_id: i32, source: Source) -> Result<Option<ValueReport>, Error>
+where
+    S: Store,
+{
+    let report = match QuestionCommon::get_type(store, question_id).await? {
+        QuestionType::Text => ValueReport::Text {
+            answered: AnswerCommon::count_values(store, question_id, source).await?,
+        },
+        QuestionType::Number => ValueReport::Number(summarize(&AnswerCommon::numbers(store, question_id, source).await?, HISTOGRAM_BUCKETS)),
+        type_ @ (QuestionType::Likert | QuestionType::Nps) => {
+            // ratings are kept as whole numbers in the numeric column
+            let ratings: Vec<i32> = AnswerCommon::numbers(store, question_id, source).await?.into_iter().map(|n| n as i32).collect();
+            let mut summary = rating::summarize(type_.rating_scale().unwrap_or(0..=0), &ratings);
+            if let QuestionType::Nps = type_ {
+                summary.nps = net_promoter_score(&ratings);
+                ValueReport::Nps(summary)
+            } else {
+                ValueReport::Likert(summary)
+            }
+        }
+        type_ @ QuestionType::Matrix => {
+            let ratings = AnswerCommon::matrix_ratings(store, question_id, source).await?;
+            let rows = OptionCommon::query(
+                store,
+                OptionQuery {
+                    ids: None,
+                    question_id: Some(question_id),
+                    limit: None,
+                    offset: None,
+                },
+            )
+            .await?;
+            let scale = type_.rating_scale().unwrap_or(0..=0);
+            ValueReport::Matrix(
+                rows.into_iter()
+                    .map(|row| {
+                        let row_ratings: Vec<i32> = ratings.iter().filter(|(oid, _)| *oid == row.id).map(|(_, r)| *r).collect();
+                        RowReport {
+                            option_id: row.id,
+                            option: row.option,
+                            summary: rating::summarize(scale.clone(), &row_ratings),
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        QuestionType::Date => ValueReport::Date {
+            answered: AnswerCommon::count_values(store, question_id, source).await?,
+            dates: AnswerCommon::dates(store, question_id, source).await?,
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(report))
+}
+
+// the texts carry the ids of their authors, so only the managers of the vote can list them
+pub async fn text_answers<S>(store: &mut S, uid: i32, question_id: i32, source: Source, page: i64, size: i64) -> Result<(Vec<TextAnswer>, i64), Error>
+where
+    S: Store,
+{
+    authorize_question(store, question_id, uid, Permission::ManageVote).await?;
+    let pagination = Pagination::page(page, size)?;
+    if !matches!(QuestionCommon::get_type(store, question_id).await?, QuestionType::Text) {
+        return Err(Error::BusinessError(format!("not a text question(id: {})", question_id)));
+    }
+    let total = AnswerCommon::count_values(store, question_id, source).await?;
+    let list = AnswerCommon::texts(store, question_id, source, Some(pagination)).await?;
+    Ok((list, total))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::models::answer::Rating;
+
+    fn submit(option_ids: Vec<i32>, ratings: Vec<(i32, i32)>) -> AnswerSubmit {
+        AnswerSubmit {
+            question_id: 1,
+            option_ids,
+            value: None,
+            ratings: ratings.into_iter().map(|(option_id, rating)| Rating { option_id, rating }).collect(),
+        }
+    }
+
+    fn constraint(min_selections: i32, max_selections: Option<i32>) -> SelectionConstraint {
+        SelectionConstraint {
+            min_selections,
+            max_selections,
+            required: min_selections > 0,
+        }
+    }
+
+    #[test]
+    fn test_check_options() {
+        let multi = QuestionType::Multi;
+        assert!(check_options(&multi, &constraint(1, Some(2)), &submit(vec![1, 2], vec![])).is_ok());
+        assert!(check_options(&multi, &constraint(1, Some(2)), &submit(vec![], vec![])).is_err());
+        assert!(check_options(&multi, &constraint(1, Some(2)), &submit(vec![1, 2, 3], vec![])).is_err());
+        // an optional question can be left empty
+        assert!(check_options(&multi, &constraint(0, None), &submit(vec![], vec![])).is_ok());
+        assert!(check_options(&multi, &constraint(0, None), &submit(vec![1], vec![(2, 3)])).is_err());
+    }
+
+    #[test]
+    fn test_check_ranked_and_matrix_options() {
+        assert!(check_options(&QuestionType::Ranked, &constraint(1, None), &submit(vec![2, 1], vec![])).is_ok());
+        assert!(check_options(&QuestionType::Ranked, &constraint(1, None), &submit(vec![1, 1], vec![])).is_err());
+        let matrix = QuestionType::Matrix;
+        assert!(check_options(&matrix, &constraint(1, None), &submit(vec![], vec![(1, 5), (2, 1)])).is_ok());
+        assert!(check_options(&matrix, &constraint(1, None), &submit(vec![], vec![(1, 6)])).is_err());
+        assert!(check_options(&matrix, &constraint(1, None), &submit(vec![], vec![(1, 2), (1, 3)])).is_err());
+        assert!(check_options(&matrix, &constraint(1, None), &submit(vec![1], vec![])).is_err());
+    }
 }
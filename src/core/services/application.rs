@@ -0,0 +1,102 @@
+use crate::core::models::application::{ApplicationStatus, JoinApplication, Query as ApplicationQuery};
+use crate::core::models::common::Pagination;
+use crate::core::models::role::Permission;
+use crate::core::ports::repository::{ApplicationCommon, OrganizationCommon, Store, TxStore};
+use crate::core::services::organization::add_member;
+use crate::core::services::permission::authorize;
+use crate::error::Error;
+
+pub async fn apply<T>(mut tx: T, uid: i32, organization_id: i32) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    if OrganizationCommon::is_member(&mut tx, organization_id, uid).await? {
+        return Err(Error::BusinessError("already a member of this organization".into()));
+    }
+    let id = ApplicationCommon::insert(&mut tx, uid, organization_id).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub async fn pending_applications<D>(db: &mut D, uid: i32, organization_id: i32, page: i64, size: i64) -> Result<(Vec<JoinApplication>, i64), Error>
+where
+    D: Store,
+{
+    let pagination = Pagination::page(page, size)?;
+    authorize(db, organization_id, uid, Permission::ManageMembers).await?;
+    let query = ApplicationQuery {
+        organization_id_eq: Some(organization_id),
+        status_eq: Some(ApplicationStatus::Pending),
+        ..Default::default()
+    };
+    let total = ApplicationCommon::count(db, &query).await?;
+    let list = ApplicationCommon::query(db, &query, Some(pagination)).await?;
+    Ok((list, total))
+}
+
+// lock the application and make sure it is a pending one of the organization
+async fn pending_application_for_update<T>(tx: &mut T, uid: i32, organization_id: i32, id: i32) -> Result<JoinApplication, Error>
+where
+    T: TxStore,
+{
+    authorize(tx, organization_id, uid, Permission::ManageMembers).await?;
+    let application = ApplicationCommon::get_for_update(tx, id).await?;
+    if application.organization_id != organization_id {
+        return Err(Error::BusinessError("application not belongs to this organization".into()));
+    }
+    if application.status != ApplicationStatus::Pending {
+        return Err(Error::BusinessError("application is not pending".into()));
+    }
+    Ok(application)
+}
+
+pub async fn approve<T>(mut tx: T, uid: i32, organization_id: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let application = pending_application_for_update(&mut tx, uid, organization_id, id).await?;
+    ApplicationCommon::update_status(&mut tx, id, ApplicationStatus::Approved, None).await?;
+    add_member(&mut tx, organization_id, application.user_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn reject<T>(mut tx: T, uid: i32, organization_id: i32, id: i32, reason: String) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    pending_application_for_update(&mut tx, uid, organization_id, id).await?;
+    ApplicationCommon::update_status(&mut tx, id, ApplicationStatus::Rejected, Some(reason)).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn my_applications<D>(db: &mut D, uid: i32, page: i64, size: i64) -> Result<(Vec<JoinApplication>, i64), Error>
+where
+    D: Store,
+{
+    let pagination = Pagination::page(page, size)?;
+    let query = ApplicationQuery {
+        user_id_eq: Some(uid),
+        ..Default::default()
+    };
+    let total = ApplicationCommon::count(db, &query).await?;
+    let list = ApplicationCommon::query(db, &query, Some(pagination)).await?;
+    Ok((list, total))
+}
+
+pub async fn withdraw<T>(mut tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let application = ApplicationCommon::get_for_update(&mut tx, id).await?;
+    if application.user_id != uid {
+        return Err(Error::BusinessError("no permission".into()));
+    }
+    if application.status != ApplicationStatus::Pending {
+        return Err(Error::BusinessError("application is not pending".into()));
+    }
+    ApplicationCommon::update_status(&mut tx, id, ApplicationStatus::Withdrawn, None).await?;
+    tx.commit().await?;
+    Ok(())
+}
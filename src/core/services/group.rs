@@ -0,0 +1,170 @@
+use crate::core::models::common::Pagination;
+use crate::core::models::group::{GroupCreate, GroupMember, GroupUpdate, MemberGroup};
+use crate::core::models::role::Permission;
+use crate::core::ports::repository::{MemberGroupCommon, OrganizationCommon, Store, TxStore, VoteCommon};
+use crate::core::services::permission::authorize;
+use crate::core::services::vote::ensure_manager;
+use crate::error::Error;
+use itertools::Itertools;
+
+fn group_name(name: &str) -> Result<String, Error> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::BusinessError("name of the group can not be empty".into()));
+    }
+    Ok(name.to_owned())
+}
+
+// the groups of an organization are visible to all its members
+async fn ensure_member<S>(store: &mut S, org_id: i32, uid: i32) -> Result<(), Error>
+where
+    S: Store,
+{
+    if OrganizationCommon::role(store, org_id, uid).await?.is_none() {
+        return Err(Error::BusinessError("no permission".into()));
+    }
+    Ok(())
+}
+
+async fn group_of<S>(store: &mut S, org_id: i32, id: i32) -> Result<MemberGroup, Error>
+where
+    S: Store,
+{
+    match MemberGroupCommon::get(store, id).await? {
+        Some(group) if group.organization_id == org_id => Ok(group),
+        _ => Err(Error::BusinessError("group not found".into())),
+    }
+}
+
+async fn ensure_unique_name<S>(store: &mut S, org_id: i32, name: &str) -> Result<(), Error>
+where
+    S: Store,
+{
+    if MemberGroupCommon::exists_name(store, org_id, name).await? {
+        return Err(Error::BusinessError("group name already exists in the organization".into()));
+    }
+    Ok(())
+}
+
+pub async fn groups<S>(store: &mut S, uid: i32, org_id: i32) -> Result<Vec<MemberGroup>, Error>
+where
+    S: Store,
+{
+    ensure_member(store, org_id, uid).await?;
+    MemberGroupCommon::query(store, org_id).await
+}
+
+pub async fn create_group<T>(mut tx: T, uid: i32, org_id: i32, create: GroupCreate) -> Result<MemberGroup, Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, org_id, uid, Permission::ManageMembers).await?;
+    let name = group_name(&create.name)?;
+    ensure_unique_name(&mut tx, org_id, &name).await?;
+    let id = MemberGroupCommon::insert(&mut tx, org_id, name).await?;
+    let group = group_of(&mut tx, org_id, id).await?;
+    tx.commit().await?;
+    Ok(group)
+}
+
+pub async fn rename_group<T>(mut tx: T, uid: i32, org_id: i32, id: i32, update: GroupUpdate) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, org_id, uid, Permission::ManageMembers).await?;
+    let group = group_of(&mut tx, org_id, id).await?;
+    let name = group_name(&update.name)?;
+    if name != group.name {
+        ensure_unique_name(&mut tx, org_id, &name).await?;
+        MemberGroupCommon::rename(&mut tx, id, name).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// the votes targeted only at the deleted group are targeted at the whole organization afterwards
+pub async fn delete_group<T>(mut tx: T, uid: i32, org_id: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, org_id, uid, Permission::ManageMembers).await?;
+    group_of(&mut tx, org_id, id).await?;
+    MemberGroupCommon::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn group_members<S>(store: &mut S, uid: i32, org_id: i32, id: i32, page: i64, size: i64) -> Result<(Vec<GroupMember>, i64), Error>
+where
+    S: Store,
+{
+    let pagination = Pagination::page(page, size)?;
+    ensure_member(store, org_id, uid).await?;
+    group_of(store, org_id, id).await?;
+    let total = MemberGroupCommon::count_members(store, id).await?;
+    let list = MemberGroupCommon::members(store, id, Some(pagination)).await?;
+    Ok((list, total))
+}
+
+pub async fn add_group_members<T>(mut tx: T, uid: i32, org_id: i32, id: i32, uids: Vec<i32>) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, org_id, uid, Permission::ManageMembers).await?;
+    group_of(&mut tx, org_id, id).await?;
+    for &member in &uids {
+        if !OrganizationCommon::is_member(&mut tx, org_id, member).await? {
+            return Err(Error::BusinessError(format!("user {member} is not a member of the organization")));
+        }
+    }
+    MemberGroupCommon::insert_members(&mut tx, id, uids).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn remove_group_member<T>(mut tx: T, uid: i32, org_id: i32, id: i32, member: i32) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, org_id, uid, Permission::ManageMembers).await?;
+    group_of(&mut tx, org_id, id).await?;
+    let deleted = MemberGroupCommon::delete_member(&mut tx, id, member).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+pub async fn vote_groups<S>(store: &mut S, uid: i32, vote_id: i32) -> Result<Vec<MemberGroup>, Error>
+where
+    S: Store,
+{
+    let org_id = VoteCommon::get_organization_id(store, vote_id).await?;
+    ensure_member(store, org_id, uid).await?;
+    MemberGroupCommon::vote_groups(store, vote_id).await
+}
+
+// targets the vote at the groups, an empty list targets it at the whole organization again
+pub async fn target_vote<T>(mut tx: T, uid: i32, vote_id: i32, ids: Vec<i32>) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, vote_id).await?;
+    let org_id = VoteCommon::get_organization_id(&mut tx, vote_id).await?;
+    let ids = ids.into_iter().unique().collect_vec();
+    for &id in &ids {
+        group_of(&mut tx, org_id, id).await?;
+    }
+    MemberGroupCommon::set_vote_groups(&mut tx, vote_id, ids).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_group_name() {
+        assert_eq!(group_name("  Board ").unwrap(), "Board");
+        assert!(group_name("   ").is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::core::models::invite_code::{Admins, Insert as InviteCodeInsert, InviteCode, InviteCodeCreate};
+use crate::core::models::role::Permission;
+use crate::core::ports::repository::{InviteCodeCommon, Store, TxStore};
+use crate::core::services::permission::authorize;
+use crate::error::Error;
+use chrono::Utc;
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+
+// an invitation of an organization can only be managed by the roles managing its members, the other codes by the admins
+async fn ensure_issuer<S>(store: &mut S, admins: &Admins, uid: i32, org_id: Option<i32>) -> Result<(), Error>
+where
+    S: Store,
+{
+    match org_id {
+        Some(org_id) => {
+            authorize(store, org_id, uid, Permission::ManageMembers).await?;
+        }
+        None if !admins.contains(uid) => return Err(Error::BusinessError("no permission".into())),
+        None => {}
+    }
+    Ok(())
+}
+
+// without an organization the code only allows to sign up
+pub async fn create_invite_code<T>(mut tx: T, admins: &Admins, uid: i32, org_id: Option<i32>, create: InviteCodeCreate) -> Result<InviteCode, Error>
+where
+    T: TxStore,
+{
+    ensure_issuer(&mut tx, admins, uid, org_id).await?;
+    if matches!(create.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+        return Err(Error::BusinessError("expiration time of the invite code has already passed".into()));
+    }
+    if create.max_uses <= 0 {
+        return Err(Error::BusinessError("usage limit of the invite code must be positive".into()));
+    }
+    let id = InviteCodeCommon::insert(
+        &mut tx,
+        InviteCodeInsert {
+            code: thread_rng().gen::<[u8; 8]>().encode_hex(),
+            issuer: uid,
+            organization_id: org_id,
+            expires_at: create.expires_at,
+            max_uses: create.max_uses,
+        },
+    )
+    .await?;
+    let code = InviteCodeCommon::get(&mut tx, id).await?.ok_or(Error::BusinessError("invite code not found".into()))?;
+    tx.commit().await?;
+    Ok(code)
+}
+
+pub async fn invite_codes<S>(store: &mut S, admins: &Admins, uid: i32, org_id: Option<i32>) -> Result<Vec<InviteCode>, Error>
+where
+    S: Store,
+{
+    ensure_issuer(store, admins, uid, org_id).await?;
+    match org_id {
+        Some(org_id) => InviteCodeCommon::query_by_organization(store, org_id).await,
+        None => InviteCodeCommon::query_by_issuer(store, uid).await,
+    }
+}
+
+pub async fn revoke_invite_code<T>(mut tx: T, admins: &Admins, uid: i32, org_id: Option<i32>, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    ensure_issuer(&mut tx, admins, uid, org_id).await?;
+    let code = InviteCodeCommon::get(&mut tx, id).await?;
+    match code {
+        Some(code) if code.organization_id == org_id && (org_id.is_some() || code.issuer == Some(uid)) => {
+            InviteCodeCommon::revoke(&mut tx, id).await?;
+        }
+        _ => return Err(Error::BusinessError("invite code not found".into())),
+    }
+    tx.commit().await?;
+    Ok(())
+}
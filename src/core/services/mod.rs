@@ -1,6 +1,17 @@
 pub mod answer;
+pub mod application;
+pub mod group;
+pub mod invite_code;
+pub mod oidc;
 pub mod option;
 pub mod organization;
+pub mod password;
+pub mod permission;
+pub mod personal_token;
 pub mod question;
+pub mod session;
+pub mod share_link;
+pub mod two_factor;
 pub mod user;
+pub mod verification;
 pub mod vote;
@@ -0,0 +1,127 @@
+use crate::core::models::identity::OidcLoginInsert;
+use crate::core::models::user::UserInsertion;
+use crate::core::models::verification::Channel;
+use crate::core::ports::identity::{Identity, IdentityProvider};
+use crate::core::ports::repository::{IdentityCommon, Store, TxStore, UserCommon};
+use crate::error::Error;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+// the user has this long to sign in at the provider
+pub const LOGIN_MINUTES: i64 = 10;
+
+fn hash_state(state: &str) -> String {
+    Sha256::digest(state).encode_hex()
+}
+
+fn gen_token() -> String {
+    thread_rng().gen::<[u8; 32]>().encode_hex()
+}
+
+// the S256 challenge of RFC 7636
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier))
+}
+
+// the state is also kept by the browser, so the callback can only be completed where the login was started
+pub struct Authorization {
+    pub state: String,
+    pub url: String,
+}
+
+pub async fn begin_login<T, P>(mut tx: T, provider: &P) -> Result<Authorization, Error>
+where
+    T: TxStore,
+    P: IdentityProvider,
+{
+    let state = gen_token();
+    let nonce = gen_token();
+    let code_verifier = gen_token();
+    let url = provider.authorization_url(&state, &nonce, &code_challenge(&code_verifier));
+    IdentityCommon::insert_login(
+        &mut tx,
+        OidcLoginInsert {
+            state_hash: hash_state(&state),
+            nonce,
+            code_verifier,
+            expires_at: Utc::now() + Duration::minutes(LOGIN_MINUTES),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(Authorization { state, url })
+}
+
+// The login is taken before the code is redeemed at the provider, so a state is only ever used once. No transaction
+// is held while the provider answers.
+pub async fn redeem<S, P>(store: &mut S, provider: &P, state: &str, code: &str) -> Result<Identity, Error>
+where
+    S: Store,
+    P: IdentityProvider,
+{
+    let login = match IdentityCommon::take_login(store, hash_state(state)).await? {
+        Some(login) if Utc::now() < login.expires_at => login,
+        _ => return Err(Error::Unauthorized),
+    };
+    provider.exchange(code, &login.code_verifier, &login.nonce).await
+}
+
+// the user signed in with the identity, created on its first sign-in
+pub async fn finish_login<T>(mut tx: T, identity: Identity) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    let uid = match IdentityCommon::get_user_id(&mut tx, identity.issuer.clone(), identity.subject.clone()).await? {
+        Some(uid) => uid,
+        None => provision(&mut tx, identity).await?,
+    };
+    tx.commit().await?;
+    Ok(uid)
+}
+
+// A new user without a password is created for the identity. It is never linked to an existing user by the email,
+// the provider telling the same address does not prove the sign-in is made by that user. The verified email is
+// kept unless another user already has it.
+async fn provision<T>(tx: &mut T, identity: Identity) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    let mut email = identity.email.filter(|_| identity.email_verified);
+    if let Some(address) = &email {
+        if UserCommon::get_by_username(tx, address.clone()).await?.is_some() {
+            email = None;
+        }
+    }
+    let uid = UserCommon::insert(
+        tx,
+        UserInsertion {
+            nickname: identity.name.or(email.clone()).unwrap_or_else(|| identity.subject.clone()),
+            email: email.clone(),
+            phone: None,
+            password: String::new(),
+            salt: None,
+            avatar: None,
+        },
+    )
+    .await?;
+    if email.is_some() {
+        UserCommon::set_verified(tx, uid, Channel::Email).await?;
+    }
+    IdentityCommon::link(tx, uid, identity.issuer, identity.subject).await?;
+    Ok(uid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_code_challenge() {
+        // the example of RFC 7636 appendix B
+        assert_eq!(code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+}
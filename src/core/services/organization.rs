@@ -2,10 +2,18 @@ use std::fmt::Debug;
 
 use serde::Deserialize;
 
-use crate::core::models::organization::{Insert as DBInsert, Organization as DBOrganization, OrganizationWithVoteInfo as DBOrganizationWithVoteInfo, Query as DBQuery, Update as DBUpdate};
+use crate::core::models::organization::{
+    AnswerRetention, Insert as DBInsert, Organization as DBOrganization, OrganizationWithVoteInfo as DBOrganizationWithVoteInfo, Query as DBQuery, Update as DBUpdate,
+};
+use crate::core::models::role::{Permission, Role};
 use crate::error::Error;
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
 
-use crate::core::ports::repository::{OrganizationCommon, Store, TxStore};
+use crate::core::ports::repository::{AnswerCommon, BallotCommon, MemberGroupCommon, OrganizationCommon, QuestionReadMarkCommon, Store, TxStore, VoteReadMarkCommon};
+use crate::core::services::permission::authorize;
+use crate::core::services::two_factor::is_enabled;
 
 #[derive(Debug, Deserialize)]
 pub struct Create {
@@ -33,6 +41,7 @@ where
     tx.add_member(id, uid).await?;
     tx.add_user_version(id, uid).await?;
     tx.add_manager(id, uid).await?;
+    tx.set_owner(id, uid).await?;
     tx.commit().await?;
     Ok(id)
 }
@@ -70,11 +79,8 @@ pub async fn update_organization<T>(mut tx: T, uid: i32, id: i32, data: Update)
 where
     T: TxStore,
 {
-    let is_manager = tx.is_manager(id, uid).await?;
-    if !is_manager {
-        return Err(Error::BusinessError("no permission".into()));
-    }
-    let org = tx.get_for_update(id).await?;
+    authorize(&mut tx, id, uid, Permission::UpdateOrganization).await?;
+    let org = OrganizationCommon::get_for_update(&mut tx, id).await?;
     OrganizationCommon::update(
         &mut tx,
         id,
@@ -96,14 +102,14 @@ where
     Ok(org)
 }
 
-pub async fn delete_organization<D>(db: &mut D, uid: i32, id: i32) -> Result<(), Error>
+pub async fn delete_organization<T>(mut tx: T, uid: i32, id: i32) -> Result<(), Error>
 where
-    D: Store,
+    T: TxStore,
 {
-    if !OrganizationCommon::is_manager(db, id, uid).await? {
-        return Err(Error::BusinessError("No permission".into()));
-    }
-    OrganizationCommon::delete(db, id).await
+    authorize(&mut tx, id, uid, Permission::DeleteOrganization).await?;
+    OrganizationCommon::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(())
 }
 
 pub async fn add_manager<T>(tx: &mut T, id: i32, uid: i32) -> Result<(), Error>
@@ -113,5 +119,222 @@ where
     if OrganizationCommon::is_manager(tx, id, uid).await? {
         return Ok(());
     }
+    if OrganizationCommon::get(tx, id).await?.require_manager_two_factor && !is_enabled(tx, uid).await? {
+        return Err(Error::BusinessError("the organization requires its managers to enable two-factor authentication".into()));
+    }
     OrganizationCommon::add_manager(tx, id, uid).await
 }
+
+// only a member can be promoted to a manager
+pub async fn appoint_manager<T>(mut tx: T, uid: i32, id: i32, manager: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, id, uid, Permission::ManageManagers).await?;
+    if !OrganizationCommon::is_member(&mut tx, id, manager).await? {
+        return Err(Error::BusinessError("not a member of the organization".into()));
+    }
+    add_manager(&mut tx, id, manager).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// the owner and the managers keep their roles until they are demoted
+pub async fn set_member_role<T>(mut tx: T, uid: i32, id: i32, member: i32, role: Role) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, id, uid, Permission::ManageMembers).await?;
+    if !role.is_assignable() {
+        return Err(Error::BusinessError(format!("{} role can not be assigned", role.as_str().to_lowercase())));
+    }
+    match OrganizationCommon::role(&mut tx, id, member).await? {
+        None => return Err(Error::BusinessError("not a member of the organization".into())),
+        Some(current) if !current.is_assignable() => return Err(Error::BusinessError(format!("the role of the {} can not be changed", current.as_str().to_lowercase()))),
+        Some(_) => OrganizationCommon::set_role(&mut tx, id, member, role).await?,
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// a new member starts with the read marks of all the votes and questions of the organization
+pub async fn add_member<T>(tx: &mut T, id: i32, uid: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    if OrganizationCommon::is_member(tx, id, uid).await? {
+        return Ok(());
+    }
+    OrganizationCommon::add_member(tx, id, uid).await?;
+    VoteReadMarkCommon::insert_for_member(tx, id, uid).await?;
+    QuestionReadMarkCommon::insert_for_member(tx, id, uid).await
+}
+
+pub async fn add_users<T>(mut tx: T, uid: i32, id: i32, uids: Vec<i32>) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, id, uid, Permission::ManageMembers).await?;
+    // serializes the concurrent additions to the organization
+    OrganizationCommon::get_for_update(&mut tx, id).await?;
+    for member in uids {
+        add_member(&mut tx, id, member).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// Moves the answers of the user to a ballot per vote, the same way as the answers of an anonymous vote. The
+// participation is kept so the user can not answer again after coming back. The available dates can not be told
+// apart from the user, they are deleted.
+async fn anonymize_answers<T>(tx: &mut T, id: i32, uid: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    for vote_id in AnswerCommon::answered_votes(tx, id, uid).await? {
+        // nobody holds the token of the ballot
+        let token: String = thread_rng().gen::<[u8; 32]>().encode_hex();
+        let ballot: String = Sha256::digest(token).encode_hex();
+        BallotCommon::insert_ballot(tx, vote_id, ballot.clone()).await?;
+        AnswerCommon::move_to_ballot(tx, vote_id, uid, ballot).await?;
+        BallotCommon::insert_participation(tx, vote_id, uid).await?;
+    }
+    AnswerCommon::delete_dates(tx, id, uid).await
+}
+
+// an organization always keeps at least one manager
+fn ensure_other_manager(managers: i64) -> Result<(), Error> {
+    if managers <= 1 {
+        return Err(Error::BusinessError("the last manager of an organization can not leave or be demoted".into()));
+    }
+    Ok(())
+}
+
+// `managers` is the number of the managers of the organization, the leaving user included
+fn check_departure(owner_id: Option<i32>, uid: i32, is_member: bool, is_manager: bool, managers: i64) -> Result<(), Error> {
+    if !is_member {
+        return Err(Error::BusinessError("not a member of the organization".into()));
+    }
+    if owner_id == Some(uid) {
+        return Err(Error::BusinessError("the ownership must be transferred before the owner leaves".into()));
+    }
+    if is_manager {
+        ensure_other_manager(managers)?;
+    }
+    Ok(())
+}
+
+// an organization without an owner can be claimed by any of its managers
+fn can_transfer(owner_id: Option<i32>, uid: i32, is_manager: bool) -> bool {
+    match owner_id {
+        Some(owner_id) => owner_id == uid,
+        None => is_manager,
+    }
+}
+
+async fn depart<T>(tx: &mut T, id: i32, uid: i32, retention: AnswerRetention) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let org = OrganizationCommon::get_for_update(tx, id).await?;
+    let is_member = OrganizationCommon::is_member(tx, id, uid).await?;
+    let is_manager = OrganizationCommon::is_manager(tx, id, uid).await?;
+    let managers = OrganizationCommon::count_managers(tx, id).await?;
+    check_departure(org.owner_id, uid, is_member, is_manager, managers)?;
+    if let AnswerRetention::Anonymize = retention {
+        anonymize_answers(tx, id, uid).await?;
+    }
+    VoteReadMarkCommon::delete_for_member(tx, id, uid).await?;
+    QuestionReadMarkCommon::delete_for_member(tx, id, uid).await?;
+    MemberGroupCommon::delete_member_of_organization(tx, id, uid).await?;
+    OrganizationCommon::remove_member(tx, id, uid).await
+}
+
+pub async fn remove_member<T>(mut tx: T, uid: i32, id: i32, member: i32, retention: AnswerRetention) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, id, uid, Permission::ManageMembers).await?;
+    depart(&mut tx, id, member, retention).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn leave_organization<T>(mut tx: T, uid: i32, id: i32, retention: AnswerRetention) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    depart(&mut tx, id, uid, retention).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// the demoted manager stays a member
+pub async fn demote_manager<T>(mut tx: T, uid: i32, id: i32, manager: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let org = OrganizationCommon::get_for_update(&mut tx, id).await?;
+    authorize(&mut tx, id, uid, Permission::ManageManagers).await?;
+    if !OrganizationCommon::is_manager(&mut tx, id, manager).await? {
+        return Err(Error::BusinessError("not a manager of the organization".into()));
+    }
+    if org.owner_id == Some(manager) {
+        return Err(Error::BusinessError("the owner can not be demoted".into()));
+    }
+    ensure_other_manager(OrganizationCommon::count_managers(&mut tx, id).await?)?;
+    OrganizationCommon::remove_manager(&mut tx, id, manager).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// the new owner is made a manager if it is not one yet, the previous owner stays a manager
+pub async fn transfer_ownership<T>(mut tx: T, uid: i32, id: i32, to: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let org = OrganizationCommon::get_for_update(&mut tx, id).await?;
+    let is_manager = OrganizationCommon::is_manager(&mut tx, id, uid).await?;
+    if !can_transfer(org.owner_id, uid, is_manager) {
+        return Err(Error::BusinessError("only the owner can transfer the ownership".into()));
+    }
+    if !OrganizationCommon::is_member(&mut tx, id, to).await? {
+        return Err(Error::BusinessError("the new owner must be a member of the organization".into()));
+    }
+    add_manager(&mut tx, id, to).await?;
+    OrganizationCommon::set_owner(&mut tx, id, to).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_last_manager() {
+        assert!(ensure_other_manager(1).is_err());
+        assert!(ensure_other_manager(2).is_ok());
+    }
+
+    #[test]
+    fn test_departure() {
+        // a member who is not a manager can always leave
+        assert!(check_departure(Some(1), 2, true, false, 1).is_ok());
+        assert!(check_departure(Some(1), 2, false, false, 1).is_err());
+        // the owner must hand the organization over first
+        assert!(check_departure(Some(1), 1, true, true, 2).is_err());
+        // a manager leaves only when another one is left
+        assert!(check_departure(Some(1), 2, true, true, 1).is_err());
+        assert!(check_departure(Some(1), 2, true, true, 2).is_ok());
+        assert!(check_departure(None, 2, true, true, 2).is_ok());
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        assert!(can_transfer(Some(1), 1, true));
+        assert!(!can_transfer(Some(1), 2, true));
+        assert!(can_transfer(None, 2, true));
+        assert!(!can_transfer(None, 2, false));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::core::models::password_reset::Insert as PasswordResetInsert;
+use crate::core::models::user::Patch as UserPatch;
+use crate::core::models::verification::Channel;
+use crate::core::ports::hasher::PasswordHasher;
+use crate::core::ports::notifier::{Notification, Notifier};
+use crate::core::ports::repository::{PasswordResetCommon, SessionCommon, TxStore, UserCommon};
+use crate::error::Error;
+use chrono::{Duration, Utc};
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+pub const RESET_TOKEN_MINUTES: i64 = 30;
+
+fn hash_reset_token(token: &str) -> String {
+    Sha256::digest(token).encode_hex()
+}
+
+// all the sessions of the user are revoked along with the new password
+async fn set_password<T, H>(tx: &mut T, hasher: &H, uid: i32, password: &str) -> Result<(), Error>
+where
+    T: TxStore,
+    H: PasswordHasher,
+{
+    if password.is_empty() {
+        return Err(Error::BusinessError("password must not be empty".into()));
+    }
+    UserCommon::patch(
+        tx,
+        uid,
+        UserPatch {
+            password: Some(hasher.hash(password).await?),
+            ..Default::default()
+        },
+    )
+    .await?;
+    SessionCommon::revoke_all(tx, uid).await
+}
+
+pub async fn change_password<T, H>(mut tx: T, hasher: &H, uid: i32, current: &str, new: &str) -> Result<(), Error>
+where
+    T: TxStore,
+    H: PasswordHasher,
+{
+    let user = UserCommon::get(&mut tx, uid).await?;
+    if !hasher.verify(current, &user.password, user.salt.as_deref()).await? {
+        return Err(Error::BusinessError("invalid password".into()));
+    }
+    set_password(&mut tx, hasher, uid, new).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// Nothing tells whether the username exists, so the endpoint can not be used to find out who has an account.
+pub async fn request_password_reset<T, N>(mut tx: T, notifier: &N, username: String) -> Result<(), Error>
+where
+    T: TxStore,
+    N: Notifier,
+{
+    let user = match UserCommon::get_by_username(&mut tx, username).await? {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+    // the users signed up by single sign-on may have no email to send the token to
+    let email = match user.email {
+        Some(email) => email,
+        None => return Ok(()),
+    };
+    let token: String = thread_rng().gen::<[u8; 32]>().encode_hex();
+    PasswordResetCommon::invalidate(&mut tx, user.id).await?;
+    PasswordResetCommon::insert(
+        &mut tx,
+        PasswordResetInsert {
+            user_id: user.id,
+            token_hash: hash_reset_token(&token),
+            expires_at: Utc::now() + Duration::minutes(RESET_TOKEN_MINUTES),
+        },
+    )
+    .await?;
+    // the token is stored before it is sent, a failed delivery can be retried with a new request
+    tx.commit().await?;
+    notifier
+        .notify(Notification {
+            channel: Channel::Email,
+            to: email,
+            subject: "Password reset".into(),
+            body: format!("Use this token to reset your password, it expires in {} minutes:\n{}", RESET_TOKEN_MINUTES, token),
+        })
+        .await?;
+    Ok(())
+}
+
+pub async fn reset_password<T, H>(mut tx: T, hasher: &H, token: &str, password: &str) -> Result<(), Error>
+where
+    T: TxStore,
+    H: PasswordHasher,
+{
+    let reset = match PasswordResetCommon::get_by_token_hash(&mut tx, hash_reset_token(token)).await? {
+        Some(reset) if reset.is_valid(Utc::now()) => reset,
+        _ => return Err(Error::BusinessError("invalid or expired reset token".into())),
+    };
+    PasswordResetCommon::invalidate(&mut tx, reset.user_id).await?;
+    set_password(&mut tx, hasher, reset.user_id, password).await?;
+    tx.commit().await?;
+    Ok(())
+}
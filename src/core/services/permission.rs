@@ -0,0 +1,47 @@
+use crate::core::models::role::{Permission, Role};
+use crate::core::ports::repository::{MemberGroupCommon, OrganizationCommon, QuestionCommon, Store, VoteCommon, VoteWhiteListCommon};
+use crate::error::Error;
+
+// returns the role of the user when it grants the permission in the organization
+pub async fn authorize<S>(store: &mut S, org_id: i32, uid: i32, permission: Permission) -> Result<Role, Error>
+where
+    S: Store,
+{
+    match OrganizationCommon::role(store, org_id, uid).await? {
+        Some(role) if role.can(permission) => Ok(role),
+        _ => Err(Error::Forbidden("no permission".into())),
+    }
+}
+
+// the permission is taken in the organization of the vote
+pub async fn authorize_vote<S>(store: &mut S, vote_id: i32, uid: i32, permission: Permission) -> Result<Role, Error>
+where
+    S: Store,
+{
+    let org_id = VoteCommon::get_organization_id(store, vote_id).await?;
+    authorize(store, org_id, uid, permission).await
+}
+
+pub async fn authorize_question<S>(store: &mut S, question_id: i32, uid: i32, permission: Permission) -> Result<Role, Error>
+where
+    S: Store,
+{
+    let org_id = QuestionCommon::get_organization_id(store, question_id).await?;
+    authorize(store, org_id, uid, permission).await
+}
+
+// The whitelisted users answer the WhiteList votes whether they are members of the organization or not, the others
+// need the answer permission and to be in the audience of the vote.
+pub async fn authorize_answer<S>(store: &mut S, vote_id: i32, uid: i32) -> Result<(), Error>
+where
+    S: Store,
+{
+    if VoteWhiteListCommon::is_whitelisted(store, vote_id, uid).await? {
+        return Ok(());
+    }
+    authorize_vote(store, vote_id, uid, Permission::Answer).await?;
+    if !MemberGroupCommon::is_in_audience(store, vote_id, uid).await? {
+        return Err(Error::BusinessError("the vote is not targeted at the user".into()));
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::core::models::personal_token::{Insert as PersonalTokenInsert, PersonalToken, PersonalTokenCreate};
+use crate::core::ports::repository::{PersonalTokenCommon, Store, TxStore};
+use crate::error::Error;
+use chrono::Utc;
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+// tells the tokens apart from the other secrets, e.g. for secret scanners
+pub const TOKEN_PREFIX: &str = "juju_";
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token).encode_hex()
+}
+
+// returns the token along with it, the token can not be retrieved again
+pub async fn create_token<T>(mut tx: T, uid: i32, create: PersonalTokenCreate) -> Result<(PersonalToken, String), Error>
+where
+    T: TxStore,
+{
+    if create.name.trim().is_empty() {
+        return Err(Error::BusinessError("name of the token must not be empty".into()));
+    }
+    if create.scopes.is_empty() {
+        return Err(Error::BusinessError("token must have at least one scope".into()));
+    }
+    if matches!(create.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+        return Err(Error::BusinessError("expiration time of the token has already passed".into()));
+    }
+    let token = format!("{}{}", TOKEN_PREFIX, thread_rng().gen::<[u8; 32]>().encode_hex::<String>());
+    let id = PersonalTokenCommon::insert(
+        &mut tx,
+        PersonalTokenInsert {
+            user_id: uid,
+            name: create.name,
+            token_hash: hash_token(&token),
+            scopes: create.scopes,
+            expires_at: create.expires_at,
+        },
+    )
+    .await?;
+    let created = PersonalTokenCommon::get(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok((created, token))
+}
+
+pub async fn tokens<S>(store: &mut S, uid: i32) -> Result<Vec<PersonalToken>, Error>
+where
+    S: Store,
+{
+    PersonalTokenCommon::query(store, uid).await
+}
+
+pub async fn revoke_token<T>(mut tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    if PersonalTokenCommon::revoke(&mut tx, uid, id).await? == 0 {
+        return Err(Error::BusinessError("token not found".into()));
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// returns the owner of the token if it is active and its scopes allow the request
+pub async fn authenticate<S>(mut store: S, token: &str, write: bool) -> Result<Option<i32>, Error>
+where
+    S: Store,
+{
+    if !token.starts_with(TOKEN_PREFIX) {
+        return Ok(None);
+    }
+    match PersonalTokenCommon::get_by_token_hash(&mut store, hash_token(token)).await? {
+        Some(t) if t.is_active(Utc::now()) && t.allows(write) => {
+            PersonalTokenCommon::touch(&mut store, t.id).await?;
+            Ok(Some(t.user_id))
+        }
+        _ => Ok(None),
+    }
+}
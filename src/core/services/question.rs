@@ -3,11 +3,16 @@ use crate::{
         models::{
             option::Insert as OptionInsert,
             question::{
-                Create as QuestionCreate, FavoriteQuestion, FavoriteQuestionQuery, Insert as QuestionInsert, Query, Question, ReadMarkInsert as QuestionReadMarkInsert,
-                ReadMarkUpdate as QuestionReadMarkUpdate,
+                Create as QuestionCreate, FavoriteQuestion, FavoriteQuestionQuery, Insert as QuestionInsert, Query, Question, QuestionType, ReadMarkInsert as QuestionReadMarkInsert,
+                ReadMarkUpdate as QuestionReadMarkUpdate, TallyMethod,
             },
+            role::Permission,
+        },
+        ports::repository::{OptionCommon, QuestionCommon, QuestionReadMarkCommon, Store},
+        services::{
+            permission::{authorize_question, authorize_vote},
+            vote::ensure_editable,
         },
-        ports::repository::{OptionCommon, OrganizationCommon, QuestionCommon, QuestionReadMarkCommon, Store},
     },
     error::Error,
 };
@@ -16,14 +21,26 @@ pub async fn create_question<S>(uid: i32, vote_id: i32, storer: &mut S, question
 where
     S: Store,
 {
+    authorize_vote(storer, vote_id, uid, Permission::EditVote).await?;
+    ensure_editable(storer, vote_id).await?;
+    let type_ = QuestionType::try_from(question.type_.as_str())?;
+    let (min_selections, max_selections) = type_.selection_bounds(question.min_selections, question.max_selections, question.required)?;
+    if !type_.is_option_based() && !question.options.is_empty() {
+        return Err(Error::BusinessError(format!("{} question can not have options", type_.as_str().to_lowercase())));
+    }
     let qid = QuestionCommon::insert(
         storer,
         uid,
         QuestionInsert {
             description: question.description,
             version: question.version,
-            type_: question.type_,
+            type_: type_.as_str().into(),
             vote_id,
+            min_selections,
+            max_selections,
+            required: question.required,
+            tally_method: question.tally_method.as_str().into(),
+            number_range: type_.number_range(question.number_range)?,
         },
     )
     .await?;
@@ -71,14 +88,28 @@ pub async fn delete_question<S>(storer: &mut S, uid: i32, id: i32) -> Result<(),
 where
     S: Store,
 {
-    let org_id = QuestionCommon::get_organization_id(storer, id).await?;
-    if !OrganizationCommon::is_manager(storer, org_id, uid).await? && !QuestionCommon::is_owner(storer, uid, id).await? {
-        return Err(Error::Unauthorized);
-    }
+    authorize_question(storer, id, uid, Permission::EditVote).await?;
+    let vote_id = QuestionCommon::get_vote_id(storer, id).await?;
+    ensure_editable(storer, vote_id).await?;
     QuestionCommon::delete(storer, id).await?;
     Ok(())
 }
 
+pub async fn set_tally_method<S>(storer: &mut S, uid: i32, id: i32, method: TallyMethod) -> Result<(), Error>
+where
+    S: Store,
+{
+    authorize_question(storer, id, uid, Permission::EditVote).await?;
+    if !matches!(QuestionCommon::get_type(storer, id).await?, QuestionType::Ranked) {
+        return Err(Error::BusinessError("tally method can only be set on ranked questions".into()));
+    }
+    // the official method must not be switched once the results are final
+    let vote_id = QuestionCommon::get_vote_id(storer, id).await?;
+    ensure_editable(storer, vote_id).await?;
+    QuestionCommon::update_tally_method(storer, id, method.as_str().into()).await?;
+    Ok(())
+}
+
 pub async fn questions_with_in_vote<S>(storer: &mut S, uid: i32, vote_id: i32) -> Result<(Vec<Question>, i64), Error>
 where
     S: Store,
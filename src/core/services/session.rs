@@ -0,0 +1,126 @@
+use crate::core::models::session::{Client, Insert as SessionInsert, SessionInfo};
+use crate::core::ports::repository::{SessionCommon, Store, TxStore};
+use crate::error::Error;
+use chrono::{Duration, Utc};
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+// access tokens are not checked against the password, so they are kept short
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+// a session expires when it has not been refreshed for this long
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+
+fn hash_refresh_token(token: &str) -> String {
+    Sha256::digest(token).encode_hex()
+}
+
+fn gen_refresh_token() -> String {
+    thread_rng().gen::<[u8; 32]>().encode_hex()
+}
+
+// the refresh token of a session, only its hash is stored
+pub struct Refreshed {
+    pub user_id: i32,
+    pub session_id: i32,
+    pub refresh_token: String,
+}
+
+pub async fn create_session<T>(mut tx: T, uid: i32, client: Client) -> Result<Refreshed, Error>
+where
+    T: TxStore,
+{
+    let refresh_token = gen_refresh_token();
+    let session_id = SessionCommon::insert(
+        &mut tx,
+        SessionInsert {
+            user_id: uid,
+            refresh_token_hash: hash_refresh_token(&refresh_token),
+            expires_at: Utc::now() + Duration::days(REFRESH_TOKEN_DAYS),
+            client,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(Refreshed {
+        user_id: uid,
+        session_id,
+        refresh_token,
+    })
+}
+
+// Every refresh token can only be used once, it is replaced by a new one. A replaced token being presented
+// again means it has leaked, so the whole session is revoked.
+pub async fn refresh<T>(mut tx: T, refresh_token: &str, client: Client) -> Result<Refreshed, Error>
+where
+    T: TxStore,
+{
+    let hash = hash_refresh_token(refresh_token);
+    let session = match SessionCommon::get_by_refresh_token_hash(&mut tx, hash.clone()).await? {
+        Some(session) => session,
+        None => {
+            if let Some(session) = SessionCommon::get_by_previous_token_hash(&mut tx, hash).await? {
+                SessionCommon::revoke(&mut tx, session.id).await?;
+                tx.commit().await?;
+            }
+            return Err(Error::Unauthorized);
+        }
+    };
+    if !session.is_active(Utc::now()) {
+        return Err(Error::Unauthorized);
+    }
+    let refresh_token = gen_refresh_token();
+    SessionCommon::rotate(&mut tx, session.id, hash_refresh_token(&refresh_token), Utc::now() + Duration::days(REFRESH_TOKEN_DAYS), client).await?;
+    tx.commit().await?;
+    Ok(Refreshed {
+        user_id: session.user_id,
+        session_id: session.id,
+        refresh_token,
+    })
+}
+
+pub async fn logout<T>(mut tx: T, refresh_token: &str) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    if let Some(session) = SessionCommon::get_by_refresh_token_hash(&mut tx, hash_refresh_token(refresh_token)).await? {
+        SessionCommon::revoke(&mut tx, session.id).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn sessions<S>(mut store: S, uid: i32, current: i32) -> Result<Vec<SessionInfo>, Error>
+where
+    S: Store,
+{
+    let sessions = SessionCommon::query(&mut store, uid).await?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| SessionInfo {
+            current: session.id == current,
+            session,
+        })
+        .collect())
+}
+
+pub async fn revoke_session<T>(mut tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    if SessionCommon::revoke_of_user(&mut tx, uid, id).await? == 0 {
+        return Err(Error::BusinessError("session not found".into()));
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// sign out everywhere else, the session of the request is kept
+pub async fn revoke_other_sessions<T>(mut tx: T, uid: i32, current: i32) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    let revoked = SessionCommon::revoke_others(&mut tx, uid, current).await?;
+    tx.commit().await?;
+    Ok(revoked)
+}
@@ -0,0 +1,159 @@
+use crate::core::models::answer::{Query as AnswerQuery, Respondent, Submit};
+use crate::core::models::question::Question;
+use crate::core::models::share_link::{Insert as ShareLinkInsert, ShareLink, ShareLinkCreate};
+use crate::core::models::vote::Vote;
+use crate::core::ports::repository::{AnswerCommon, QuestionCommon, ShareLinkCommon, Store, TxStore, VoteCommon};
+use crate::core::services::vote::{check_answers, ensure_manager, save_answers};
+use crate::error::Error;
+use chrono::{DateTime, Utc};
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token).encode_hex()
+}
+
+// whether one more join or submission is allowed after the ones of the last hour
+fn within_rate_limit(recent: i64, rate_limit: i32) -> bool {
+    recent < rate_limit as i64
+}
+
+// returns the id and the token of the link, the token can not be retrieved again
+pub async fn create_share_link<T>(mut tx: T, uid: i32, vote_id: i32, create: ShareLinkCreate) -> Result<(i32, String), Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, vote_id).await?;
+    if create.expires_at <= Utc::now() {
+        return Err(Error::BusinessError("expiration time of the share link has already passed".into()));
+    }
+    if create.rate_limit <= 0 {
+        return Err(Error::BusinessError("rate limit of the share link must be positive".into()));
+    }
+    let token: String = thread_rng().gen::<[u8; 32]>().encode_hex();
+    let id = ShareLinkCommon::insert(
+        &mut tx,
+        ShareLinkInsert {
+            vote_id,
+            token_hash: hash_token(&token),
+            creator: uid,
+            expires_at: create.expires_at,
+            rate_limit: create.rate_limit,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    Ok((id, token))
+}
+
+pub async fn share_links<S>(store: &mut S, uid: i32, vote_id: i32) -> Result<Vec<ShareLink>, Error>
+where
+    S: Store,
+{
+    ensure_manager(store, uid, vote_id).await?;
+    ShareLinkCommon::query(store, vote_id).await
+}
+
+pub async fn revoke_share_link<T>(mut tx: T, uid: i32, vote_id: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, vote_id).await?;
+    if ShareLinkCommon::revoke(&mut tx, vote_id, id).await? == 0 {
+        return Err(Error::BusinessError("share link not found".into()));
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct Joined {
+    pub guest_id: i32,
+    pub vote_id: i32,
+    pub share_link_id: i32,
+    // the guest identity is not valid after the link expires
+    pub expires_at: DateTime<Utc>,
+}
+
+pub async fn join<T>(mut tx: T, token: &str) -> Result<Joined, Error>
+where
+    T: TxStore,
+{
+    let link = ShareLinkCommon::get_by_token_hash_for_update(&mut tx, hash_token(token))
+        .await?
+        .ok_or(Error::BusinessError("invalid share link".into()))?;
+    if !link.is_valid(Utc::now()) {
+        return Err(Error::BusinessError("share link is revoked or expired".into()));
+    }
+    if !within_rate_limit(ShareLinkCommon::count_recent_guests(&mut tx, link.id).await?, link.rate_limit) {
+        return Err(Error::TooManyRequests);
+    }
+    let guest_id = ShareLinkCommon::insert_guest(&mut tx, link.id).await?;
+    tx.commit().await?;
+    Ok(Joined {
+        guest_id,
+        vote_id: link.vote_id,
+        share_link_id: link.id,
+        expires_at: link.expires_at,
+    })
+}
+
+// the link may have been revoked after the guest joined
+fn valid_link(link: Option<ShareLink>, vote_id: i32) -> Result<ShareLink, Error> {
+    // the token of the guest outlives the deleted links
+    let link = link.ok_or(Error::Unauthorized)?;
+    if link.vote_id != vote_id || !link.is_valid(Utc::now()) {
+        return Err(Error::BusinessError("share link is revoked or expired".into()));
+    }
+    Ok(link)
+}
+
+pub async fn guest_vote<S>(store: &mut S, guest_id: i32, share_link_id: i32, vote_id: i32) -> Result<(Vote, Vec<Question>), Error>
+where
+    S: Store,
+{
+    valid_link(ShareLinkCommon::get(store, share_link_id).await?, vote_id)?;
+    let vote = VoteCommon::get_for_guest(store, vote_id).await?;
+    let questions = QuestionCommon::query_for_guest(store, guest_id, vote_id).await?;
+    Ok((vote, questions))
+}
+
+pub async fn submit_guest_answers<T>(mut tx: T, guest_id: i32, share_link_id: i32, vote_id: i32, answers: Vec<Submit>) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let link = valid_link(ShareLinkCommon::get_for_update(&mut tx, share_link_id).await?, vote_id)?;
+    if !within_rate_limit(ShareLinkCommon::count_recent_submissions(&mut tx, link.id, guest_id).await?, link.rate_limit) {
+        return Err(Error::TooManyRequests);
+    }
+    let (checked, late) = check_answers(&mut tx, vote_id, answers).await?;
+    // a new submission replaces the previous one of the guest
+    AnswerCommon::delete(
+        &mut tx,
+        AnswerQuery {
+            question_id_eq: None,
+            user_id_eq: None,
+            ballot_eq: None,
+            guest_id_eq: Some(guest_id),
+        },
+    )
+    .await?;
+    save_answers(&mut tx, &Respondent::Guest(guest_id), checked, late).await?;
+    ShareLinkCommon::update_guest_submitted_at(&mut tx, guest_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        assert!(within_rate_limit(0, 1));
+        assert!(within_rate_limit(59, 60));
+        assert!(!within_rate_limit(60, 60));
+        assert!(!within_rate_limit(61, 60));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::core::models::role::Permission;
+use crate::core::models::two_factor::{ChallengeInsert, Enrollment, TwoFactor};
+use crate::core::ports::repository::{OrganizationCommon, Store, TwoFactorCommon, TxStore, UserCommon};
+use crate::core::services::permission::authorize;
+use crate::core::totp;
+use crate::error::Error;
+use chrono::{Duration, Utc};
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+// the issuer shown by the authenticator apps
+pub const ISSUER: &str = "juju";
+pub const RECOVERY_CODES: usize = 10;
+// the password has been verified, the second factor is expected within this
+pub const CHALLENGE_MINUTES: i64 = 5;
+pub const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+fn hash_secret(s: &str) -> String {
+    Sha256::digest(s).encode_hex()
+}
+
+// recovery codes are compared case insensitively and regardless of separators
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+async fn enabled<S>(store: &mut S, uid: i32) -> Result<TwoFactor, Error>
+where
+    S: Store,
+{
+    match TwoFactorCommon::get(store, uid).await? {
+        Some(two_factor) if two_factor.enabled => Ok(two_factor),
+        _ => Err(Error::BusinessError("two-factor authentication is not enabled".into())),
+    }
+}
+
+// a TOTP code or one of the recovery codes, each can be used only once
+async fn check_code<S>(store: &mut S, two_factor: &TwoFactor, code: &str) -> Result<bool, Error>
+where
+    S: Store,
+{
+    if let Some(counter) = totp::verify(&two_factor.secret, code.trim(), Utc::now().timestamp()) {
+        if !two_factor.accepts_counter(counter) {
+            return Ok(false);
+        }
+        // the conditional update refuses the same code submitted concurrently
+        return TwoFactorCommon::advance_last_counter(store, two_factor.user_id, counter).await;
+    }
+    TwoFactorCommon::use_recovery_code(store, two_factor.user_id, hash_secret(&normalize_recovery_code(code))).await
+}
+
+// returns the recovery codes, only their hashes are kept
+async fn gen_recovery_codes<S>(store: &mut S, uid: i32) -> Result<Vec<String>, Error>
+where
+    S: Store,
+{
+    let codes: Vec<String> = (0..RECOVERY_CODES).map(|_| thread_rng().gen::<[u8; 5]>().encode_hex()).collect();
+    TwoFactorCommon::replace_recovery_codes(store, uid, codes.iter().map(|c| hash_secret(c)).collect()).await?;
+    Ok(codes)
+}
+
+pub async fn is_enabled<S>(store: &mut S, uid: i32) -> Result<bool, Error>
+where
+    S: Store,
+{
+    Ok(TwoFactorCommon::get(store, uid).await?.map(|t| t.enabled).unwrap_or(false))
+}
+
+pub async fn enroll<T>(mut tx: T, uid: i32) -> Result<Enrollment, Error>
+where
+    T: TxStore,
+{
+    if is_enabled(&mut tx, uid).await? {
+        return Err(Error::BusinessError("two-factor authentication is already enabled".into()));
+    }
+    let user = UserCommon::get(&mut tx, uid).await?;
+    let secret = totp::generate_secret();
+    TwoFactorCommon::upsert_secret(&mut tx, uid, secret.clone()).await?;
+    tx.commit().await?;
+    Ok(Enrollment {
+        otpauth_uri: totp::otpauth_uri(ISSUER, user.email.as_deref().or(user.phone.as_deref()).unwrap_or(&user.nickname), &secret),
+        secret,
+    })
+}
+
+// the enrollment takes effect once a code of the secret is confirmed
+pub async fn confirm<T>(mut tx: T, uid: i32, code: &str) -> Result<Vec<String>, Error>
+where
+    T: TxStore,
+{
+    let two_factor = match TwoFactorCommon::get(&mut tx, uid).await? {
+        Some(two_factor) if !two_factor.enabled => two_factor,
+        Some(_) => return Err(Error::BusinessError("two-factor authentication is already enabled".into())),
+        None => return Err(Error::BusinessError("two-factor authentication has not been enrolled".into())),
+    };
+    let counter = totp::verify(&two_factor.secret, code.trim(), Utc::now().timestamp()).ok_or(Error::BusinessError("invalid code".into()))?;
+    if !TwoFactorCommon::advance_last_counter(&mut tx, uid, counter).await? {
+        return Err(Error::BusinessError("invalid code".into()));
+    }
+    TwoFactorCommon::enable(&mut tx, uid).await?;
+    let codes = gen_recovery_codes(&mut tx, uid).await?;
+    tx.commit().await?;
+    Ok(codes)
+}
+
+pub async fn disable<T>(mut tx: T, uid: i32, code: &str) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let two_factor = enabled(&mut tx, uid).await?;
+    if OrganizationCommon::manages_two_factor_required(&mut tx, uid).await? {
+        return Err(Error::BusinessError("two-factor authentication is required by an organization you manage".into()));
+    }
+    if !check_code(&mut tx, &two_factor, code).await? {
+        return Err(Error::BusinessError("invalid code".into()));
+    }
+    TwoFactorCommon::delete(&mut tx, uid).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// the previous recovery codes stop working
+pub async fn regenerate_recovery_codes<T>(mut tx: T, uid: i32, code: &str) -> Result<Vec<String>, Error>
+where
+    T: TxStore,
+{
+    let two_factor = enabled(&mut tx, uid).await?;
+    if !check_code(&mut tx, &two_factor, code).await? {
+        return Err(Error::BusinessError("invalid code".into()));
+    }
+    let codes = gen_recovery_codes(&mut tx, uid).await?;
+    tx.commit().await?;
+    Ok(codes)
+}
+
+// returns the token to answer with the second factor, in place of a session
+pub async fn create_challenge<T>(mut tx: T, uid: i32) -> Result<String, Error>
+where
+    T: TxStore,
+{
+    let token: String = thread_rng().gen::<[u8; 32]>().encode_hex();
+    TwoFactorCommon::insert_challenge(
+        &mut tx,
+        ChallengeInsert {
+            user_id: uid,
+            token_hash: hash_secret(&token),
+            expires_at: Utc::now() + Duration::minutes(CHALLENGE_MINUTES),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(token)
+}
+
+// returns the user who has passed the challenge
+pub async fn verify_challenge<T>(mut tx: T, token: &str, code: &str) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    let challenge = match TwoFactorCommon::get_challenge_by_token_hash(&mut tx, hash_secret(token)).await? {
+        Some(challenge) if challenge.is_valid(Utc::now(), MAX_CHALLENGE_ATTEMPTS) => challenge,
+        _ => return Err(Error::Unauthorized),
+    };
+    let two_factor = enabled(&mut tx, challenge.user_id).await?;
+    if !check_code(&mut tx, &two_factor, code).await? {
+        TwoFactorCommon::increase_challenge_attempts(&mut tx, challenge.id).await?;
+        tx.commit().await?;
+        return Err(Error::BusinessError("invalid code".into()));
+    }
+    TwoFactorCommon::mark_challenge_used(&mut tx, challenge.id).await?;
+    tx.commit().await?;
+    Ok(challenge.user_id)
+}
+
+// every manager must have enabled two-factor authentication before it is required
+pub async fn require_for_managers<T>(mut tx: T, uid: i32, org_id: i32, required: bool) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    authorize(&mut tx, org_id, uid, Permission::UpdateOrganization).await?;
+    if required {
+        let missing = OrganizationCommon::count_managers_without_two_factor(&mut tx, org_id).await?;
+        if missing > 0 {
+            return Err(Error::BusinessError(format!("{} managers have not enabled two-factor authentication", missing)));
+        }
+    }
+    OrganizationCommon::set_manager_two_factor_required(&mut tx, org_id, required).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_code_used_twice() {
+        let mut two_factor = TwoFactor {
+            user_id: 1,
+            secret: totp::generate_secret(),
+            enabled: true,
+            last_counter: None,
+        };
+        let now = 1692345678;
+        let code = totp::code(&two_factor.secret, totp::counter(now)).unwrap();
+        let counter = totp::verify(&two_factor.secret, &code, now).unwrap();
+        assert!(two_factor.accepts_counter(counter));
+        two_factor.last_counter = Some(counter);
+        let counter = totp::verify(&two_factor.secret, &code, now + totp::STEP).unwrap();
+        assert!(!two_factor.accepts_counter(counter));
+        let next = totp::code(&two_factor.secret, totp::counter(now + totp::STEP)).unwrap();
+        assert!(two_factor.accepts_counter(totp::verify(&two_factor.secret, &next, now + totp::STEP).unwrap()));
+    }
+}
@@ -1,5 +1,7 @@
-use crate::core::models::user::{Patch as UserPatch, Profile, ProfileUpdate};
-use crate::core::ports::repository::{OrganizationCommon, Store, UserCommon};
+use crate::core::models::user::{Patch as UserPatch, Profile, ProfileUpdate, User as UserModel, UserInsertion};
+use crate::core::ports::hasher::PasswordHasher;
+use crate::core::ports::repository::{InviteCodeCommon, OrganizationCommon, Store, TxStore, UserCommon};
+use crate::core::services::organization::add_member;
 use crate::error::Error;
 
 #[derive(Debug, Default)]
@@ -10,15 +12,19 @@ pub struct User {
     pub is_member: Option<bool>,
     pub is_manager: Option<bool>,
 }
-pub async fn search_by_phone<D>(mut db: D, phone: String, org_id: Option<i32>) -> Result<Option<User>, Error>
+// with `verified_only`, an unverified phone is treated as not found
+pub async fn search_by_phone<D>(mut db: D, phone: String, org_id: Option<i32>, verified_only: bool) -> Result<Option<User>, Error>
 where
     D: Store,
 {
     if let Some(user) = UserCommon::get_by_phone(&mut db, phone).await? {
+        if verified_only && !user.phone_verified {
+            return Ok(None);
+        }
         let mut u = User {
             id: user.id,
             nickname: user.nickname,
-            phone: user.phone,
+            phone: user.phone.unwrap_or_default(),
             ..Default::default()
         };
         if let Some(org_id) = org_id {
@@ -59,3 +65,55 @@ where
     .await?;
     Ok(())
 }
+
+// A legacy or outdated hash is replaced once the password is known to be right, so the hashes are upgraded
+// as the users log in.
+pub async fn verify_password<T, H>(mut tx: T, hasher: &H, user: &UserModel, password: &str) -> Result<bool, Error>
+where
+    T: TxStore,
+    H: PasswordHasher,
+{
+    if !hasher.verify(password, &user.password, user.salt.as_deref()).await? {
+        return Ok(false);
+    }
+    if user.salt.is_some() || hasher.needs_rehash(&user.password) {
+        UserCommon::rehash_password(&mut tx, user.id, hasher.hash(password).await?).await?;
+        tx.commit().await?;
+    }
+    Ok(true)
+}
+
+#[derive(Debug)]
+pub struct Signup {
+    pub nickname: String,
+    pub phone: String,
+    pub email: String,
+    pub password: String,
+    pub invite_code: String,
+}
+
+// the user joins the organization of the invite code, if any
+pub async fn signup<T, H>(mut tx: T, hasher: &H, signup: Signup) -> Result<i32, Error>
+where
+    T: TxStore,
+    H: PasswordHasher,
+{
+    let code = InviteCodeCommon::redeem(&mut tx, signup.invite_code).await?.ok_or(Error::BusinessError("invalid invite code".into()))?;
+    let uid = UserCommon::insert(
+        &mut tx,
+        UserInsertion {
+            nickname: signup.nickname,
+            phone: Some(signup.phone),
+            email: Some(signup.email),
+            password: hasher.hash(&signup.password).await?,
+            salt: None,
+            avatar: None,
+        },
+    )
+    .await?;
+    if let Some(org_id) = code.organization_id {
+        add_member(&mut tx, org_id, uid).await?;
+    }
+    tx.commit().await?;
+    Ok(uid)
+}
@@ -0,0 +1,93 @@
+use crate::core::models::user::User;
+use crate::core::models::verification::{Channel, Insert as VerificationInsert};
+use crate::core::ports::notifier::{Notification, Notifier};
+use crate::core::ports::repository::{TxStore, UserCommon, VerificationCommon};
+use crate::error::Error;
+use chrono::{Duration, Utc};
+use hex::ToHex;
+use rand::{thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+pub const CODE_MINUTES: i64 = 10;
+// a new code can not be sent before this
+pub const RESEND_SECONDS: i64 = 60;
+// the code is given up after this many wrong guesses
+pub const MAX_ATTEMPTS: i32 = 5;
+
+fn hash_code(code: &str) -> String {
+    Sha256::digest(code).encode_hex()
+}
+
+fn channel(user: &User, username: &str) -> Channel {
+    if user.email.as_deref() == Some(username) {
+        Channel::Email
+    } else {
+        Channel::Phone
+    }
+}
+
+// Like the password reset, nothing tells whether the username exists. A code asked again too soon is not sent
+// either, silently, as a throttled answer would only be given for the existing usernames.
+pub async fn send_code<T, N>(mut tx: T, notifier: &N, username: String) -> Result<(), Error>
+where
+    T: TxStore,
+    N: Notifier,
+{
+    let user = match UserCommon::get_by_username(&mut tx, username.clone()).await? {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+    let channel = channel(&user, &username);
+    if let Some(latest) = VerificationCommon::latest(&mut tx, user.id, channel).await? {
+        if Utc::now() < latest.created_at + Duration::seconds(RESEND_SECONDS) {
+            return Ok(());
+        }
+        VerificationCommon::mark_used(&mut tx, latest.id).await?;
+    }
+    let code = format!("{:06}", thread_rng().gen_range(0..1_000_000));
+    VerificationCommon::insert(
+        &mut tx,
+        VerificationInsert {
+            user_id: user.id,
+            channel,
+            target: username.clone(),
+            code_hash: hash_code(&code),
+            expires_at: Utc::now() + Duration::minutes(CODE_MINUTES),
+        },
+    )
+    .await?;
+    // the code is stored before it is sent, a failed delivery can be retried once the resend delay has passed
+    tx.commit().await?;
+    notifier
+        .notify(Notification {
+            channel,
+            to: username,
+            subject: "Verification code".into(),
+            body: format!("Your verification code is {}, it expires in {} minutes.", code, CODE_MINUTES),
+        })
+        .await?;
+    Ok(())
+}
+
+pub async fn verify_code<T>(mut tx: T, username: String, code: &str) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    let invalid = || Error::BusinessError("invalid or expired verification code".into());
+    let user = UserCommon::get_by_username(&mut tx, username.clone()).await?.ok_or_else(invalid)?;
+    let channel = channel(&user, &username);
+    let verification = match VerificationCommon::latest(&mut tx, user.id, channel).await? {
+        // the code is for the identifier it has been sent to, which may have been changed since
+        Some(verification) if verification.is_valid(Utc::now(), MAX_ATTEMPTS) && verification.target == username => verification,
+        _ => return Err(invalid()),
+    };
+    if verification.code_hash != hash_code(code) {
+        VerificationCommon::increase_attempts(&mut tx, verification.id).await?;
+        tx.commit().await?;
+        return Err(invalid());
+    }
+    VerificationCommon::mark_used(&mut tx, verification.id).await?;
+    UserCommon::set_verified(&mut tx, user.id, channel).await?;
+    tx.commit().await?;
+    Ok(())
+}
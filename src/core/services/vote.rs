@@ -1,51 +1,78 @@
 use crate::core::models::vote::{FavoriteVote, FavoriteVoteQuery};
-use crate::core::ports::repository::{OptionCommon, QuestionCommon, QuestionReadMarkCommon, Store, TxStore, VoteCommon, VoteReadMarkCommon};
+use crate::core::ports::repository::{OptionCommon, QuestionCommon, QuestionReadMarkCommon, Store, TxStore, VoteCommon, VoteReadMarkCommon, VoteWhiteListCommon};
+use crate::core::services::answer::{answer_inserts, check_required, check_selections, respondent, value_insert};
+use crate::core::services::permission::{authorize, authorize_vote};
+use crate::core::services::question::questions_with_in_vote;
+use crate::core::tally::{irv, schulze};
 use crate::core::{
     models::{
-        answer::{Answer, Insert as AnswerInsert, Submit},
+        answer::{Answer, Insert as AnswerInsert, Query as AnswerQuery, Respondent, Source, Submit},
         common::Pagination,
         option::Insert as OptionInsert,
-        question::{Insert as QuestionInsert, QuestionType, ReadMarkInsert as QuestionReadMarkInsert},
-        vote::{Insert as VoteInsert, Query as DBVoteQuery, ReadMarkInsert as VoteReadMarkInsert, Vote, VoteCreate, VoteQuery, VoteVisibility},
+        question::{Insert as QuestionInsert, QuestionType, ReadMarkInsert as QuestionReadMarkInsert, TallyMethod},
+        role::Permission,
+        vote::{Insert as VoteInsert, Query as DBVoteQuery, ReadMarkInsert as VoteReadMarkInsert, Submission, Vote, VoteCreate, VoteQuery, VoteStatus, VoteVisibility, WhiteListed},
     },
     ports::repository::AnswerCommon,
 };
 use crate::error::Error;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+// the longest grace period in days a vote can be given
+pub const MAX_GRACE_PERIOD: i32 = 365;
+
+fn check_grace_period(grace_period: i32) -> Result<(), Error> {
+    if grace_period < 0 {
+        return Err(Error::BusinessError("grace period can not be negative".into()));
+    }
+    if grace_period > MAX_GRACE_PERIOD {
+        return Err(Error::BusinessError(format!("grace period can not be longer than {MAX_GRACE_PERIOD} days")));
+    }
+    Ok(())
+}
 
 pub async fn create_vote<T>(mut storer: T, uid: i32, vote: VoteCreate) -> Result<i32, Error>
 where
     T: TxStore,
 {
+    check_grace_period(vote.grace_period)?;
+    authorize(&mut storer, vote.organization_id, uid, Permission::CreateVote).await?;
     // 创建投票
     let vote_id = VoteCommon::insert(
         &mut storer,
         VoteInsert {
             name: vote.name,
             deadline: vote.deadline,
-            visibility: match vote.visibility {
-                VoteVisibility::Public => "Public".into(),
-                VoteVisibility::Organization => "Organization".into(),
-                VoteVisibility::WhiteList => "WhiteList".into(),
-            },
+            visibility: vote.visibility.as_str().into(),
             organization_id: vote.organization_id,
+            status: if vote.draft { VoteStatus::Draft } else { VoteStatus::Open },
+            grace_period: vote.grace_period,
+            anonymous: vote.anonymous,
         },
     )
     .await?;
     // 创建投票阅读标记
     VoteReadMarkCommon::insert(&mut storer, VoteReadMarkInsert { vote_id, user_id: uid, version: 1 }).await?;
     for q in vote.questions {
+        let (min_selections, max_selections) = q.type_.selection_bounds(q.min_selections, q.max_selections, q.required)?;
+        if !q.type_.is_option_based() && !q.options.is_empty() {
+            return Err(Error::BusinessError(format!("{} question can not have options", q.type_.as_str().to_lowercase())));
+        }
         // 创建问题
         let qst_id = QuestionCommon::insert(
             &mut storer,
             uid,
             QuestionInsert {
                 description: q.description,
-                type_: match q.type_ {
-                    QuestionType::Multi => "Multi".into(),
-                    QuestionType::Single => "Single".into(),
-                },
+                type_: q.type_.as_str().into(),
                 version: 1,
                 vote_id,
+                min_selections,
+                max_selections,
+                required: q.required,
+                tally_method: q.tally_method.as_str().into(),
+                number_range: q.type_.number_range(q.number_range)?,
             },
         )
         .await?;
@@ -109,19 +136,255 @@ where
     Ok(vote)
 }
 
-pub async fn submit_answers<D>(db: &mut D, uid: i32, id: i32, answers: Vec<Submit>) -> Result<(), Error>
+// returns whether the submission is a late one
+pub async fn check_submission<S>(store: &mut S, vote_id: i32) -> Result<bool, Error>
+where
+    S: Store,
+{
+    let window = VoteCommon::get_submission_window(store, vote_id).await?;
+    match window.check(chrono::Local::now().date_naive()) {
+        Submission::OnTime => Ok(false),
+        Submission::Late => Ok(true),
+        Submission::Rejected => Err(Error::VoteClosed(vote_id)),
+    }
+}
+
+pub async fn ensure_editable<S>(store: &mut S, vote_id: i32) -> Result<(), Error>
+where
+    S: Store,
+{
+    let status = VoteCommon::get_status(store, vote_id).await?;
+    if !status.is_editable() {
+        return Err(Error::BusinessError(format!("vote can not be modified(status: {})", status.as_str())));
+    }
+    Ok(())
+}
+
+// `from` is the status the vote must be in, when the same target can be reached from several ones
+async fn transit<T>(mut tx: T, uid: i32, id: i32, from: Option<VoteStatus>, to: VoteStatus) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, id).await?;
+    let status = VoteCommon::get_status_for_update(&mut tx, id).await?;
+    if from.map(|from| from != status).unwrap_or(false) || !status.can_transit_to(to) {
+        return Err(Error::BusinessError(format!("can not change vote status from {} to {}", status.as_str(), to.as_str())));
+    }
+    VoteCommon::update_status(&mut tx, id, to).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn publish_vote<T>(tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    transit(tx, uid, id, Some(VoteStatus::Draft), VoteStatus::Open).await
+}
+
+pub async fn close_vote<T>(tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    transit(tx, uid, id, None, VoteStatus::Closed).await
+}
+
+pub async fn reopen_vote<T>(tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    transit(tx, uid, id, Some(VoteStatus::Closed), VoteStatus::Open).await
+}
+
+pub async fn archive_vote<T>(tx: T, uid: i32, id: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    transit(tx, uid, id, None, VoteStatus::Archived).await
+}
+
+pub async fn set_grace_period<T>(mut tx: T, uid: i32, id: i32, grace_period: i32) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    check_grace_period(grace_period)?;
+    ensure_manager(&mut tx, uid, id).await?;
+    VoteCommon::update_grace_period(&mut tx, id, grace_period).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// the vote is managed by the roles which are allowed to manage the votes of its organization
+pub async fn ensure_manager<S>(store: &mut S, uid: i32, id: i32) -> Result<(), Error>
+where
+    S: Store,
+{
+    authorize_vote(store, id, uid, Permission::ManageVote).await?;
+    Ok(())
+}
+
+pub async fn update_vote<T>(mut tx: T, uid: i32, id: i32, name: String, deadline: Option<NaiveDate>) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    authorize_vote(&mut tx, id, uid, Permission::EditVote).await?;
+    let updated = VoteCommon::update(&mut tx, id, name, deadline).await?;
+    tx.commit().await?;
+    Ok(updated)
+}
+
+pub async fn delete_vote<T>(mut tx: T, uid: i32, id: i32) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, id).await?;
+    let deleted = VoteCommon::delete(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+pub async fn set_visibility<T>(mut tx: T, uid: i32, id: i32, visibility: VoteVisibility) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, id).await?;
+    VoteCommon::update_visibility(&mut tx, id, visibility.as_str().into()).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn whitelist<S>(store: &mut S, uid: i32, id: i32, page: i64, size: i64) -> Result<(Vec<WhiteListed>, i64), Error>
+where
+    S: Store,
+{
+    let pagination = Pagination::page(page, size)?;
+    ensure_manager(store, uid, id).await?;
+    let total = VoteWhiteListCommon::count(store, id).await?;
+    let list = VoteWhiteListCommon::query(store, id, Some(pagination)).await?;
+    Ok((list, total))
+}
+
+pub async fn add_to_whitelist<T>(mut tx: T, uid: i32, id: i32, user_ids: Vec<i32>) -> Result<(), Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, id).await?;
+    VoteWhiteListCommon::insert(&mut tx, id, user_ids).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn remove_from_whitelist<T>(mut tx: T, uid: i32, id: i32, user_id: i32) -> Result<i32, Error>
+where
+    T: TxStore,
+{
+    ensure_manager(&mut tx, uid, id).await?;
+    let deleted = VoteWhiteListCommon::delete(&mut tx, id, user_id).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+// returns the ballot token issued by the first submission to an anonymous vote
+pub async fn submit_answers<D>(db: &mut D, uid: i32, id: i32, answers: Vec<Submit>, ballot_token: Option<String>) -> Result<Option<String>, Error>
 where
     D: Store,
 {
-    let inserts: Vec<AnswerInsert> = answers
-        .into_iter()
-        .map(|a| a.option_ids.into_iter().map(|o| AnswerInsert { user_id: uid, option_id: o }))
-        .flatten()
-        .collect();
+    let (checked, late) = check_answers(db, id, answers).await?;
+    let (respondent, issued) = respondent(db, id, uid, ballot_token).await?;
+    save_answers(db, &respondent, checked, late).await?;
+    Ok(issued)
+}
+
+// checks all the answers to the vote before any of them is stored, returns them along with the type of
+// their questions and whether they are late
+pub async fn check_answers<D>(db: &mut D, id: i32, answers: Vec<Submit>) -> Result<(Vec<(Submit, QuestionType)>, bool), Error>
+where
+    D: Store,
+{
+    let late = check_submission(db, id).await?;
+    let question_ids = answers.iter().map(|a| a.question_id).collect();
+    if !QuestionCommon::is_belongs_to_vote(db, id, question_ids).await? {
+        return Err(Error::BusinessError("questions not belongs to exactly one vote".into()));
+    }
+    check_required(db, id, &answers).await?;
+    let mut checked = Vec::new();
+    for a in answers {
+        let type_ = check_selections(db, &a).await?;
+        if !OptionCommon::is_belongs_to_question(db, a.question_id, a.selected_option_ids()).await? {
+            return Err(Error::BusinessError(format!("options not belongs to the question(id: {})", a.question_id)));
+        }
+        checked.push((a, type_));
+    }
+    Ok((checked, late))
+}
+
+pub async fn save_answers<D>(db: &mut D, respondent: &Respondent, checked: Vec<(Submit, QuestionType)>, late: bool) -> Result<(), Error>
+where
+    D: Store,
+{
+    let mut inserts: Vec<AnswerInsert> = Vec::new();
+    for (a, type_) in checked {
+        // a new submission replaces the previous answers of the respondent to the question
+        let query = AnswerQuery {
+            question_id_eq: Some(a.question_id),
+            user_id_eq: respondent.user_id(),
+            ballot_eq: respondent.ballot(),
+            guest_id_eq: respondent.guest_id(),
+        };
+        AnswerCommon::delete(db, query.clone()).await?;
+        AnswerCommon::delete_values(db, query).await?;
+        if let Some(value) = value_insert(respondent, &a, late) {
+            AnswerCommon::upsert_value(db, value).await?;
+            continue;
+        }
+        inserts.extend(answer_inserts(respondent, a, &type_, late));
+    }
     AnswerCommon::bulk_insert(db, inserts).await?;
     Ok(())
 }
 
+#[derive(Debug, Serialize)]
+pub struct RankedReport {
+    pub question_id: i32,
+    pub question: String,
+    // the method chosen by the creator, `winners` is taken from its result
+    pub method: TallyMethod,
+    pub winners: Vec<i32>,
+    pub instant_runoff: irv::Outcome,
+    pub schulze: schulze::Outcome,
+}
+
+// tally results of all ranked questions in the vote
+pub async fn ranked_reports<D>(db: &mut D, uid: i32, id: i32, source: Source) -> Result<Vec<RankedReport>, Error>
+where
+    D: Store,
+{
+    let (questions, _) = questions_with_in_vote(db, uid, id).await?;
+    let mut reports = Vec::new();
+    for q in questions {
+        if !matches!(QuestionType::try_from(q.type_.as_str()), Ok(QuestionType::Ranked)) {
+            continue;
+        }
+        let method = TallyMethod::try_from(q.tally_method.as_str())?;
+        let ballots = AnswerCommon::ranked_ballots(db, q.id, source).await?;
+        let candidates: Vec<i32> = q.options.iter().map(|o| o.id).collect();
+        let instant_runoff = irv::instant_runoff(&candidates, &ballots);
+        let schulze = schulze::schulze(&candidates, &ballots);
+        reports.push(RankedReport {
+            question_id: q.id,
+            question: q.description,
+            method,
+            winners: match method {
+                TallyMethod::InstantRunoff => instant_runoff.winners.clone(),
+                TallyMethod::Schulze => schulze.winners.clone(),
+            },
+            instant_runoff,
+            schulze,
+        });
+    }
+    Ok(reports)
+}
+
 async fn has_already_ranked<D>(db: &mut D, user_id: i32, vote_id: i32) -> Result<bool, Error>
 where
     D: Store,
@@ -159,3 +422,16 @@ where
     VoteCommon::insert_favorite(db, FavoriteVote { user_id, vote_id, attitude: -1 }).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grace_period() {
+        assert!(check_grace_period(0).is_ok());
+        assert!(check_grace_period(MAX_GRACE_PERIOD).is_ok());
+        assert!(check_grace_period(-1).is_err());
+        assert!(check_grace_period(MAX_GRACE_PERIOD + 1).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tally {
+    pub candidate: i32,
+    pub votes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Round {
+    // ordered by votes descending
+    pub tallies: Vec<Tally>,
+    // ballots which have no remaining candidate in this round
+    pub exhausted: usize,
+    pub eliminated: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Outcome {
+    pub rounds: Vec<Round>,
+    // more than one winner means the remaining candidates are tied, empty means there is no valid ballot
+    pub winners: Vec<i32>,
+}
+
+// Instant-runoff voting. Each ballot lists candidates from the most preferred to the least preferred one.
+// In every round a ballot counts for its highest ranked remaining candidate, a candidate with more than
+// half of the continuing ballots wins, otherwise all candidates with the fewest votes are eliminated.
+pub fn instant_runoff(candidates: &[i32], ballots: &[Vec<i32>]) -> Outcome {
+    let mut remaining: Vec<i32> = candidates.to_vec();
+    remaining.sort();
+    remaining.dedup();
+    let mut rounds = Vec::new();
+    loop {
+        let mut counts: BTreeMap<i32, usize> = remaining.iter().map(|c| (*c, 0)).collect();
+        let mut exhausted = 0;
+        for ballot in ballots {
+            match ballot.iter().find(|c| counts.contains_key(c)) {
+                Some(c) => *counts.entry(*c).or_default() += 1,
+                None => exhausted += 1,
+            }
+        }
+        let mut tallies: Vec<Tally> = counts.into_iter().map(|(candidate, votes)| Tally { candidate, votes }).collect();
+        tallies.sort_by(|a, b| b.votes.cmp(&a.votes).then(a.candidate.cmp(&b.candidate)));
+        let continuing = ballots.len() - exhausted;
+        if tallies.is_empty() || continuing == 0 {
+            rounds.push(Round {
+                tallies,
+                exhausted,
+                eliminated: Vec::new(),
+            });
+            return Outcome { rounds, winners: Vec::new() };
+        }
+        let top = &tallies[0];
+        if top.votes * 2 > continuing {
+            let winners = vec![top.candidate];
+            rounds.push(Round {
+                tallies,
+                exhausted,
+                eliminated: Vec::new(),
+            });
+            return Outcome { rounds, winners };
+        }
+        let fewest = tallies.last().map(|t| t.votes).unwrap_or_default();
+        let losers: Vec<i32> = tallies.iter().filter(|t| t.votes == fewest).map(|t| t.candidate).collect();
+        if losers.len() == remaining.len() {
+            rounds.push(Round {
+                tallies,
+                exhausted,
+                eliminated: Vec::new(),
+            });
+            return Outcome { rounds, winners: losers };
+        }
+        remaining.retain(|c| !losers.contains(c));
+        rounds.push(Round {
+            tallies,
+            exhausted,
+            eliminated: losers,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_majority_in_first_round() {
+        let ballots = vec![vec![1, 2], vec![1, 3], vec![2, 1]];
+        let outcome = instant_runoff(&[1, 2, 3], &ballots);
+        assert_eq!(outcome.winners, vec![1]);
+        assert_eq!(outcome.rounds.len(), 1);
+    }
+
+    #[test]
+    fn test_transfer_after_elimination() {
+        // 3 is eliminated first and its ballots move to 2
+        let mut ballots = vec![vec![1, 2]; 4];
+        ballots.extend(vec![vec![2, 1]; 3]);
+        ballots.extend(vec![vec![3, 2]; 2]);
+        let outcome = instant_runoff(&[1, 2, 3], &ballots);
+        assert_eq!(outcome.rounds[0].eliminated, vec![3]);
+        assert_eq!(outcome.winners, vec![2]);
+        assert_eq!(outcome.rounds.len(), 2);
+    }
+
+    #[test]
+    fn test_exhausted_ballots_and_tie() {
+        let ballots = vec![vec![1], vec![2], vec![3]];
+        let outcome = instant_runoff(&[1, 2, 3], &ballots);
+        assert_eq!(outcome.winners, vec![1, 2, 3]);
+        let outcome = instant_runoff(&[1, 2], &[]);
+        assert!(outcome.winners.is_empty());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod irv;
+pub mod numeric;
+pub mod rating;
+pub mod schulze;
@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    // a bucket covers [lower, upper), the last one also includes upper
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub count: usize,
+    // none when there is no value
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub histogram: Vec<Bucket>,
+}
+
+// Summarizes numeric answers, the histogram splits [min, max] of the values into at most `buckets` equal width buckets.
+pub fn summarize(values: &[f64], buckets: usize) -> Summary {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let count = sorted.len();
+    if count == 0 {
+        return Summary {
+            count,
+            mean: None,
+            median: None,
+            min: None,
+            max: None,
+            histogram: Vec::new(),
+        };
+    }
+    let mean = sorted.iter().sum::<f64>() / count as f64;
+    let median = if count % 2 == 1 { sorted[count / 2] } else { (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0 };
+    let (min, max) = (sorted[0], sorted[count - 1]);
+    Summary {
+        count,
+        mean: Some(mean),
+        median: Some(median),
+        min: Some(min),
+        max: Some(max),
+        histogram: histogram(&sorted, min, max, buckets),
+    }
+}
+
+fn histogram(sorted: &[f64], min: f64, max: f64, buckets: usize) -> Vec<Bucket> {
+    if max == min || buckets <= 1 {
+        return vec![Bucket {
+            lower: min,
+            upper: max,
+            count: sorted.len(),
+        }];
+    }
+    let width = (max - min) / buckets as f64;
+    let mut histogram: Vec<Bucket> = (0..buckets)
+        .map(|i| Bucket {
+            lower: min + width * i as f64,
+            upper: if i + 1 == buckets { max } else { min + width * (i + 1) as f64 },
+            count: 0,
+        })
+        .collect();
+    for v in sorted {
+        let i = (((v - min) / width) as usize).min(buckets - 1);
+        histogram[i].count += 1;
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[4.0, 1.0, 3.0, 2.0], 3);
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.mean, Some(2.5));
+        assert_eq!(summary.median, Some(2.5));
+        assert_eq!(summary.min, Some(1.0));
+        assert_eq!(summary.max, Some(4.0));
+        assert_eq!(summary.histogram.iter().map(|b| b.count).collect::<Vec<_>>(), vec![1, 1, 2]);
+        assert_eq!(summary.histogram.last().unwrap().upper, 4.0);
+
+        let summary = summarize(&[5.0, 1.0, 9.0], 4);
+        assert_eq!(summary.median, Some(5.0));
+        assert_eq!(summary.histogram.iter().map(|b| b.count).sum::<usize>(), 3);
+    }
+
+    #[test]
+    fn test_summarize_degenerate() {
+        let summary = summarize(&[], 10);
+        assert_eq!(summary.count, 0);
+        assert_eq!(summary.mean, None);
+        assert!(summary.histogram.is_empty());
+
+        let summary = summarize(&[7.0, 7.0], 10);
+        assert_eq!(summary.histogram, vec![Bucket { lower: 7.0, upper: 7.0, count: 2 }]);
+    }
+}
@@ -0,0 +1,70 @@
+use serde::Serialize;
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Distribution {
+    pub rating: i32,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub count: usize,
+    // none when there is no rating
+    pub mean: Option<f64>,
+    // every point of the scale is listed, including the ones nobody chose
+    pub distribution: Vec<Distribution>,
+    // only for NPS questions, in the range of -100 to 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nps: Option<f64>,
+}
+
+// Summarizes ratings on a scale, ratings out of the scale are ignored.
+pub fn summarize(scale: RangeInclusive<i32>, ratings: &[i32]) -> Summary {
+    let ratings: Vec<i32> = ratings.iter().copied().filter(|r| scale.contains(r)).collect();
+    let count = ratings.len();
+    let mean = if count == 0 { None } else { Some(ratings.iter().map(|r| *r as f64).sum::<f64>() / count as f64) };
+    let distribution = scale
+        .map(|rating| Distribution {
+            rating,
+            count: ratings.iter().filter(|r| **r == rating).count(),
+        })
+        .collect();
+    Summary { count, mean, distribution, nps: None }
+}
+
+// Net Promoter Score: the percentage of promoters (9-10) minus the percentage of detractors (0-6).
+pub fn net_promoter_score(ratings: &[i32]) -> Option<f64> {
+    let ratings: Vec<i32> = ratings.iter().copied().filter(|r| (0..=10).contains(r)).collect();
+    if ratings.is_empty() {
+        return None;
+    }
+    let promoters = ratings.iter().filter(|r| **r >= 9).count() as f64;
+    let detractors = ratings.iter().filter(|r| **r <= 6).count() as f64;
+    Some((promoters - detractors) / ratings.len() as f64 * 100.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(1..=5, &[1, 2, 2, 5, 7]);
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.mean, Some(2.5));
+        assert_eq!(summary.distribution.iter().map(|d| d.count).collect::<Vec<_>>(), vec![1, 2, 0, 0, 1]);
+
+        let summary = summarize(1..=5, &[]);
+        assert_eq!(summary.mean, None);
+        assert_eq!(summary.distribution.len(), 5);
+    }
+
+    #[test]
+    fn test_net_promoter_score() {
+        // 2 promoters, 1 passive, 1 detractor
+        assert_eq!(net_promoter_score(&[10, 9, 7, 3]), Some(25.0));
+        assert_eq!(net_promoter_score(&[0, 6]), Some(-100.0));
+        assert_eq!(net_promoter_score(&[]), None);
+    }
+}
@@ -0,0 +1,195 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Outcome {
+    // sorted candidates, the matrices below are indexed in this order
+    pub candidates: Vec<i32>,
+    // pairwise[i][j] is the number of ballots preferring candidates[i] over candidates[j]
+    pub pairwise: Vec<Vec<usize>>,
+    // strength of the strongest path from candidates[i] to candidates[j]
+    pub strongest_paths: Vec<Vec<usize>>,
+    // from the best to the worst, candidates in the same group are tied
+    pub ranking: Vec<Vec<i32>>,
+    // more than one winner means a tie, empty means there is no ballot
+    pub winners: Vec<i32>,
+}
+
+// Schulze (beatpath) method. Each ballot lists candidates from the most preferred to the least preferred one,
+// candidates missing from a ballot are considered equally less preferred than all listed ones.
+pub fn schulze(candidates: &[i32], ballots: &[Vec<i32>]) -> Outcome {
+    let mut candidates = candidates.to_vec();
+    candidates.sort();
+    candidates.dedup();
+    let n = candidates.len();
+
+    let mut pairwise = vec![vec![0; n]; n];
+    for ballot in ballots {
+        let mut ranks = vec![usize::MAX; n];
+        for (pos, c) in ballot.iter().enumerate() {
+            if let Ok(i) = candidates.binary_search(c) {
+                ranks[i] = ranks[i].min(pos);
+            }
+        }
+        for i in 0..n {
+            for j in 0..n {
+                if ranks[i] < ranks[j] {
+                    pairwise[i][j] += 1;
+                }
+            }
+        }
+    }
+
+    let mut paths = vec![vec![0; n]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && pairwise[i][j] > pairwise[j][i] {
+                paths[i][j] = pairwise[i][j];
+            }
+        }
+    }
+    for k in 0..n {
+        for i in 0..n {
+            if i == k {
+                continue;
+            }
+            for j in 0..n {
+                if j == i || j == k {
+                    continue;
+                }
+                paths[i][j] = paths[i][j].max(paths[i][k].min(paths[k][j]));
+            }
+        }
+    }
+
+    // the beats relation of the strongest paths is transitive, so peeling off the unbeaten candidates always terminates
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut ranking = Vec::new();
+    while !remaining.is_empty() {
+        let (top, rest): (Vec<usize>, Vec<usize>) = remaining.iter().partition(|&&i| !remaining.iter().any(|&j| paths[j][i] > paths[i][j]));
+        ranking.push(top.iter().map(|&i| candidates[i]).collect::<Vec<i32>>());
+        remaining = rest;
+    }
+    let winners = match (ballots.is_empty(), ranking.first()) {
+        (false, Some(first)) => first.clone(),
+        _ => Vec::new(),
+    };
+
+    Outcome {
+        candidates,
+        pairwise,
+        strongest_paths: paths,
+        ranking,
+        winners,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    fn ballots(groups: &[(usize, &[i32])]) -> Vec<Vec<i32>> {
+        groups.iter().flat_map(|(count, ballot)| vec![ballot.to_vec(); *count]).collect()
+    }
+
+    #[test]
+    fn test_wikipedia_example() {
+        // https://en.wikipedia.org/wiki/Schulze_method#Example, A to E are 1 to 5
+        let ballots = ballots(&[
+            (5, &[1, 3, 2, 5, 4]),
+            (5, &[1, 4, 5, 3, 2]),
+            (8, &[2, 5, 4, 1, 3]),
+            (3, &[3, 1, 2, 5, 4]),
+            (7, &[3, 1, 5, 2, 4]),
+            (2, &[3, 2, 1, 4, 5]),
+            (7, &[4, 3, 5, 2, 1]),
+            (8, &[5, 2, 1, 4, 3]),
+        ]);
+        let outcome = schulze(&[1, 2, 3, 4, 5], &ballots);
+        assert_eq!(outcome.pairwise[0], vec![0, 20, 26, 30, 22]);
+        assert_eq!(outcome.strongest_paths[0], vec![0, 28, 28, 30, 24]);
+        assert_eq!(outcome.strongest_paths[4], vec![25, 28, 28, 31, 0]);
+        assert_eq!(outcome.ranking, vec![vec![5], vec![1], vec![3], vec![2], vec![4]]);
+        assert_eq!(outcome.winners, vec![5]);
+    }
+
+    #[test]
+    fn test_tennessee_capital() {
+        // Memphis, Nashville, Chattanooga and Knoxville are 1 to 4
+        let ballots = ballots(&[(42, &[1, 2, 3, 4]), (26, &[2, 3, 4, 1]), (15, &[3, 4, 2, 1]), (17, &[4, 3, 2, 1])]);
+        let outcome = schulze(&[1, 2, 3, 4], &ballots);
+        assert_eq!(outcome.ranking, vec![vec![2], vec![3], vec![4], vec![1]]);
+    }
+
+    #[test]
+    fn test_ties() {
+        let outcome = schulze(&[1, 2], &ballots(&[(1, &[1, 2]), (1, &[2, 1])]));
+        assert_eq!(outcome.winners, vec![1, 2]);
+        let outcome = schulze(&[1, 2, 3], &[]);
+        assert!(outcome.winners.is_empty());
+        assert_eq!(outcome.ranking, vec![vec![1, 2, 3]]);
+    }
+
+    fn random_election(rng: &mut StdRng) -> (Vec<i32>, Vec<Vec<i32>>) {
+        let candidates: Vec<i32> = (1..=rng.gen_range(2..7)).collect();
+        let ballots = (0..rng.gen_range(1..40))
+            .map(|_| {
+                let mut ballot = candidates.clone();
+                ballot.shuffle(rng);
+                ballot.truncate(rng.gen_range(1..=candidates.len()));
+                ballot
+            })
+            .collect();
+        (candidates, ballots)
+    }
+
+    #[test]
+    fn test_properties_on_random_elections() {
+        let mut rng = StdRng::seed_from_u64(20230801);
+        for _ in 0..500 {
+            let (candidates, ballots) = random_election(&mut rng);
+            let outcome = schulze(&candidates, &ballots);
+            let n = candidates.len();
+            let p = &outcome.strongest_paths;
+            let d = &outcome.pairwise;
+
+            // the ranking is a partition of all candidates
+            let mut ranked: Vec<i32> = outcome.ranking.concat();
+            ranked.sort();
+            assert_eq!(ranked, candidates);
+
+            for i in 0..n {
+                for j in 0..n {
+                    // a path is at least as strong as the direct win
+                    if i != j && d[i][j] > d[j][i] {
+                        assert!(p[i][j] >= d[i][j]);
+                    }
+                    // the beats relation is transitive
+                    for k in 0..n {
+                        if p[i][j] > p[j][i] && p[j][k] > p[k][j] {
+                            assert!(p[i][k] > p[k][i]);
+                        }
+                    }
+                }
+            }
+
+            // a Condorcet winner is always the only Schulze winner
+            if let Some(w) = (0..n).find(|&i| (0..n).all(|j| i == j || d[i][j] > d[j][i])) {
+                assert_eq!(outcome.winners, vec![candidates[w]]);
+            }
+
+            // nobody in a group is beaten by a candidate in the same or a later group
+            for (g, group) in outcome.ranking.iter().enumerate() {
+                for a in group {
+                    let i = candidates.binary_search(a).unwrap();
+                    for b in outcome.ranking[g..].concat() {
+                        let j = candidates.binary_search(&b).unwrap();
+                        assert!(p[j][i] <= p[i][j]);
+                    }
+                }
+            }
+        }
+    }
+}
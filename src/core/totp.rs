@@ -0,0 +1,106 @@
+// Time-based one-time passwords as in RFC 6238, compatible with the common authenticator apps: HMAC-SHA1, 6 digits
+// and a 30 seconds step.
+use base32::Alphabet;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, Rng};
+use sha1::Sha1;
+
+pub const STEP: i64 = 30;
+pub const DIGITS: u32 = 6;
+// the codes of the adjacent steps are accepted too, for the clocks drifting apart
+pub const SKEW: i64 = 1;
+
+const ALPHABET: Alphabet = Alphabet::RFC4648 { padding: false };
+
+// a base32 encoded secret of 160 bits
+pub fn generate_secret() -> String {
+    base32::encode(ALPHABET, &thread_rng().gen::<[u8; 20]>())
+}
+
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        encode(issuer),
+        encode(account),
+        secret,
+        encode(issuer),
+        DIGITS,
+        STEP
+    )
+}
+
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn counter(timestamp: i64) -> i64 {
+    timestamp / STEP
+}
+
+fn code_of_key(key: &[u8], counter: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("hmac accepts keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+    format!("{:0width$}", binary % 10_u32.pow(DIGITS), width = DIGITS as usize)
+}
+
+pub fn code(secret: &str, counter: i64) -> Option<String> {
+    let key = base32::decode(ALPHABET, secret)?;
+    Some(code_of_key(&key, counter))
+}
+
+// Returns the counter the code matches, so that a code can be refused once its counter has been used.
+pub fn verify(secret: &str, code: &str, timestamp: i64) -> Option<i64> {
+    let key = base32::decode(ALPHABET, secret)?;
+    let current = counter(timestamp);
+    (current - SKEW..=current + SKEW).find(|&c| code_of_key(&key, c) == code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the SHA1 secret of the test vectors in RFC 6238, "12345678901234567890"
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        assert_eq!(code(SECRET, counter(59)).unwrap(), "287082");
+        assert_eq!(code(SECRET, counter(1111111109)).unwrap(), "081804");
+        assert_eq!(code(SECRET, counter(1234567890)).unwrap(), "005924");
+        assert_eq!(code(SECRET, counter(2000000000)).unwrap(), "279037");
+    }
+
+    #[test]
+    fn test_verify() {
+        assert_eq!(verify(SECRET, "287082", 59), Some(1));
+        assert_eq!(verify(SECRET, "287082", 59 + STEP), Some(1));
+        assert_eq!(verify(SECRET, "287082", 59 + 2 * STEP), None);
+        assert_eq!(verify(SECRET, "000000", 59), None);
+        assert_eq!(verify("not base32!", "287082", 59), None);
+    }
+
+    #[test]
+    fn test_generated_secret() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        let now = 1692345678;
+        let code = code(&secret, counter(now)).unwrap();
+        assert_eq!(verify(&secret, &code, now), Some(counter(now)));
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        assert_eq!(
+            otpauth_uri("juju", "a b@example.com", "ABC"),
+            "otpauth://totp/juju:a%20b%40example.com?secret=ABC&issuer=juju&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
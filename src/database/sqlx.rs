@@ -1,21 +1,35 @@
 use crate::core::models::{
-    answer::{Insert as AnswerInsert, Query as AnswerQuery},
+    answer::{DateCount, Insert as AnswerInsert, Query as AnswerQuery, Respondent, Source, TextAnswer, Value as AnswerValue, ValueInsert},
+    application::{ApplicationStatus, JoinApplication, Query as ApplicationQuery},
     common::Pagination,
+    group::{GroupMember, MemberGroup},
+    identity::{OidcLogin, OidcLoginInsert},
+    invite_code::{Insert as InviteCodeInsert, InviteCode},
     option::{Insert as OptionInsert, Opt, Query as OptionQuery},
     organization::{Insert as OrganizationInsert, Organization, OrganizationWithVoteInfo, Query as OrganizationQuery, Update as OrganizationUpdate},
+    password_reset::{Insert as PasswordResetInsert, PasswordReset},
+    personal_token::{Insert as PersonalTokenInsert, PersonalToken},
     question::{
-        FavoriteQuestion, FavoriteQuestionQuery, Insert as QuestionInsert, Query as QuestionQuery, Question, ReadMarkInsert as QuestionReadMarkInsert, ReadMarkUpdate as QuestionReadMarkUpdate,
+        FavoriteQuestion, FavoriteQuestionQuery, Insert as QuestionInsert, NumberRange, Query as QuestionQuery, Question, QuestionType, ReadMarkInsert as QuestionReadMarkInsert,
+        ReadMarkUpdate as QuestionReadMarkUpdate, SelectionConstraint,
     },
-    user::{Patch as UserPath, User},
-    vote::{FavoriteVote, FavoriteVoteQuery, Insert as VoteInsert, Query as VoteQuery, ReadMarkInsert as VoteReadMarkInsert, Vote, VoteRow},
+    role::Role,
+    session::{Client, Insert as SessionInsert, Session},
+    share_link::{Insert as ShareLinkInsert, ShareLink},
+    two_factor::{Challenge, ChallengeInsert, TwoFactor},
+    user::{Patch as UserPath, User, UserInsertion},
+    verification::{Channel, Insert as VerificationInsert, Verification},
+    vote::{FavoriteVote, FavoriteVoteQuery, Insert as VoteInsert, Query as VoteQuery, ReadMarkInsert as VoteReadMarkInsert, SubmissionWindow, Vote, VoteRow, VoteStatus, WhiteListed},
 };
 use crate::core::ports::repository::{
-    AnswerCommon, Common, Manager, OptionCommon, OrganizationCommon, QuestionCommon, QuestionReadMarkCommon, Store, TxStore, UserCommon, VoteCommon, VoteReadMarkCommon,
+    AnswerCommon, ApplicationCommon, BallotCommon, Common, IdentityCommon, InviteCodeCommon, Manager, MemberGroupCommon, OptionCommon, OrganizationCommon, PasswordResetCommon, PersonalTokenCommon,
+    QuestionCommon, QuestionReadMarkCommon, SessionCommon, ShareLinkCommon, Store, TwoFactorCommon, TxStore, UserCommon, VerificationCommon, VoteCommon, VoteReadMarkCommon, VoteWhiteListCommon,
 };
 use crate::error::Error;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
+use itertools::Itertools;
 use sqlx::pool::PoolConnection;
-use sqlx::{query, query_as, query_scalar, Executor, PgPool, Postgres, QueryBuilder, Transaction};
+use sqlx::{query, query_as, query_scalar, Executor, FromRow, PgPool, Postgres, QueryBuilder, Transaction};
 
 pub struct PgSqlx<E>
 where
@@ -31,8 +45,72 @@ where
     pub fn new(executor: E) -> Self {
         Self { executor }
     }
+
+    // for the raw queries of the handlers, so they run on the connection of the store
+    pub fn executor(&mut self) -> &mut E {
+        &mut self.executor
+    }
+}
+
+impl<E> PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    // the read mark of the user is ignored without an user
+    async fn vote(&mut self, uid: Option<i32>, id: i32) -> Result<Vote, Error> {
+        let (id, name, deadline, organization_id, version, visibility, likes, dislikes, status, has_updated, num_of_questions, grace_period, anonymous): VoteRow = query_as(
+            "
+            SELECT 
+                v.id,
+                v.name,
+                v.deadline,
+                v.organization_id,
+                v.version,
+                v.visibility,
+                v.likes,
+                v.dislikes,
+                v.status,
+                CASE WHEN v.version > COALESCE(vrm.version, 0) THEN true ELSE false END AS has_updated,
+                COUNT(q.id) AS num_of_questions,
+                v.grace_period,
+                v.anonymous
+            FROM votes AS v
+            LEFT JOIN vote_read_marks AS vrm ON v.id = vrm.vote_id AND vrm.user_id = $1
+            LEFT JOIN questions AS q ON q.vote_id = v.id
+            WHERE v.id = $2
+            GROUP BY v.id, has_updated
+            ",
+        )
+        .bind(uid)
+        .bind(id)
+        .fetch_one(&mut self.executor)
+        .await?;
+        Ok(Vote {
+            id,
+            name,
+            deadline,
+            organization_id,
+            version,
+            visibility,
+            likes,
+            dislikes,
+            status,
+            has_updated,
+            num_of_questions,
+            grace_period,
+            anonymous,
+        })
+    }
 }
 
+// the effective role of a member `m` of the organization `o`
+pub(crate) const MEMBER_ROLE: &str = "
+    CASE
+        WHEN o.owner_id = m.user_id THEN 'Owner'
+        WHEN EXISTS(SELECT * FROM organization_managers AS om WHERE om.organization_id = o.id AND om.user_id = m.user_id) THEN 'Manager'
+        ELSE m.role
+    END";
+
 impl<E> OrganizationCommon for PgSqlx<E>
 where
     for<'e> &'e mut E: Executor<'e, Database = Postgres>,
@@ -47,7 +125,7 @@ where
     }
 
     async fn insert(&mut self, data: OrganizationInsert) -> Result<i32, Error> {
-        let id = query_scalar("INSERT INTO organizations (name, version) VALUES ($1, $2, $3) RETURNING id")
+        let id = query_scalar("INSERT INTO organizations (name, version, description) VALUES ($1, $2, $3) RETURNING id")
             .bind(data.name)
             .bind(data.version)
             .bind(data.description)
@@ -125,6 +203,11 @@ where
         Ok(exists)
     }
 
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error> {
+        let exists = query_scalar("SELECT EXISTS(SELECT 1 FROM organizations WHERE id = $1)").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(exists)
+    }
+
     async fn add_user_version(&mut self, id: i32, uid: i32) -> Result<(), Error> {
         query("INSERT INTO organization_read_marks (organization_id, user_id, version) VALUES ($1, $2, 1)")
             .bind(id)
@@ -153,6 +236,41 @@ where
         Ok(())
     }
 
+    async fn remove_member(&mut self, id: i32, uid: i32) -> Result<(), Error> {
+        for stmt in [
+            "DELETE FROM organization_managers WHERE organization_id = $1 AND user_id = $2",
+            "DELETE FROM organization_read_marks WHERE organization_id = $1 AND user_id = $2",
+            // so the user can apply again
+            "DELETE FROM join_applications WHERE organization_id = $1 AND user_id = $2",
+            "DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2",
+        ] {
+            query(stmt).bind(id).bind(uid).execute(&mut self.executor).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove_manager(&mut self, id: i32, uid: i32) -> Result<(), Error> {
+        query("DELETE FROM organization_managers WHERE organization_id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn count_managers(&mut self, id: i32) -> Result<i64, Error> {
+        let count = query_scalar("SELECT COUNT(*) FROM organization_managers WHERE organization_id = $1")
+            .bind(id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count)
+    }
+
+    async fn set_owner(&mut self, id: i32, uid: i32) -> Result<(), Error> {
+        query("UPDATE organizations SET owner_id = $1 WHERE id = $2").bind(uid).bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+
     async fn get(&mut self, id: i32) -> Result<Organization, Error> {
         let org = query_as("SELECT * FROM organizations WHERE id = $1").bind(id).fetch_one(&mut self.executor).await?;
         Ok(org)
@@ -180,6 +298,70 @@ where
             .await?;
         Ok(res)
     }
+
+    async fn role(&mut self, id: i32, uid: i32) -> Result<Option<Role>, Error> {
+        let role = query_scalar(&format!(
+            "
+            SELECT {}
+            FROM organization_members AS m
+            JOIN organizations AS o ON m.organization_id = o.id
+            WHERE m.organization_id = $1 AND m.user_id = $2",
+            MEMBER_ROLE
+        ))
+        .bind(id)
+        .bind(uid)
+        .fetch_optional(&mut self.executor)
+        .await?;
+        Ok(role)
+    }
+
+    async fn set_role(&mut self, id: i32, uid: i32, role: Role) -> Result<(), Error> {
+        query("UPDATE organization_members SET role = $1 WHERE organization_id = $2 AND user_id = $3")
+            .bind(role)
+            .bind(id)
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_manager_two_factor_required(&mut self, id: i32, required: bool) -> Result<(), Error> {
+        query("UPDATE organizations SET require_manager_two_factor = $1 WHERE id = $2")
+            .bind(required)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn count_managers_without_two_factor(&mut self, id: i32) -> Result<i64, Error> {
+        let count = query_scalar(
+            "
+            SELECT COUNT(*)
+            FROM organization_managers AS m
+            LEFT JOIN two_factors AS t ON t.user_id = m.user_id AND t.enabled
+            WHERE m.organization_id = $1 AND t.user_id IS NULL",
+        )
+        .bind(id)
+        .fetch_one(&mut self.executor)
+        .await?;
+        Ok(count)
+    }
+
+    async fn manages_two_factor_required(&mut self, uid: i32) -> Result<bool, Error> {
+        let res = query_scalar(
+            "
+            SELECT EXISTS(
+                SELECT *
+                FROM organization_managers AS m
+                JOIN organizations AS o ON o.id = m.organization_id
+                WHERE m.user_id = $1 AND o.require_manager_two_factor)",
+        )
+        .bind(uid)
+        .fetch_one(&mut self.executor)
+        .await?;
+        Ok(res)
+    }
 }
 
 impl<E> UserCommon for PgSqlx<E>
@@ -191,19 +373,40 @@ where
         Ok(user)
     }
 
+    async fn insert(&mut self, user: UserInsertion) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO users (nickname, email, phone, password, salt, avatar) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id")
+            .bind(user.nickname)
+            .bind(user.email)
+            .bind(user.phone)
+            .bind(user.password)
+            .bind(user.salt)
+            .bind(user.avatar)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get_by_username(&mut self, username: String) -> Result<Option<User>, Error> {
+        let user = query_as("SELECT * FROM users WHERE phone = $1 OR email = $1").bind(username).fetch_optional(&mut self.executor).await?;
+        Ok(user)
+    }
+
     async fn get(&mut self, id: i32) -> Result<User, Error> {
         let user = query_as("SELECT * FROM users WHERE id = $1").bind(id).fetch_one(&mut self.executor).await?;
         Ok(user)
     }
 
     async fn patch(&mut self, id: i32, user: UserPath) -> Result<(), Error> {
+        // the salt belongs to the password, a new password without a salt clears the legacy one
         query(
             "UPDATE users SET 
         nickname = COALESCE($1, nickname),
         phone = COALESCE($2, phone),
         email = COALESCE($3, email),
+        phone_verified = CASE WHEN $2::VARCHAR IS NULL OR $2 = phone THEN phone_verified ELSE false END,
+        email_verified = CASE WHEN $3::VARCHAR IS NULL OR $3 = email THEN email_verified ELSE false END,
         password = COALESCE($4, password),
-        salt = COALESCE($5, salt),
+        salt = CASE WHEN $4::VARCHAR IS NULL THEN salt ELSE $5 END,
         avatar = COALESCE($6, avatar)
         WHERE id = $7",
         )
@@ -218,6 +421,24 @@ where
         .await?;
         Ok(())
     }
+
+    async fn rehash_password(&mut self, id: i32, hash: String) -> Result<(), Error> {
+        query("UPDATE users SET password = $1, salt = NULL WHERE id = $2")
+            .bind(hash)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_verified(&mut self, id: i32, channel: Channel) -> Result<(), Error> {
+        let stmt = match channel {
+            Channel::Email => "UPDATE users SET email_verified = true WHERE id = $1",
+            Channel::Phone => "UPDATE users SET phone_verified = true WHERE id = $1",
+        };
+        query(stmt).bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
 }
 
 pub struct PgSqlxManager {
@@ -245,11 +466,14 @@ where
     for<'e> &'e mut E: Executor<'e, Database = Postgres>,
 {
     async fn insert(&mut self, data: VoteInsert) -> Result<i32, Error> {
-        let id = query_scalar("INSERT INTO votes (name, deadline, organization_id, visibility) VALUES ($1, $2, $3, $4) RETURNING id")
+        let id = query_scalar("INSERT INTO votes (name, deadline, organization_id, visibility, status, grace_period, anonymous) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id")
             .bind(data.name)
             .bind(data.deadline)
             .bind(data.organization_id)
             .bind(data.visibility)
+            .bind(data.status)
+            .bind(data.grace_period)
+            .bind(data.anonymous)
             .fetch_one(&mut self.executor)
             .await?;
         Ok(id)
@@ -259,13 +483,19 @@ where
         let mut stmt = QueryBuilder::new(
             "
         SELECT COUNT(DISTINCT id)
-        FROM votes 
+        FROM votes AS v
         WHERE 1 = 1",
         );
         if let Some(oid) = query.organization_id_eq {
             stmt.push(" AND organization_id = ");
             stmt.push_bind(oid);
         }
+        // whitelist votes are only listed to the whitelisted users and the managers
+        stmt.push(" AND (v.visibility <> 'WhiteList' OR EXISTS(SELECT 1 FROM vote_whitelists AS w WHERE w.vote_id = v.id AND w.user_id = ");
+        stmt.push_bind(query.uid);
+        stmt.push(") OR EXISTS(SELECT 1 FROM organization_managers AS om WHERE om.organization_id = v.organization_id AND om.user_id = ");
+        stmt.push_bind(query.uid);
+        stmt.push("))");
         let (n,) = stmt.build_query_as().fetch_one(&mut self.executor).await?;
         Ok(n)
     }
@@ -273,15 +503,29 @@ where
     async fn query(&mut self, query: &VoteQuery, pagination: Option<Pagination>) -> Result<Vec<Vote>, Error> {
         let mut stmt = QueryBuilder::new(
             "SELECT
-            v.*,
-            CASE WHEN v.deadline < CURRENT_DATE THEN 'Exprired' ELSE 'Active' END AS status,
+            v.id,
+            v.name,
+            v.deadline,
+            v.organization_id,
+            v.version,
+            v.visibility,
+            v.likes,
+            v.dislikes,
+            v.status,
             CASE WHEN v.version > COALESCE(vrm.version, 0) THEN true ELSE false END AS has_updated,
-            COUNT(q.id) AS num_of_questions
+            COUNT(q.id) AS num_of_questions,
+            v.grace_period,
+            v.anonymous
         FROM votes AS v
         LEFT JOIN vote_read_marks AS vrm ON v.id = vrm.vote_id AND vrm.user_id = $1
         LEFT JOIN questions AS q ON q.vote_id = v.id
-        WHERE $2 IS NULL OR v.organization_id = $2
-        GROUP BY v.id, status, has_updated ",
+        WHERE ($2 IS NULL OR v.organization_id = $2)
+        AND (
+            v.visibility <> 'WhiteList'
+            OR EXISTS(SELECT 1 FROM vote_whitelists AS w WHERE w.vote_id = v.id AND w.user_id = $1)
+            OR EXISTS(SELECT 1 FROM organization_managers AS om WHERE om.organization_id = v.organization_id AND om.user_id = $1)
+        )
+        GROUP BY v.id, has_updated ",
         );
         if let Some(page) = pagination {
             stmt.push(page.to_sql_clause());
@@ -301,41 +545,17 @@ where
                 status: r.8,
                 has_updated: r.9,
                 num_of_questions: r.10,
+                grace_period: r.11,
+                anonymous: r.12,
             })
             .collect())
     }
     async fn get(&mut self, uid: i32, id: i32) -> Result<Vote, Error> {
-        let (id, name, deadline, organization_id, version, visibility, likes, dislikes, status, has_updated, num_of_questions): VoteRow = query_as(
-            "
-            SELECT 
-                v.*,
-                CASE WHEN v.deadline < CURRENT_DATE THEN 'Exprired' ELSE 'Active' END AS status,
-                CASE WHEN v.version > COALESCE(vrm.version, 0) THEN true ELSE false END AS has_updated,
-                COUNT(q.id) AS num_of_questions
-            FROM votes AS v
-            LEFT JOIN vote_read_marks AS vrm ON v.id = vrm.vote_id AND vrm.user_id = $1
-            LEFT JOIN questions AS q ON q.vote_id = v.id
-            WHERE v.id = $2
-            GROUP BY v.id, status, has_updated
-            ",
-        )
-        .bind(uid)
-        .bind(id)
-        .fetch_one(&mut self.executor)
-        .await?;
-        Ok(Vote {
-            id,
-            name,
-            deadline,
-            organization_id,
-            version,
-            visibility,
-            likes,
-            dislikes,
-            status,
-            has_updated,
-            num_of_questions,
-        })
+        self.vote(Some(uid), id).await
+    }
+
+    async fn get_for_guest(&mut self, id: i32) -> Result<Vote, Error> {
+        self.vote(None, id).await
     }
 
     async fn update_read_mark_version(&mut self, uid: i32, id: i32, version: i64) -> Result<(), Error> {
@@ -373,6 +593,155 @@ where
         .await?;
         Ok(exists)
     }
+
+    async fn get_organization_id(&mut self, id: i32) -> Result<i32, Error> {
+        let oid = query_scalar("SELECT organization_id FROM votes WHERE id = $1").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(oid)
+    }
+
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error> {
+        let exists = query_scalar("SELECT EXISTS(SELECT 1 FROM votes WHERE id = $1)").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(exists)
+    }
+
+    async fn get_status(&mut self, id: i32) -> Result<VoteStatus, Error> {
+        let status = query_scalar("SELECT status FROM votes WHERE id = $1").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(status)
+    }
+
+    async fn get_status_for_update(&mut self, id: i32) -> Result<VoteStatus, Error> {
+        let status = query_scalar("SELECT status FROM votes WHERE id = $1 FOR UPDATE").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(status)
+    }
+
+    async fn update_status(&mut self, id: i32, status: VoteStatus) -> Result<(), Error> {
+        query("UPDATE votes SET status = $1, version = version + 1 WHERE id = $2")
+            .bind(status)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_submission_window(&mut self, id: i32) -> Result<SubmissionWindow, Error> {
+        let (status, deadline, grace_period) = query_as("SELECT status, deadline, grace_period FROM votes WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(SubmissionWindow { status, deadline, grace_period })
+    }
+
+    async fn update_grace_period(&mut self, id: i32, grace_period: i32) -> Result<(), Error> {
+        query("UPDATE votes SET grace_period = $1, version = version + 1 WHERE id = $2")
+            .bind(grace_period)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn is_anonymous(&mut self, id: i32) -> Result<bool, Error> {
+        let anonymous = query_scalar("SELECT anonymous FROM votes WHERE id = $1").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(anonymous)
+    }
+
+    async fn update_visibility(&mut self, id: i32, visibility: String) -> Result<(), Error> {
+        query("UPDATE votes SET visibility = $1, version = version + 1 WHERE id = $2")
+            .bind(visibility)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&mut self, id: i32, name: String, deadline: Option<NaiveDate>) -> Result<i32, Error> {
+        let updated = query("UPDATE votes SET name = $1, deadline = $2, version = version + 1 WHERE id = $3")
+            .bind(name)
+            .bind(deadline)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(updated as i32)
+    }
+
+    async fn delete(&mut self, id: i32) -> Result<i32, Error> {
+        let deleted = query("DELETE FROM votes WHERE id = $1").bind(id).execute(&mut self.executor).await?.rows_affected();
+        Ok(deleted as i32)
+    }
+}
+
+impl<E> VoteWhiteListCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, vote_id: i32, uids: Vec<i32>) -> Result<(), Error> {
+        if uids.is_empty() {
+            return Ok(());
+        }
+        QueryBuilder::new("INSERT INTO vote_whitelists (vote_id, user_id)")
+            .push_values(uids, |mut b, uid| {
+                b.push_bind(vote_id);
+                b.push_bind(uid);
+            })
+            .push(" ON CONFLICT DO NOTHING")
+            .build()
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, vote_id: i32, uid: i32) -> Result<i32, Error> {
+        let deleted = query("DELETE FROM vote_whitelists WHERE vote_id = $1 AND user_id = $2")
+            .bind(vote_id)
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(deleted as i32)
+    }
+
+    async fn query(&mut self, vote_id: i32, pagination: Option<Pagination>) -> Result<Vec<WhiteListed>, Error> {
+        let mut stmt = QueryBuilder::new(
+            "
+            SELECT u.id AS user_id, u.nickname, u.avatar
+            FROM vote_whitelists AS w
+            JOIN users AS u ON w.user_id = u.id
+            WHERE w.vote_id = ",
+        );
+        stmt.push_bind(vote_id);
+        stmt.push(" ORDER BY w.id ");
+        if let Some(page) = pagination {
+            stmt.push(page.to_sql_clause());
+        }
+        let list = stmt.build_query_as().fetch_all(&mut self.executor).await?;
+        Ok(list)
+    }
+
+    async fn count(&mut self, vote_id: i32) -> Result<i64, Error> {
+        let count = query_scalar("SELECT COUNT(*) FROM vote_whitelists WHERE vote_id = $1")
+            .bind(vote_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count)
+    }
+
+    async fn is_whitelisted(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error> {
+        let whitelisted = query_scalar(
+            "
+            SELECT EXISTS(
+                SELECT 1
+                FROM vote_whitelists AS w
+                JOIN votes AS v ON w.vote_id = v.id
+                WHERE w.vote_id = $1 AND w.user_id = $2 AND v.visibility = 'WhiteList'
+            )",
+        )
+        .bind(vote_id)
+        .bind(uid)
+        .fetch_one(&mut self.executor)
+        .await?;
+        Ok(whitelisted)
+    }
 }
 
 impl Store for PgSqlx<PoolConnection<Postgres>> {}
@@ -404,34 +773,144 @@ impl<'a> Manager<'a, PgSqlx<PoolConnection<Postgres>>, PgSqlx<Transaction<'a, Po
     }
 }
 
-type QuestionRow = (i32, String, i32, String, i64, i32, i32, i32, bool, bool, i32, String, i32, Vec<String>);
+#[derive(FromRow)]
+struct QuestionRow {
+    id: i32,
+    description: String,
+    vote_id: i32,
+    type_: String,
+    version: i64,
+    owner: i32,
+    likes: i32,
+    dislikes: i32,
+    min_selections: i32,
+    max_selections: Option<i32>,
+    required: bool,
+    tally_method: String,
+    number_min: Option<f64>,
+    number_max: Option<f64>,
+    number_step: Option<f64>,
+    has_updated: bool,
+    has_answered: bool,
+    // questions without options are left joined to a single row of nulls
+    option_id: Option<i32>,
+    option_option: Option<String>,
+    option_question_id: Option<i32>,
+    option_images: Option<Vec<String>>,
+}
+
+impl QuestionRow {
+    fn option(&self) -> Option<Opt> {
+        Some(Opt {
+            id: self.option_id?,
+            option: self.option_option.clone()?,
+            question_id: self.option_question_id?,
+            images: self.option_images.clone().unwrap_or_default(),
+        })
+    }
+}
+
+impl From<QuestionRow> for Question {
+    fn from(r: QuestionRow) -> Self {
+        Question {
+            options: r.option().into_iter().collect(),
+            id: r.id,
+            description: r.description,
+            vote_id: r.vote_id,
+            type_: r.type_,
+            version: r.version,
+            owner: r.owner,
+            likes: r.likes,
+            dislikes: r.dislikes,
+            min_selections: r.min_selections,
+            max_selections: r.max_selections,
+            required: r.required,
+            tally_method: r.tally_method,
+            number_range: NumberRange {
+                min: r.number_min,
+                max: r.number_max,
+                step: r.number_step,
+            },
+            has_updated: r.has_updated,
+            has_answered: r.has_answered,
+        }
+    }
+}
+
+// the rows are ordered by question, one per option
+fn group_question_rows(rows: Vec<QuestionRow>) -> Vec<Question> {
+    rows.into_iter().fold(Vec::<Question>::new(), |mut l, r| {
+        if let Some(mut last) = l.pop() {
+            if last.id == r.id {
+                last.options.extend(r.option());
+                l.push(last);
+                return l;
+            }
+            l.push(last);
+        }
+        l.push(r.into());
+        l
+    })
+}
+
+// `respondent` is the column of the value answers compared with the first argument, `user_id` or `guest_id`
+fn question_columns(respondent: &str) -> String {
+    format!(
+        "
+            q.id,
+            q.description,
+            q.vote_id,
+            q.type_,
+            q.version,
+            q.owner,
+            q.likes,
+            q.dislikes,
+            q.min_selections,
+            q.max_selections,
+            q.required,
+            q.tally_method,
+            q.number_min,
+            q.number_max,
+            q.number_step,
+            COALESCE(qrm.version, 0) < q.version AS has_updated,
+            COUNT(a.id) OVER(PARTITION BY q.id ORDER BY q.id) > 0 OR EXISTS(SELECT 1 FROM value_answers AS va WHERE va.question_id = q.id AND va.{respondent} = $1) AS has_answered,
+            o.id AS option_id,
+            o.option AS option_option,
+            o.question_id AS option_question_id,
+            o.images AS option_images"
+    )
+}
 
 impl<E> QuestionCommon for PgSqlx<E>
 where
     for<'e> &'e mut E: Executor<'e, Database = Postgres>,
 {
     async fn insert(&mut self, uid: i32, question: QuestionInsert) -> Result<i32, Error> {
-        let id = query_scalar("INSERT INTO questions (description, type_, version, vote_id, owner) VALUES ($1, $2, 1, $3, $4) RETURNING id")
-            .bind(question.description)
-            .bind(question.type_)
-            .bind(question.vote_id)
-            .bind(uid)
-            .fetch_one(&mut self.executor)
-            .await?;
+        let id = query_scalar(
+            "INSERT INTO questions (description, type_, version, vote_id, owner, min_selections, max_selections, required, tally_method, number_min, number_max, number_step)
+            VALUES ($1, $2, 1, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING id",
+        )
+        .bind(question.description)
+        .bind(question.type_)
+        .bind(question.vote_id)
+        .bind(uid)
+        .bind(question.min_selections)
+        .bind(question.max_selections)
+        .bind(question.required)
+        .bind(question.tally_method)
+        .bind(question.number_range.min)
+        .bind(question.number_range.max)
+        .bind(question.number_range.step)
+        .fetch_one(&mut self.executor)
+        .await?;
         Ok(id)
     }
 
     async fn query(&mut self, uid: i32, query: QuestionQuery, pagination: Option<Pagination>) -> Result<Vec<Question>, Error> {
-        let mut q = QueryBuilder::new(
+        let mut q = QueryBuilder::new("SELECT ");
+        q.push(question_columns("user_id"));
+        q.push(
             "
-        SELECT 
-            q.*,
-            COALESCE(qrm.version, 0) < q.version AS has_updated,
-            COUNT(a.id) OVER(PARTITION BY q.id ORDER BY q.id) > 0 AS has_answered,
-            o.id AS option_id,
-            o.option AS option_option,
-            o.question_id AS option_question_id,
-            o.images AS option_images
         FROM questions AS q
         LEFT JOIN question_read_marks AS qrm ON q.id = qrm.question_id AND qrm.user_id = $1
         LEFT JOIN options AS o ON o.question_id = q.id
@@ -443,84 +922,51 @@ where
             q.push(page.to_sql_clause());
         }
         let rows: Vec<QuestionRow> = q.build_query_as().bind(uid).bind(query.vote_id_eq).fetch_all(&mut self.executor).await?;
-        let questions = rows.into_iter().fold(Vec::<Question>::new(), |mut l, r| {
-            if let Some(mut last) = l.pop() {
-                if last.id == r.0 {
-                    last.options.push(Opt {
-                        id: r.10,
-                        option: r.11,
-                        question_id: r.12,
-                        images: r.13,
-                    });
-                    l.push(last);
-                    return l;
-                }
-                l.push(last);
-            }
-            l.push(Question {
-                id: r.0,
-                description: r.1,
-                vote_id: r.2,
-                type_: r.3,
-                version: r.4,
-                owner: r.5,
-                likes: r.6,
-                dislikes: r.7,
-                has_updated: r.8,
-                has_answered: r.9,
-                options: vec![Opt {
-                    id: r.10,
-                    option: r.11,
-                    question_id: r.12,
-                    images: r.13,
-                }],
-            });
-            l
-        });
-        Ok(questions)
+        Ok(group_question_rows(rows))
+    }
+
+    async fn query_for_guest(&mut self, guest_id: i32, vote_id: i32) -> Result<Vec<Question>, Error> {
+        // the guests have no read marks
+        let rows: Vec<QuestionRow> = query_as(&format!(
+            "
+        SELECT {}
+        FROM questions AS q
+        LEFT JOIN question_read_marks AS qrm ON FALSE
+        LEFT JOIN options AS o ON o.question_id = q.id
+        LEFT JOIN answers AS a ON a.option_id = o.id AND a.guest_id = $1
+        WHERE q.vote_id = $2",
+            question_columns("guest_id")
+        ))
+        .bind(guest_id)
+        .bind(vote_id)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(group_question_rows(rows))
     }
 
     async fn get(&mut self, uid: i32, id: i32) -> Result<Question, Error> {
-        let rows: Vec<QuestionRow> = query_as(
+        let rows: Vec<QuestionRow> = query_as(&format!(
             "
-        SELECT 
-            q.*, 
-            COALESCE(qrm.version, 0) < q.version AS has_updated,
-            COUNT(a.id) OVER(PARTITION BY q.id ORDER BY q.id) > 0 AS has_answered,
-            o.id AS option_id,
-            o.option AS option_option,
-            o.question_id AS option_question_id,
-            o.images AS option_images
+        SELECT {}
         FROM questions AS q
         LEFT JOIN question_read_marks AS qrm ON q.id = qrm.question_id AND qrm.user_id = $1
         LEFT JOIN options AS o ON o.question_id = q.id
         LEFT JOIN answers AS a ON a.option_id = o.id AND a.user_id = $1
         WHERE q.id = $2",
-        )
+            question_columns("user_id")
+        ))
         .bind(uid)
         .bind(id)
         .fetch_all(&mut self.executor)
         .await?;
-        let question = rows.into_iter().fold(Question::default(), |mut q, r| {
-            q.id = r.0;
-            q.description = r.1;
-            q.vote_id = r.2;
-            q.type_ = r.3;
-            q.version = r.4;
-            q.owner = r.5;
-            q.likes = r.6;
-            q.dislikes = r.7;
-            q.has_updated = r.8;
-            q.has_answered = r.9;
-            q.options.push(Opt {
-                id: r.10,
-                option: r.11,
-                question_id: r.12,
-                images: r.13,
-            });
-            q
+        let question = rows.into_iter().fold(None, |q: Option<Question>, r| match q {
+            None => Some(r.into()),
+            Some(mut q) => {
+                q.options.extend(r.option());
+                Some(q)
+            }
         });
-        Ok(question)
+        Ok(question.unwrap_or_default())
     }
 
     async fn delete(&mut self, id: i32) -> Result<(), Error> {
@@ -536,6 +982,58 @@ where
         Ok(oid)
     }
 
+    async fn get_vote_id(&mut self, question_id: i32) -> Result<i32, Error> {
+        let vid = query_scalar("SELECT vote_id FROM questions WHERE id = $1").bind(question_id).fetch_one(&mut self.executor).await?;
+        Ok(vid)
+    }
+
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error> {
+        let exists = query_scalar("SELECT EXISTS(SELECT 1 FROM questions WHERE id = $1)").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(exists)
+    }
+
+    async fn get_selection_constraint(&mut self, question_id: i32) -> Result<SelectionConstraint, Error> {
+        let (min_selections, max_selections, required) = query_as("SELECT min_selections, max_selections, required FROM questions WHERE id = $1")
+            .bind(question_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(SelectionConstraint {
+            min_selections,
+            max_selections,
+            required,
+        })
+    }
+
+    async fn update_tally_method(&mut self, id: i32, method: String) -> Result<(), Error> {
+        query("UPDATE questions SET tally_method = $1, version = version + 1 WHERE id = $2")
+            .bind(method)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_type(&mut self, question_id: i32) -> Result<QuestionType, Error> {
+        let type_: String = query_scalar("SELECT type_ FROM questions WHERE id = $1").bind(question_id).fetch_one(&mut self.executor).await?;
+        QuestionType::try_from(type_.as_str())
+    }
+
+    async fn get_number_range(&mut self, question_id: i32) -> Result<NumberRange, Error> {
+        let (min, max, step) = query_as("SELECT number_min, number_max, number_step FROM questions WHERE id = $1")
+            .bind(question_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(NumberRange { min, max, step })
+    }
+
+    async fn required_ids(&mut self, vote_id: i32) -> Result<Vec<i32>, Error> {
+        let ids = query_scalar("SELECT id FROM questions WHERE vote_id = $1 AND required")
+            .bind(vote_id)
+            .fetch_all(&mut self.executor)
+            .await?;
+        Ok(ids)
+    }
+
     async fn is_owner(&mut self, uid: i32, question_id: i32) -> Result<bool, Error> {
         let is_owner = query_scalar("SELECT EXISTS(SELECT 1 FROM questions WHERE id = $1 AND owner = $2)")
             .bind(question_id)
@@ -546,20 +1044,16 @@ where
     }
 
     async fn is_belongs_to_vote(&mut self, vote_id: i32, ids: Vec<i32>) -> Result<bool, Error> {
-        let is_belongs_to = query_scalar(
-            "WITH v AS (
-            SELECT COALESCE(ARRAY_AGG(v.id), ARRAY[]::integer[]) AS ids 
-            FROM votes AS v
-            JOIN questions AS q ON v.id = q.vote_id
-            WHERE q.id IN $1
-        )
-        SELECT ids = ARRAY[$2] FROM v",
-        )
-        .bind(ids)
-        .bind(vote_id)
-        .fetch_one(&mut self.executor)
-        .await?;
-        Ok(is_belongs_to)
+        if ids.is_empty() {
+            return Ok(true);
+        }
+        let expected = ids.iter().unique().count() as i64;
+        let count: i64 = query_scalar("SELECT COUNT(DISTINCT id) FROM questions WHERE id = ANY($1) AND vote_id = $2")
+            .bind(ids)
+            .bind(vote_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count == expected)
     }
 
     async fn count(&mut self, query: QuestionQuery) -> Result<i64, Error> {
@@ -651,21 +1145,27 @@ where
     }
 
     async fn is_belongs_to_question(&mut self, question_id: i32, ids: Vec<i32>) -> Result<bool, Error> {
-        let is_in_one: bool = query_scalar(
-            "WITH qs AS (
-            SELECT
-            COALESCE(ARRAY_AGG(DISTINCT q.id)) ids
-            FROM questions AS q
-            JOIN options AS o ON o.question_id = q.id
-            WHERE o.id IN $1
-        )
-        SELECT ids = ARRAY[$2] FROM qs",
-        )
-        .bind(ids)
-        .bind(question_id)
-        .fetch_one(&mut self.executor)
-        .await?;
-        Ok(is_in_one)
+        // nothing to check for an optional question left empty
+        if ids.is_empty() {
+            return Ok(true);
+        }
+        let expected = ids.iter().unique().count() as i64;
+        let count: i64 = query_scalar("SELECT COUNT(DISTINCT id) FROM options WHERE id = ANY($1) AND question_id = $2")
+            .bind(ids)
+            .bind(question_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count == expected)
+    }
+
+    async fn get_question_id(&mut self, id: i32) -> Result<Option<i32>, Error> {
+        let question_id = query_scalar("SELECT question_id FROM options WHERE id = $1").bind(id).fetch_optional(&mut self.executor).await?;
+        Ok(question_id)
+    }
+
+    async fn exists_by_id(&mut self, id: i32) -> Result<bool, Error> {
+        let exists = query_scalar("SELECT EXISTS(SELECT 1 FROM options WHERE id = $1)").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(exists)
     }
 }
 
@@ -682,6 +1182,34 @@ where
             .await?;
         Ok(id)
     }
+
+    async fn insert_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error> {
+        query(
+            "
+            INSERT INTO vote_read_marks (vote_id, user_id, version)
+            SELECT v.id, $1, 0
+            FROM votes AS v
+            WHERE v.organization_id = $2",
+        )
+        .bind(uid)
+        .bind(organization_id)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error> {
+        query(
+            "
+            DELETE FROM vote_read_marks
+            WHERE user_id = $1 AND vote_id IN (SELECT id FROM votes WHERE organization_id = $2)",
+        )
+        .bind(uid)
+        .bind(organization_id)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
 }
 
 impl<E> QuestionReadMarkCommon for PgSqlx<E>
@@ -707,6 +1235,47 @@ where
             .await?;
         Ok(())
     }
+
+    async fn insert_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error> {
+        query(
+            "
+            INSERT INTO question_read_marks (question_id, user_id, version)
+            SELECT q.id, $1, 0
+            FROM votes AS v
+            JOIN questions AS q ON v.id = q.vote_id
+            WHERE v.organization_id = $2",
+        )
+        .bind(uid)
+        .bind(organization_id)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_for_member(&mut self, organization_id: i32, uid: i32) -> Result<(), Error> {
+        query(
+            "
+            DELETE FROM question_read_marks
+            WHERE user_id = $1 AND question_id IN (
+                SELECT q.id
+                FROM votes AS v
+                JOIN questions AS q ON v.id = q.vote_id
+                WHERE v.organization_id = $2)",
+        )
+        .bind(uid)
+        .bind(organization_id)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+}
+
+// condition on the guest_id column of answers and value_answers
+fn source_condition(source: Source) -> &'static str {
+    match source {
+        Source::Users => "guest_id IS NULL",
+        Source::Guests => "guest_id IS NOT NULL",
+    }
 }
 
 impl<E> AnswerCommon for PgSqlx<E>
@@ -714,21 +1283,35 @@ where
     for<'e> &'e mut E: Executor<'e, Database = Postgres>,
 {
     async fn insert(&mut self, answer: AnswerInsert) -> Result<i32, Error> {
-        let id = query_scalar("INSERT INTO answers (user_id, option_id) VALUES ($1, $2) RETURNING id")
-            .bind(answer.user_id)
+        let id = query_scalar("INSERT INTO answers (user_id, ballot, guest_id, option_id, late, position, rating) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id")
+            .bind(answer.respondent.user_id())
+            .bind(answer.respondent.ballot())
+            .bind(answer.respondent.guest_id())
             .bind(answer.option_id)
+            .bind(answer.late)
+            .bind(answer.position)
+            .bind(answer.rating)
             .fetch_one(&mut self.executor)
             .await?;
         Ok(id)
     }
 
     async fn bulk_insert(&mut self, answers: Vec<AnswerInsert>) -> Result<(), Error> {
+        if answers.is_empty() {
+            return Ok(());
+        }
         let mut q = QueryBuilder::new(
             "
-        INSERT INTO answers (user_id, option_id)",
+        INSERT INTO answers (user_id, ballot, guest_id, option_id, late, position, rating)",
         );
         q.push_values(answers, |mut s, a| {
-            s.push_bind(a.user_id).push_bind(a.option_id);
+            s.push_bind(a.respondent.user_id())
+                .push_bind(a.respondent.ballot())
+                .push_bind(a.respondent.guest_id())
+                .push_bind(a.option_id)
+                .push_bind(a.late)
+                .push_bind(a.position)
+                .push_bind(a.rating);
         })
         .build()
         .execute(&mut self.executor)
@@ -739,23 +1322,1039 @@ where
     async fn delete(&mut self, query: AnswerQuery) -> Result<i32, Error> {
         let deleted = query_scalar(
             "WITH deleted AS (
-                WITH by_question AS (
-                    SELECT q.id 
-                    FROM questions AS q
-                    JOIN options AS o ON o.question_id = q.id
-                    JOIN answers AS a ON a.option_id = o.id
-                    WHERE $1 IS NULL OR q.id = $1
-                )
-                DELETE FROM answers 
-                WHERE ($2 IS NULL OR user_id = $2) AND option_id IN by_question
+                DELETE FROM answers AS a
+                USING options AS o
+                WHERE a.option_id = o.id
+                AND ($1 IS NULL OR o.question_id = $1)
+                AND ($2 IS NULL OR a.user_id = $2)
+                AND ($3 IS NULL OR a.ballot = $3)
+                AND ($4 IS NULL OR a.guest_id = $4)
+                RETURNING a.id
+            ) 
+            SELECT COUNT(id) FROM deleted",
+        )
+        .bind(query.question_id_eq)
+        .bind(query.user_id_eq)
+        .bind(query.ballot_eq)
+        .bind(query.guest_id_eq)
+        .fetch_one(&mut self.executor)
+        .await?;
+        Ok(deleted)
+    }
+
+    async fn delete_values(&mut self, query: AnswerQuery) -> Result<i32, Error> {
+        let deleted = query_scalar(
+            "WITH deleted AS (
+                DELETE FROM value_answers
+                WHERE ($1 IS NULL OR question_id = $1)
+                AND ($2 IS NULL OR user_id = $2)
+                AND ($3 IS NULL OR ballot = $3)
+                AND ($4 IS NULL OR guest_id = $4)
                 RETURNING id
             ) 
             SELECT COUNT(id) FROM deleted",
         )
         .bind(query.question_id_eq)
         .bind(query.user_id_eq)
+        .bind(query.ballot_eq)
+        .bind(query.guest_id_eq)
         .fetch_one(&mut self.executor)
         .await?;
         Ok(deleted)
     }
+
+    async fn ranked_ballots(&mut self, question_id: i32, source: Source) -> Result<Vec<Vec<i32>>, Error> {
+        let rows: Vec<(String, i32)> = query_as(&format!(
+            "
+        SELECT COALESCE(a.user_id::VARCHAR, a.ballot, 'guest:' || a.guest_id) AS respondent, a.option_id
+        FROM answers AS a
+        JOIN options AS o ON a.option_id = o.id
+        WHERE o.question_id = $1 AND {}
+        ORDER BY respondent, a.position",
+            source_condition(source)
+        ))
+        .bind(question_id)
+        .fetch_all(&mut self.executor)
+        .await?;
+        let ballots = rows
+            .into_iter()
+            .group_by(|(respondent, _)| respondent.clone())
+            .into_iter()
+            .map(|(_, ranks)| ranks.map(|(_, oid)| oid).collect())
+            .collect();
+        Ok(ballots)
+    }
+
+    async fn matrix_ratings(&mut self, question_id: i32, source: Source) -> Result<Vec<(i32, i32)>, Error> {
+        let ratings = query_as(&format!(
+            "
+        SELECT a.option_id, a.rating
+        FROM answers AS a
+        JOIN options AS o ON a.option_id = o.id
+        WHERE o.question_id = $1 AND a.rating IS NOT NULL AND {}",
+            source_condition(source)
+        ))
+        .bind(question_id)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(ratings)
+    }
+
+    async fn upsert_value(&mut self, answer: ValueInsert) -> Result<(), Error> {
+        let (text, number, date) = match answer.value {
+            AnswerValue::Text(t) => (Some(t), None, None),
+            AnswerValue::Number(n) => (None, Some(n), None),
+            AnswerValue::Date(d) => (None, None, Some(d)),
+        };
+        let conflict = match answer.respondent {
+            Respondent::User(_) => "user_id",
+            Respondent::Ballot(_) => "ballot",
+            Respondent::Guest(_) => "guest_id",
+        };
+        query(&format!(
+            "
+        INSERT INTO value_answers (question_id, user_id, ballot, guest_id, text, number, date, late)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (question_id, {}) DO UPDATE SET text = $5, number = $6, date = $7, late = $8",
+            conflict
+        ))
+        .bind(answer.question_id)
+        .bind(answer.respondent.user_id())
+        .bind(answer.respondent.ballot())
+        .bind(answer.respondent.guest_id())
+        .bind(text)
+        .bind(number)
+        .bind(date)
+        .bind(answer.late)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn count_values(&mut self, question_id: i32, source: Source) -> Result<i64, Error> {
+        let count = query_scalar(&format!("SELECT COUNT(id) FROM value_answers WHERE question_id = $1 AND {}", source_condition(source)))
+            .bind(question_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count)
+    }
+
+    async fn texts(&mut self, question_id: i32, source: Source, pagination: Option<Pagination>) -> Result<Vec<TextAnswer>, Error> {
+        let mut q = QueryBuilder::new("SELECT id, user_id, text, late FROM value_answers WHERE text IS NOT NULL AND question_id = ");
+        q.push_bind(question_id);
+        q.push(" AND ");
+        q.push(source_condition(source));
+        q.push(" ORDER BY id ");
+        if let Some(page) = pagination {
+            q.push(page.to_sql_clause());
+        }
+        let texts = q.build_query_as().fetch_all(&mut self.executor).await?;
+        Ok(texts)
+    }
+
+    async fn numbers(&mut self, question_id: i32, source: Source) -> Result<Vec<f64>, Error> {
+        let numbers = query_scalar(&format!(
+            "SELECT number FROM value_answers WHERE number IS NOT NULL AND question_id = $1 AND {} ORDER BY number",
+            source_condition(source)
+        ))
+        .bind(question_id)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(numbers)
+    }
+
+    async fn dates(&mut self, question_id: i32, source: Source) -> Result<Vec<DateCount>, Error> {
+        let dates = query_as(&format!(
+            "SELECT date, COUNT(id) AS count FROM value_answers WHERE date IS NOT NULL AND question_id = $1 AND {} GROUP BY date ORDER BY date",
+            source_condition(source)
+        ))
+        .bind(question_id)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(dates)
+    }
+
+    async fn answered_votes(&mut self, organization_id: i32, uid: i32) -> Result<Vec<i32>, Error> {
+        let ids = query_scalar(
+            "
+            SELECT v.id
+            FROM votes AS v
+            WHERE v.organization_id = $1
+                AND (
+                    EXISTS(
+                        SELECT 1
+                        FROM answers AS a
+                        JOIN options AS o ON o.id = a.option_id
+                        JOIN questions AS q ON q.id = o.question_id
+                        WHERE q.vote_id = v.id AND a.user_id = $2)
+                    OR EXISTS(
+                        SELECT 1
+                        FROM value_answers AS va
+                        JOIN questions AS q ON q.id = va.question_id
+                        WHERE q.vote_id = v.id AND va.user_id = $2))",
+        )
+        .bind(organization_id)
+        .bind(uid)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(ids)
+    }
+
+    async fn move_to_ballot(&mut self, vote_id: i32, uid: i32, ballot: String) -> Result<(), Error> {
+        query(
+            "
+            UPDATE answers SET user_id = NULL, ballot = $1
+            WHERE user_id = $2 AND option_id IN (
+                SELECT o.id
+                FROM options AS o
+                JOIN questions AS q ON q.id = o.question_id
+                WHERE q.vote_id = $3)",
+        )
+        .bind(&ballot)
+        .bind(uid)
+        .bind(vote_id)
+        .execute(&mut self.executor)
+        .await?;
+        query("UPDATE value_answers SET user_id = NULL, ballot = $1 WHERE user_id = $2 AND question_id IN (SELECT id FROM questions WHERE vote_id = $3)")
+            .bind(&ballot)
+            .bind(uid)
+            .bind(vote_id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_dates(&mut self, organization_id: i32, uid: i32) -> Result<(), Error> {
+        query("DELETE FROM date_ranges WHERE user_id = $1 AND vote_id IN (SELECT id FROM votes WHERE organization_id = $2)")
+            .bind(uid)
+            .bind(organization_id)
+            .execute(&mut self.executor)
+            .await?;
+        query("DELETE FROM dates WHERE user_id = $1 AND vote_id IN (SELECT id FROM votes WHERE organization_id = $2)")
+            .bind(uid)
+            .bind(organization_id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+}
+
+impl<E> ApplicationCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, uid: i32, organization_id: i32) -> Result<i32, Error> {
+        let id = query_scalar(
+            "
+        INSERT INTO join_applications (user_id, organization_id, status) VALUES ($1, $2, 'Pending')
+        ON CONFLICT (user_id, organization_id) DO UPDATE SET status = 'Pending', reason = NULL
+        WHERE join_applications.status IN ('Rejected', 'Withdrawn')
+        RETURNING id",
+        )
+        .bind(uid)
+        .bind(organization_id)
+        .fetch_optional(&mut self.executor)
+        .await?;
+        id.ok_or(Error::BusinessError("application already exists".into()))
+    }
+
+    async fn get(&mut self, id: i32) -> Result<JoinApplication, Error> {
+        let application = query_as("SELECT * FROM join_applications WHERE id = $1").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(application)
+    }
+
+    async fn get_for_update(&mut self, id: i32) -> Result<JoinApplication, Error> {
+        let application = query_as("SELECT * FROM join_applications WHERE id = $1 FOR UPDATE").bind(id).fetch_one(&mut self.executor).await?;
+        Ok(application)
+    }
+
+    async fn query(&mut self, query: &ApplicationQuery, pagination: Option<Pagination>) -> Result<Vec<JoinApplication>, Error> {
+        let mut stmt = QueryBuilder::new("SELECT * FROM join_applications WHERE 1 = 1");
+        if let Some(uid) = query.user_id_eq {
+            stmt.push(" AND user_id = ").push_bind(uid);
+        }
+        if let Some(oid) = query.organization_id_eq {
+            stmt.push(" AND organization_id = ").push_bind(oid);
+        }
+        if let Some(status) = query.status_eq {
+            stmt.push(" AND status = ").push_bind(status);
+        }
+        stmt.push(" ORDER BY id DESC ");
+        if let Some(page) = pagination {
+            stmt.push(page.to_sql_clause());
+        }
+        let applications = stmt.build_query_as().fetch_all(&mut self.executor).await?;
+        Ok(applications)
+    }
+
+    async fn count(&mut self, query: &ApplicationQuery) -> Result<i64, Error> {
+        let mut stmt = QueryBuilder::new("SELECT COUNT(id) FROM join_applications WHERE 1 = 1");
+        if let Some(uid) = query.user_id_eq {
+            stmt.push(" AND user_id = ").push_bind(uid);
+        }
+        if let Some(oid) = query.organization_id_eq {
+            stmt.push(" AND organization_id = ").push_bind(oid);
+        }
+        if let Some(status) = query.status_eq {
+            stmt.push(" AND status = ").push_bind(status);
+        }
+        let (n,) = stmt.build_query_as().fetch_one(&mut self.executor).await?;
+        Ok(n)
+    }
+
+    async fn update_status(&mut self, id: i32, status: ApplicationStatus, reason: Option<String>) -> Result<(), Error> {
+        query("UPDATE join_applications SET status = $1, reason = $2 WHERE id = $3")
+            .bind(status)
+            .bind(reason)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+}
+
+impl<E> BallotCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert_participation(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error> {
+        let inserted = query("INSERT INTO vote_participations (vote_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+            .bind(vote_id)
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(inserted > 0)
+    }
+
+    async fn has_participated(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error> {
+        let participated = query_scalar("SELECT EXISTS(SELECT 1 FROM vote_participations WHERE vote_id = $1 AND user_id = $2)")
+            .bind(vote_id)
+            .bind(uid)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(participated)
+    }
+
+    async fn insert_ballot(&mut self, vote_id: i32, token_hash: String) -> Result<(), Error> {
+        query("INSERT INTO ballots (token_hash, vote_id) VALUES ($1, $2)")
+            .bind(token_hash)
+            .bind(vote_id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn exists_ballot(&mut self, vote_id: i32, token_hash: String) -> Result<bool, Error> {
+        let exists = query_scalar("SELECT EXISTS(SELECT 1 FROM ballots WHERE token_hash = $1 AND vote_id = $2)")
+            .bind(token_hash)
+            .bind(vote_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(exists)
+    }
+}
+
+impl<E> ShareLinkCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, link: ShareLinkInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO share_links (vote_id, token_hash, creator, expires_at, rate_limit) VALUES ($1, $2, $3, $4, $5) RETURNING id")
+            .bind(link.vote_id)
+            .bind(link.token_hash)
+            .bind(link.creator)
+            .bind(link.expires_at)
+            .bind(link.rate_limit)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get(&mut self, id: i32) -> Result<Option<ShareLink>, Error> {
+        let link = query_as("SELECT id, vote_id, creator, expires_at, rate_limit, revoked, created_at FROM share_links WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(link)
+    }
+
+    async fn get_by_token_hash(&mut self, token_hash: String) -> Result<Option<ShareLink>, Error> {
+        let link = query_as("SELECT id, vote_id, creator, expires_at, rate_limit, revoked, created_at FROM share_links WHERE token_hash = $1")
+            .bind(token_hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(link)
+    }
+
+    async fn get_for_update(&mut self, id: i32) -> Result<Option<ShareLink>, Error> {
+        let link = query_as("SELECT id, vote_id, creator, expires_at, rate_limit, revoked, created_at FROM share_links WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(link)
+    }
+
+    async fn get_by_token_hash_for_update(&mut self, token_hash: String) -> Result<Option<ShareLink>, Error> {
+        let link = query_as("SELECT id, vote_id, creator, expires_at, rate_limit, revoked, created_at FROM share_links WHERE token_hash = $1 FOR UPDATE")
+            .bind(token_hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(link)
+    }
+
+    async fn query(&mut self, vote_id: i32) -> Result<Vec<ShareLink>, Error> {
+        let links = query_as("SELECT id, vote_id, creator, expires_at, rate_limit, revoked, created_at FROM share_links WHERE vote_id = $1 ORDER BY id DESC")
+            .bind(vote_id)
+            .fetch_all(&mut self.executor)
+            .await?;
+        Ok(links)
+    }
+
+    async fn revoke(&mut self, vote_id: i32, id: i32) -> Result<i32, Error> {
+        let revoked = query("UPDATE share_links SET revoked = true WHERE vote_id = $1 AND id = $2")
+            .bind(vote_id)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(revoked as i32)
+    }
+
+    async fn insert_guest(&mut self, share_link_id: i32) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO guests (share_link_id) VALUES ($1) RETURNING id")
+            .bind(share_link_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn count_recent_guests(&mut self, share_link_id: i32) -> Result<i64, Error> {
+        let count = query_scalar("SELECT COUNT(id) FROM guests WHERE share_link_id = $1 AND created_at > now() - INTERVAL '1 hour'")
+            .bind(share_link_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count)
+    }
+
+    async fn count_recent_submissions(&mut self, share_link_id: i32, guest_id: i32) -> Result<i64, Error> {
+        let count = query_scalar("SELECT COUNT(id) FROM guests WHERE share_link_id = $1 AND id <> $2 AND submitted_at > now() - INTERVAL '1 hour'")
+            .bind(share_link_id)
+            .bind(guest_id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count)
+    }
+
+    async fn update_guest_submitted_at(&mut self, guest_id: i32) -> Result<(), Error> {
+        query("UPDATE guests SET submitted_at = now() WHERE id = $1").bind(guest_id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+}
+
+const INVITE_CODE_COLUMNS: &str = "id, code, issuer, organization_id, expires_at, max_uses, uses, revoked, created_at";
+
+impl<E> InviteCodeCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, code: InviteCodeInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO invite_codes (code, issuer, organization_id, expires_at, max_uses) VALUES ($1, $2, $3, $4, $5) RETURNING id")
+            .bind(code.code)
+            .bind(code.issuer)
+            .bind(code.organization_id)
+            .bind(code.expires_at)
+            .bind(code.max_uses)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get(&mut self, id: i32) -> Result<Option<InviteCode>, Error> {
+        let code = query_as(&format!("SELECT {} FROM invite_codes WHERE id = $1", INVITE_CODE_COLUMNS))
+            .bind(id)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(code)
+    }
+
+    async fn redeem(&mut self, code: String) -> Result<Option<InviteCode>, Error> {
+        let code = query_as(&format!(
+            "
+            UPDATE invite_codes
+            SET uses = uses + 1
+            WHERE code = $1
+            AND NOT revoked
+            AND uses < max_uses
+            AND (expires_at IS NULL OR expires_at > now())
+            RETURNING {}",
+            INVITE_CODE_COLUMNS
+        ))
+        .bind(code)
+        .fetch_optional(&mut self.executor)
+        .await?;
+        Ok(code)
+    }
+
+    async fn query_by_issuer(&mut self, uid: i32) -> Result<Vec<InviteCode>, Error> {
+        let codes = query_as(&format!(
+            "SELECT {} FROM invite_codes WHERE issuer = $1 AND organization_id IS NULL ORDER BY id DESC",
+            INVITE_CODE_COLUMNS
+        ))
+        .bind(uid)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(codes)
+    }
+
+    async fn query_by_organization(&mut self, org_id: i32) -> Result<Vec<InviteCode>, Error> {
+        let codes = query_as(&format!("SELECT {} FROM invite_codes WHERE organization_id = $1 ORDER BY id DESC", INVITE_CODE_COLUMNS))
+            .bind(org_id)
+            .fetch_all(&mut self.executor)
+            .await?;
+        Ok(codes)
+    }
+
+    async fn revoke(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE invite_codes SET revoked = true WHERE id = $1").bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+}
+
+impl<E> PasswordResetCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, reset: PasswordResetInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO password_resets (user_id, token_hash, expires_at) VALUES ($1, $2, $3) RETURNING id")
+            .bind(reset.user_id)
+            .bind(reset.token_hash)
+            .bind(reset.expires_at)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get_by_token_hash(&mut self, hash: String) -> Result<Option<PasswordReset>, Error> {
+        let reset = query_as("SELECT id, user_id, expires_at, used_at FROM password_resets WHERE token_hash = $1 FOR UPDATE")
+            .bind(hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(reset)
+    }
+
+    async fn invalidate(&mut self, uid: i32) -> Result<(), Error> {
+        query("UPDATE password_resets SET used_at = now() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+}
+
+impl<E> VerificationCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, verification: VerificationInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO verifications (user_id, channel, target, code_hash, expires_at) VALUES ($1, $2, $3, $4, $5) RETURNING id")
+            .bind(verification.user_id)
+            .bind(verification.channel.as_str())
+            .bind(verification.target)
+            .bind(verification.code_hash)
+            .bind(verification.expires_at)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn latest(&mut self, uid: i32, channel: Channel) -> Result<Option<Verification>, Error> {
+        let verification = query_as(
+            "
+            SELECT id, user_id, target, code_hash, attempts, expires_at, created_at, used_at
+            FROM verifications
+            WHERE user_id = $1 AND channel = $2
+            ORDER BY id DESC
+            LIMIT 1
+            FOR UPDATE",
+        )
+        .bind(uid)
+        .bind(channel.as_str())
+        .fetch_optional(&mut self.executor)
+        .await?;
+        Ok(verification)
+    }
+
+    async fn increase_attempts(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE verifications SET attempts = attempts + 1 WHERE id = $1").bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+
+    async fn mark_used(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE verifications SET used_at = now() WHERE id = $1").bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+}
+
+impl<E> TwoFactorCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn get(&mut self, uid: i32) -> Result<Option<TwoFactor>, Error> {
+        let two_factor = query_as("SELECT user_id, secret, enabled, last_counter FROM two_factors WHERE user_id = $1 FOR UPDATE")
+            .bind(uid)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(two_factor)
+    }
+
+    async fn upsert_secret(&mut self, uid: i32, secret: String) -> Result<(), Error> {
+        query(
+            "
+            INSERT INTO two_factors (user_id, secret) VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret, enabled = false, last_counter = NULL, created_at = now()",
+        )
+        .bind(uid)
+        .bind(secret)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn enable(&mut self, uid: i32) -> Result<(), Error> {
+        query("UPDATE two_factors SET enabled = true WHERE user_id = $1").bind(uid).execute(&mut self.executor).await?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, uid: i32) -> Result<(), Error> {
+        query("DELETE FROM recovery_codes WHERE user_id = $1").bind(uid).execute(&mut self.executor).await?;
+        query("DELETE FROM two_factors WHERE user_id = $1").bind(uid).execute(&mut self.executor).await?;
+        Ok(())
+    }
+
+    async fn advance_last_counter(&mut self, uid: i32, counter: i64) -> Result<bool, Error> {
+        let advanced = query("UPDATE two_factors SET last_counter = $1 WHERE user_id = $2 AND (last_counter IS NULL OR last_counter < $1)")
+            .bind(counter)
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(advanced > 0)
+    }
+
+    async fn replace_recovery_codes(&mut self, uid: i32, hashes: Vec<String>) -> Result<(), Error> {
+        query("DELETE FROM recovery_codes WHERE user_id = $1").bind(uid).execute(&mut self.executor).await?;
+        QueryBuilder::new("INSERT INTO recovery_codes (user_id, code_hash)")
+            .push_values(hashes, |mut b, hash| {
+                b.push_bind(uid);
+                b.push_bind(hash);
+            })
+            .build()
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn use_recovery_code(&mut self, uid: i32, hash: String) -> Result<bool, Error> {
+        let used = query("UPDATE recovery_codes SET used_at = now() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL")
+            .bind(uid)
+            .bind(hash)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(used > 0)
+    }
+
+    async fn insert_challenge(&mut self, challenge: ChallengeInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO two_factor_challenges (user_id, token_hash, expires_at) VALUES ($1, $2, $3) RETURNING id")
+            .bind(challenge.user_id)
+            .bind(challenge.token_hash)
+            .bind(challenge.expires_at)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get_challenge_by_token_hash(&mut self, hash: String) -> Result<Option<Challenge>, Error> {
+        let challenge = query_as("SELECT id, user_id, attempts, expires_at, used_at FROM two_factor_challenges WHERE token_hash = $1 FOR UPDATE")
+            .bind(hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(challenge)
+    }
+
+    async fn increase_challenge_attempts(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE two_factor_challenges SET attempts = attempts + 1 WHERE id = $1")
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn mark_challenge_used(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE two_factor_challenges SET used_at = now() WHERE id = $1").bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+}
+
+const PERSONAL_TOKEN_COLUMNS: &str = "id, user_id, name, scopes, created_at, last_used_at, expires_at, revoked_at";
+
+impl<E> PersonalTokenCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, token: PersonalTokenInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO personal_tokens (user_id, name, token_hash, scopes, expires_at) VALUES ($1, $2, $3, $4, $5) RETURNING id")
+            .bind(token.user_id)
+            .bind(token.name)
+            .bind(token.token_hash)
+            .bind(token.scopes.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+            .bind(token.expires_at)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get(&mut self, id: i32) -> Result<PersonalToken, Error> {
+        let token = query_as(&format!("SELECT {} FROM personal_tokens WHERE id = $1", PERSONAL_TOKEN_COLUMNS))
+            .bind(id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(token)
+    }
+
+    async fn get_by_token_hash(&mut self, hash: String) -> Result<Option<PersonalToken>, Error> {
+        let token = query_as(&format!("SELECT {} FROM personal_tokens WHERE token_hash = $1", PERSONAL_TOKEN_COLUMNS))
+            .bind(hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(token)
+    }
+
+    async fn query(&mut self, uid: i32) -> Result<Vec<PersonalToken>, Error> {
+        let tokens = query_as(&format!(
+            "SELECT {} FROM personal_tokens WHERE user_id = $1 AND revoked_at IS NULL ORDER BY id DESC",
+            PERSONAL_TOKEN_COLUMNS
+        ))
+        .bind(uid)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(tokens)
+    }
+
+    async fn revoke(&mut self, uid: i32, id: i32) -> Result<i32, Error> {
+        let revoked = query("UPDATE personal_tokens SET revoked_at = now() WHERE user_id = $1 AND id = $2 AND revoked_at IS NULL")
+            .bind(uid)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(revoked as i32)
+    }
+
+    async fn touch(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE personal_tokens SET last_used_at = now() WHERE id = $1").bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+}
+
+impl<E> IdentityCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert_login(&mut self, login: OidcLoginInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO oidc_logins (state_hash, nonce, code_verifier, expires_at) VALUES ($1, $2, $3, $4) RETURNING id")
+            .bind(login.state_hash)
+            .bind(login.nonce)
+            .bind(login.code_verifier)
+            .bind(login.expires_at)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn take_login(&mut self, state_hash: String) -> Result<Option<OidcLogin>, Error> {
+        let login = query_as("DELETE FROM oidc_logins WHERE state_hash = $1 RETURNING id, nonce, code_verifier, expires_at")
+            .bind(state_hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(login)
+    }
+
+    async fn get_user_id(&mut self, issuer: String, subject: String) -> Result<Option<i32>, Error> {
+        let uid = query_scalar("SELECT user_id FROM user_identities WHERE issuer = $1 AND subject = $2")
+            .bind(issuer)
+            .bind(subject)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(uid)
+    }
+
+    async fn link(&mut self, uid: i32, issuer: String, subject: String) -> Result<(), Error> {
+        query("INSERT INTO user_identities (user_id, issuer, subject) VALUES ($1, $2, $3)")
+            .bind(uid)
+            .bind(issuer)
+            .bind(subject)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+}
+
+const GROUP_COLUMNS: &str = "g.id, g.organization_id, g.name, (SELECT COUNT(*) FROM member_group_users AS gu WHERE gu.group_id = g.id) AS member_count, g.created_at";
+
+impl<E> MemberGroupCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, organization_id: i32, name: String) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO member_groups (organization_id, name) VALUES ($1, $2) RETURNING id")
+            .bind(organization_id)
+            .bind(name)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get(&mut self, id: i32) -> Result<Option<MemberGroup>, Error> {
+        let group = query_as(&format!("SELECT {} FROM member_groups AS g WHERE g.id = $1", GROUP_COLUMNS))
+            .bind(id)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(group)
+    }
+
+    async fn query(&mut self, organization_id: i32) -> Result<Vec<MemberGroup>, Error> {
+        let groups = query_as(&format!("SELECT {} FROM member_groups AS g WHERE g.organization_id = $1 ORDER BY g.id", GROUP_COLUMNS))
+            .bind(organization_id)
+            .fetch_all(&mut self.executor)
+            .await?;
+        Ok(groups)
+    }
+
+    async fn exists_name(&mut self, organization_id: i32, name: &str) -> Result<bool, Error> {
+        let exists = query_scalar("SELECT EXISTS(SELECT 1 FROM member_groups WHERE organization_id = $1 AND name = $2)")
+            .bind(organization_id)
+            .bind(name)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(exists)
+    }
+
+    async fn rename(&mut self, id: i32, name: String) -> Result<(), Error> {
+        query("UPDATE member_groups SET name = $1 WHERE id = $2").bind(name).bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, id: i32) -> Result<(), Error> {
+        query("DELETE FROM member_groups WHERE id = $1").bind(id).execute(&mut self.executor).await?;
+        Ok(())
+    }
+
+    async fn insert_members(&mut self, id: i32, uids: Vec<i32>) -> Result<(), Error> {
+        query(
+            "
+            INSERT INTO member_group_users (group_id, user_id)
+            SELECT g.id, m.user_id
+            FROM member_groups AS g
+            JOIN organization_members AS m ON g.organization_id = m.organization_id
+            WHERE g.id = $1 AND m.user_id = ANY($2)
+            ON CONFLICT DO NOTHING",
+        )
+        .bind(id)
+        .bind(uids)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_member(&mut self, id: i32, uid: i32) -> Result<i32, Error> {
+        let deleted = query("DELETE FROM member_group_users WHERE group_id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(deleted as i32)
+    }
+
+    async fn members(&mut self, id: i32, pagination: Option<Pagination>) -> Result<Vec<GroupMember>, Error> {
+        let mut stmt = QueryBuilder::new(
+            "
+            SELECT u.id AS user_id, u.nickname, u.avatar
+            FROM member_group_users AS gu
+            JOIN users AS u ON gu.user_id = u.id
+            WHERE gu.group_id = ",
+        );
+        stmt.push_bind(id);
+        stmt.push(" ORDER BY gu.id ");
+        if let Some(page) = pagination {
+            stmt.push(page.to_sql_clause());
+        }
+        let list = stmt.build_query_as().fetch_all(&mut self.executor).await?;
+        Ok(list)
+    }
+
+    async fn count_members(&mut self, id: i32) -> Result<i64, Error> {
+        let count = query_scalar("SELECT COUNT(*) FROM member_group_users WHERE group_id = $1")
+            .bind(id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(count)
+    }
+
+    async fn delete_member_of_organization(&mut self, organization_id: i32, uid: i32) -> Result<(), Error> {
+        query("DELETE FROM member_group_users WHERE user_id = $1 AND group_id IN (SELECT id FROM member_groups WHERE organization_id = $2)")
+            .bind(uid)
+            .bind(organization_id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_vote_groups(&mut self, vote_id: i32, ids: Vec<i32>) -> Result<(), Error> {
+        query("DELETE FROM vote_groups WHERE vote_id = $1").bind(vote_id).execute(&mut self.executor).await?;
+        if ids.is_empty() {
+            return Ok(());
+        }
+        QueryBuilder::new("INSERT INTO vote_groups (vote_id, group_id)")
+            .push_values(ids, |mut b, id| {
+                b.push_bind(vote_id);
+                b.push_bind(id);
+            })
+            .push(" ON CONFLICT DO NOTHING")
+            .build()
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn vote_groups(&mut self, vote_id: i32) -> Result<Vec<MemberGroup>, Error> {
+        let groups = query_as(&format!(
+            "SELECT {} FROM member_groups AS g JOIN vote_groups AS vg ON g.id = vg.group_id WHERE vg.vote_id = $1 ORDER BY g.id",
+            GROUP_COLUMNS
+        ))
+        .bind(vote_id)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(groups)
+    }
+
+    async fn is_in_audience(&mut self, vote_id: i32, uid: i32) -> Result<bool, Error> {
+        let is_in = query_scalar("SELECT EXISTS(SELECT 1 FROM vote_audiences WHERE vote_id = $1 AND user_id = $2)")
+            .bind(vote_id)
+            .bind(uid)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(is_in)
+    }
+}
+
+const SESSION_COLUMNS: &str = "id, user_id, device, ip, user_agent, created_at, last_used_at, expires_at, revoked_at";
+
+impl<E> SessionCommon for PgSqlx<E>
+where
+    for<'e> &'e mut E: Executor<'e, Database = Postgres>,
+{
+    async fn insert(&mut self, session: SessionInsert) -> Result<i32, Error> {
+        let id = query_scalar("INSERT INTO sessions (user_id, refresh_token_hash, expires_at, device, ip, user_agent) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id")
+            .bind(session.user_id)
+            .bind(session.refresh_token_hash)
+            .bind(session.expires_at)
+            .bind(session.client.device())
+            .bind(session.client.ip)
+            .bind(session.client.user_agent)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get_by_refresh_token_hash(&mut self, hash: String) -> Result<Option<Session>, Error> {
+        let session = query_as(&format!("SELECT {} FROM sessions WHERE refresh_token_hash = $1 FOR UPDATE", SESSION_COLUMNS))
+            .bind(hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(session)
+    }
+
+    async fn get_by_previous_token_hash(&mut self, hash: String) -> Result<Option<Session>, Error> {
+        let session = query_as(&format!("SELECT {} FROM sessions WHERE previous_token_hash = $1", SESSION_COLUMNS))
+            .bind(hash)
+            .fetch_optional(&mut self.executor)
+            .await?;
+        Ok(session)
+    }
+
+    async fn rotate(&mut self, id: i32, hash: String, expires_at: DateTime<Utc>, client: Client) -> Result<(), Error> {
+        query(
+            "
+            UPDATE sessions
+            SET previous_token_hash = refresh_token_hash, refresh_token_hash = $1, expires_at = $2, last_used_at = now(), device = $3, ip = $4, user_agent = $5
+            WHERE id = $6",
+        )
+        .bind(hash)
+        .bind(expires_at)
+        .bind(client.device())
+        .bind(client.ip)
+        .bind(client.user_agent)
+        .bind(id)
+        .execute(&mut self.executor)
+        .await?;
+        Ok(())
+    }
+
+    async fn is_active(&mut self, id: i32) -> Result<bool, Error> {
+        let active = query_scalar("SELECT EXISTS(SELECT 1 FROM sessions WHERE id = $1 AND revoked_at IS NULL AND expires_at > now())")
+            .bind(id)
+            .fetch_one(&mut self.executor)
+            .await?;
+        Ok(active)
+    }
+
+    async fn revoke(&mut self, id: i32) -> Result<(), Error> {
+        query("UPDATE sessions SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
+
+    async fn query(&mut self, uid: i32) -> Result<Vec<Session>, Error> {
+        let sessions = query_as(&format!(
+            "SELECT {} FROM sessions WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > now() ORDER BY last_used_at DESC",
+            SESSION_COLUMNS
+        ))
+        .bind(uid)
+        .fetch_all(&mut self.executor)
+        .await?;
+        Ok(sessions)
+    }
+
+    async fn revoke_of_user(&mut self, uid: i32, id: i32) -> Result<i32, Error> {
+        let revoked = query("UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND id = $2 AND revoked_at IS NULL")
+            .bind(uid)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(revoked as i32)
+    }
+
+    async fn revoke_others(&mut self, uid: i32, id: i32) -> Result<i32, Error> {
+        let revoked = query("UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL")
+            .bind(uid)
+            .bind(id)
+            .execute(&mut self.executor)
+            .await?
+            .rows_affected();
+        Ok(revoked as i32)
+    }
+
+    async fn revoke_all(&mut self, uid: i32) -> Result<(), Error> {
+        query("UPDATE sessions SET revoked_at = now() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(uid)
+            .execute(&mut self.executor)
+            .await?;
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 use actix_web::cookie::time::error::{ComponentRange, IndeterminateOffset};
+use actix_web::http::StatusCode;
 use actix_web::ResponseError;
 
 use crate::actix_multipart::MultipartError;
@@ -58,11 +59,30 @@ pub enum Error {
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("vote is closed or expired(id: {0})")]
+    VoteClosed(i32),
+
+    #[error("too many requests")]
+    TooManyRequests,
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
-impl ResponseError for Error {}
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Error::VoteClosed(_) => StatusCode::FORBIDDEN,
+            Error::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
 
 impl From<MultipartError> for Error {
     fn from(err: MultipartError) -> Self {
@@ -1,38 +1,93 @@
 use crate::context::UserInfo;
 use crate::core::services::answer::{bulk_submit, submit};
 use crate::error::Error;
+use crate::response::BallotResponse;
 use crate::sqlx::PgPool;
 use crate::{
     actix_web::{
         http::StatusCode,
         web::{Data, Json, Path},
-        HttpResponse,
+        HttpRequest, HttpResponse,
     },
-    core::models::answer::{BulkSubmit, Submit},
+    core::models::answer::{BulkSubmit, Rating, Submit, Value},
     core::ports::repository::TxStore,
     database::sqlx::PgSqlx,
 };
 
-pub async fn submit_answer(user_info: UserInfo, qst_id: Path<(i32,)>, Json(answer): Json<Vec<i32>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+pub const BALLOT_TOKEN_HEADER: &str = "X-Ballot-Token";
+
+// the ballot token of anonymous votes is sent in a header so that all the answer endpoints accept it
+pub fn ballot_token(req: &HttpRequest) -> Option<String> {
+    req.headers().get(BALLOT_TOKEN_HEADER).and_then(|v| v.to_str().ok()).map(|v| v.to_owned())
+}
+
+pub fn submitted(status: StatusCode, issued: Option<String>) -> HttpResponse {
+    match issued {
+        Some(ballot_token) => HttpResponse::build(status).json(BallotResponse { ballot_token }),
+        None => HttpResponse::build(status).finish(),
+    }
+}
+
+pub async fn submit_answer(req: HttpRequest, user_info: UserInfo, qst_id: Path<(i32,)>, Json(answer): Json<Vec<i32>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
     let qst_id = qst_id.into_inner().0;
     let tx = db.begin().await?;
     let mut store = PgSqlx::new(tx);
-    submit(
+    let issued = submit(
         &mut store,
         user_info.id,
         Submit {
             question_id: qst_id,
             option_ids: answer,
+            value: None,
+            ratings: Vec::new(),
+        },
+        ballot_token(&req),
+    )
+    .await?;
+    store.commit().await?;
+    Ok(submitted(StatusCode::OK, issued))
+}
+
+pub async fn submit_value(req: HttpRequest, user_info: UserInfo, qst_id: Path<(i32,)>, Json(value): Json<Value>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let mut store = PgSqlx::new(db.begin().await?);
+    let issued = submit(
+        &mut store,
+        user_info.id,
+        Submit {
+            question_id: qst_id.0,
+            option_ids: Vec::new(),
+            value: Some(value),
+            ratings: Vec::new(),
+        },
+        ballot_token(&req),
+    )
+    .await?;
+    store.commit().await?;
+    Ok(submitted(StatusCode::OK, issued))
+}
+
+pub async fn submit_ratings(req: HttpRequest, user_info: UserInfo, qst_id: Path<(i32,)>, Json(ratings): Json<Vec<Rating>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let mut store = PgSqlx::new(db.begin().await?);
+    let issued = submit(
+        &mut store,
+        user_info.id,
+        Submit {
+            question_id: qst_id.0,
+            option_ids: Vec::new(),
+            value: None,
+            ratings,
         },
+        ballot_token(&req),
     )
     .await?;
     store.commit().await?;
-    Ok(HttpResponse::build(StatusCode::OK).finish())
+    Ok(submitted(StatusCode::OK, issued))
 }
 
-pub async fn submit_answers(db: Data<PgPool>, user_info: UserInfo, Json(answers): Json<BulkSubmit>) -> Result<HttpResponse, Error> {
+pub async fn submit_answers(req: HttpRequest, db: Data<PgPool>, user_info: UserInfo, Json(mut answers): Json<BulkSubmit>) -> Result<HttpResponse, Error> {
     let tx = db.begin().await?;
     let store = PgSqlx::new(tx);
-    bulk_submit(store, user_info.id, answers).await?;
-    Ok(HttpResponse::build(StatusCode::CREATED).finish())
+    answers.ballot_token = ballot_token(&req);
+    let issued = bulk_submit(store, user_info.id, answers).await?;
+    Ok(submitted(StatusCode::CREATED, issued))
 }
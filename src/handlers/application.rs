@@ -1,22 +1,54 @@
 use actix_web::{
     http::StatusCode,
-    web::{Data, Json},
+    web::{Data, Json, Path, Query},
     HttpResponse,
 };
-use sqlx::{query, PgPool};
+use sqlx::PgPool;
 
 use crate::{
     context::UserInfo,
-    core::models::application::{ApplicationStatus, JoinApplicationInsert},
+    core::models::application::{JoinApplication, JoinApplicationInsert, Reject},
+    core::services::application::{apply, approve, my_applications as my_applications_, pending_applications, reject as reject_, withdraw as withdraw_},
+    database::sqlx::PgSqlx,
     error::Error,
+    request::Pagination,
+    response::{CreateResponse, List},
 };
 
 pub async fn create_join_application(user_info: UserInfo, Json(data): Json<JoinApplicationInsert>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
-    query("INSERT INTO join_applications (user_id, organization_id, status) VALUES ($1, $2, $3)")
-        .bind(user_info.id)
-        .bind(data.organization_id)
-        .bind(ApplicationStatus::Pending)
-        .execute(&mut db.acquire().await?)
-        .await?;
-    Ok(HttpResponse::new(StatusCode::CREATED))
+    let tx = PgSqlx::new(db.begin().await?);
+    let id = apply(tx, user_info.id, data.organization_id).await?;
+    Ok(HttpResponse::build(StatusCode::CREATED).json(CreateResponse { id }))
+}
+
+pub async fn list(user_info: UserInfo, org_id: Path<(i32,)>, Query(Pagination { page, size }): Query<Pagination>, db: Data<PgPool>) -> Result<Json<List<JoinApplication>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let (list, total) = pending_applications(&mut store, user_info.id, org_id.0, page, size).await?;
+    Ok(Json(List::new(list, total)))
+}
+
+pub async fn approve_application(user_info: UserInfo, path: Path<(i32, i32)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, application_id) = path.into_inner();
+    let tx = PgSqlx::new(db.begin().await?);
+    approve(tx, user_info.id, org_id, application_id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn reject(user_info: UserInfo, path: Path<(i32, i32)>, Json(Reject { reason }): Json<Reject>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, application_id) = path.into_inner();
+    let tx = PgSqlx::new(db.begin().await?);
+    reject_(tx, user_info.id, org_id, application_id, reason).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn my_applications(user_info: UserInfo, Query(Pagination { page, size }): Query<Pagination>, db: Data<PgPool>) -> Result<Json<List<JoinApplication>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let (list, total) = my_applications_(&mut store, user_info.id, page, size).await?;
+    Ok(Json(List::new(list, total)))
+}
+
+pub async fn withdraw(user_info: UserInfo, application_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let tx = PgSqlx::new(db.begin().await?);
+    withdraw_(tx, user_info.id, application_id.0).await?;
+    Ok(HttpResponse::Ok().finish())
 }
@@ -6,6 +6,9 @@ use sqlx::{PgPool, QueryBuilder};
 use crate::actix_web::web::{Json, Path, Query};
 use crate::chrono::NaiveDate;
 use crate::context::UserInfo;
+use crate::core::services::permission::authorize_answer;
+use crate::core::services::vote::check_submission;
+use crate::database::sqlx::PgSqlx;
 use crate::error::Error;
 use crate::serde::{Deserialize, Serialize};
 use crate::sqlx::{query, query_as, FromRow};
@@ -37,24 +40,11 @@ fn merge_date_range(mut ranges: Vec<DateRange>) -> Vec<DateRange> {
 pub async fn submit_date_ranges(user_info: UserInfo, vote_id: Path<(i32,)>, Json(mut dates): Json<Vec<DateRange>>, db: Data<PgPool>) -> Result<Json<Vec<DateRange>>, Error> {
     dates = merge_date_range(dates);
     let vote_id = vote_id.into_inner().0;
+    let mut store = PgSqlx::new(db.acquire().await?);
+    authorize_answer(&mut store, vote_id, user_info.id).await?;
+    // the dates are not flagged as late, they are only refused once the vote stops accepting answers
+    check_submission(&mut store, vote_id).await?;
     let mut tx = db.begin().await?;
-    let (is_valid,): (bool,) = query_as(
-        r#"
-    SELECT EXISTS(
-        SELECT * 
-        FROM users AS u
-        JOIN organization_members AS uo ON u.id = uo.user_id
-        JOIN organizations AS o ON uo.organization_id = o.id
-        JOIN votes AS v ON o.id = votes.organization_id
-        WHERE u.id = $1 AND v.id = $2)"#,
-    )
-    .bind(user_info.id)
-    .bind(vote_id)
-    .fetch_one(&mut tx)
-    .await?;
-    if !is_valid {
-        return Err(Error::BusinessError("Vote not exists or permission danied".into()));
-    }
     query("DELETE date_ranges WHERE user_id = $1 AND vote_id = $2")
         .bind(user_info.id)
         .bind(vote_id)
@@ -127,21 +117,15 @@ pub async fn date_range_list(user_info: UserInfo, vote_id: Path<(i32,)>, db: Dat
 const YEAR_STAT: &str = r#"
 WITH 
     total AS ( 
-        SELECT COUNT(DISTINCT u.id) AS count_
-        FROM votes AS v 
-        JOIN organizations AS o ON v.organization_id = o.id
-        JOIN organization_members AS uo ON o.id = uo.organization_id
-        JOIN users AS u ON uo.user_id = u.id
-        WHERE v.id = $1
+        SELECT COUNT(DISTINCT au.user_id) AS count_
+        FROM vote_audiences AS au
+        WHERE au.vote_id = $1
     ),
     t AS (
-        SELECT d.date_ AS date_, COUNT(DISTINCT u.id) AS count_
-        FROM votes AS v 
-        JOIN organizations AS o ON v.organization_id = o.id
-        JOIN organization_members AS uo ON o.id = uo.organization_id
-        JOIN users AS u ON uo.user_id = u.id
-        JOIN dates AS d ON v.id = d.vote_id AND u.id = d.user_id
-        WHERE  v.id = $1 AND EXTRACT(YEAR FROM d.date_) = $2
+        SELECT d.date_ AS date_, COUNT(DISTINCT au.user_id) AS count_
+        FROM vote_audiences AS au
+        JOIN dates AS d ON au.vote_id = d.vote_id AND au.user_id = d.user_id
+        WHERE  au.vote_id = $1 AND EXTRACT(YEAR FROM d.date_) = $2
         GROUP BY date_
         ORDER BY date_
     ),
@@ -151,11 +135,11 @@ WITH
 
     SELECT 
         ms.month AS month, 
-        SUM(CASE WHEN (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) < 0.25 THEN 1 ELSE 0 END) AS u25_count,
-        SUM(CASE WHEN (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) >= 0.25 AND (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) < 0.5 THEN 1 ELSE 0 END) AS u50_count,
-        SUM(CASE WHEN (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) >= 0.5 AND (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) < 0.75 THEN 1 ELSE 0 END) AS u75_count,
-        SUM(CASE WHEN (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) >= 0.75 AND (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) < 1 THEN 1 ELSE 0 END) AS u100_count,
-        SUM(CASE WHEN (t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) = 1 THEN 1 ELSE 0 END) AS p100_count
+        SUM(CASE WHEN (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) < 0.25 THEN 1 ELSE 0 END) AS u25_count,
+        SUM(CASE WHEN (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) >= 0.25 AND (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) < 0.5 THEN 1 ELSE 0 END) AS u50_count,
+        SUM(CASE WHEN (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) >= 0.5 AND (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) < 0.75 THEN 1 ELSE 0 END) AS u75_count,
+        SUM(CASE WHEN (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) >= 0.75 AND (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) < 1 THEN 1 ELSE 0 END) AS u100_count,
+        SUM(CASE WHEN (t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) = 1 THEN 1 ELSE 0 END) AS p100_count
     FROM 
         months AS ms LEFT JOIN t ON  ms.month = EXTRACT(MONTH FROM t.date_)
     GROUP BY month
@@ -165,28 +149,22 @@ WITH
 const MONTH_STAT: &str = r#"
 WITH 
     total AS ( 
-        SELECT COUNT(DISTINCT u.id) AS count_
-        FROM votes AS v 
-        JOIN organizations AS o ON v.organization_id = o.id
-        JOIN organization_members AS uo ON o.id = uo.organization_id
-        JOIN users AS u ON uo.user_id = u.id
-        WHERE v.id = $1
+        SELECT COUNT(DISTINCT au.user_id) AS count_
+        FROM vote_audiences AS au
+        WHERE au.vote_id = $1
     ),
     t AS (
-        SELECT d.date_ AS date_, COUNT(DISTINCT u.id) AS count_
-        FROM votes AS v 
-        JOIN organizations AS o ON v.organization_id = o.id
-        JOIN organization_members AS uo ON o.id = uo.organization_id
-        JOIN users AS u ON uo.user_id = u.id
-        JOIN dates AS d ON v.id = d.vote_id AND u.id = d.user_id
-        WHERE  v.id = $1 AND EXTRACT(YEAR FROM d.date_) = $2 AND EXTRACT(MONTH FROM d.date_) = $3
+        SELECT d.date_ AS date_, COUNT(DISTINCT au.user_id) AS count_
+        FROM vote_audiences AS au
+        JOIN dates AS d ON au.vote_id = d.vote_id AND au.user_id = d.user_id
+        WHERE  au.vote_id = $1 AND EXTRACT(YEAR FROM d.date_) = $2 AND EXTRACT(MONTH FROM d.date_) = $3
         GROUP BY date_
         ORDER BY date_
     ),
     dates (date_) AS (
         SELECT DATE(GENERATE_SERIES(DATE(CONCAT($2, '-01-01')), DATE(CONCAT($2, '-12-31')), '1 day'::interval))
     )
-    SELECT ds.date_ AS date_, COALESCE((SUM(t.count_::FLOAT / (SELECT count_ FROM total)::FLOAT) * 10000)::Integer, 0) AS rate
+    SELECT ds.date_ AS date_, COALESCE((SUM(t.count_::FLOAT / (SELECT NULLIF(count_, 0) FROM total)::FLOAT) * 10000)::Integer, 0) AS rate
     FROM dates AS ds LEFT JOIN t ON ds.date_ = t.date_
     WHERE EXTRACT(YEAR FROM ds.date_) = $2 AND EXTRACT(MONTH FROM ds.date_) = $3
     GROUP BY ds.date_
@@ -0,0 +1,65 @@
+use actix_web::HttpResponse;
+
+use crate::actix_web::web::{Data, Json, Path, Query};
+use crate::context::UserInfo;
+use crate::core::models::group::{GroupCreate, GroupMember, GroupUpdate, MemberGroup};
+use crate::core::services::group::{add_group_members, create_group, delete_group, group_members, groups, remove_group_member, rename_group, target_vote, vote_groups};
+use crate::database::sqlx::PgSqlx;
+use crate::error::Error;
+use crate::request::Pagination;
+use crate::response::{DeleteResponse, List};
+use crate::sqlx::PgPool;
+
+pub async fn list(user_info: UserInfo, org_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json<Vec<MemberGroup>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let list = groups(&mut store, user_info.id, org_id.0).await?;
+    Ok(Json(list))
+}
+
+pub async fn create(user_info: UserInfo, org_id: Path<(i32,)>, Json(body): Json<GroupCreate>, db: Data<PgPool>) -> Result<Json<MemberGroup>, Error> {
+    let group = create_group(PgSqlx::new(db.begin().await?), user_info.id, org_id.0, body).await?;
+    Ok(Json(group))
+}
+
+pub async fn update(user_info: UserInfo, path: Path<(i32, i32)>, Json(body): Json<GroupUpdate>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    rename_group(PgSqlx::new(db.begin().await?), user_info.id, org_id, id, body).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn delete(user_info: UserInfo, path: Path<(i32, i32)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    delete_group(PgSqlx::new(db.begin().await?), user_info.id, org_id, id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn members(user_info: UserInfo, path: Path<(i32, i32)>, Query(Pagination { page, size }): Query<Pagination>, db: Data<PgPool>) -> Result<Json<List<GroupMember>>, Error> {
+    let (org_id, id) = path.into_inner();
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let (list, total) = group_members(&mut store, user_info.id, org_id, id, page, size).await?;
+    Ok(Json(List::new(list, total)))
+}
+
+pub async fn add_members(user_info: UserInfo, path: Path<(i32, i32)>, Json(user_ids): Json<Vec<i32>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    add_group_members(PgSqlx::new(db.begin().await?), user_info.id, org_id, id, user_ids).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn remove_member(user_info: UserInfo, path: Path<(i32, i32, i32)>, db: Data<PgPool>) -> Result<Json<DeleteResponse>, Error> {
+    let (org_id, id, user_id) = path.into_inner();
+    let deleted = remove_group_member(PgSqlx::new(db.begin().await?), user_info.id, org_id, id, user_id).await?;
+    Ok(Json(DeleteResponse::new(deleted)))
+}
+
+// the groups the vote is targeted at, empty when it is targeted at the whole organization
+pub async fn of_vote(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json<Vec<MemberGroup>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let list = vote_groups(&mut store, user_info.id, vote_id.0).await?;
+    Ok(Json(list))
+}
+
+pub async fn target(user_info: UserInfo, vote_id: Path<(i32,)>, Json(group_ids): Json<Vec<i32>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    target_vote(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0, group_ids).await?;
+    Ok(HttpResponse::Ok().finish())
+}
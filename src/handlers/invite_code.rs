@@ -0,0 +1,43 @@
+use actix_web::HttpResponse;
+
+use crate::actix_web::web::{Data, Json, Path};
+use crate::context::UserInfo;
+use crate::core::models::invite_code::{Admins, InviteCode, InviteCodeCreate};
+use crate::core::services::invite_code::{create_invite_code, invite_codes, revoke_invite_code};
+use crate::database::sqlx::PgSqlx;
+use crate::error::Error;
+use crate::sqlx::PgPool;
+
+pub async fn create(user_info: UserInfo, Json(body): Json<InviteCodeCreate>, admins: Data<Admins>, db: Data<PgPool>) -> Result<Json<InviteCode>, Error> {
+    let code = create_invite_code(PgSqlx::new(db.begin().await?), &admins, user_info.id, None, body).await?;
+    Ok(Json(code))
+}
+
+pub async fn list(user_info: UserInfo, admins: Data<Admins>, db: Data<PgPool>) -> Result<Json<Vec<InviteCode>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let codes = invite_codes(&mut store, &admins, user_info.id, None).await?;
+    Ok(Json(codes))
+}
+
+pub async fn revoke(user_info: UserInfo, id: Path<(i32,)>, admins: Data<Admins>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    revoke_invite_code(PgSqlx::new(db.begin().await?), &admins, user_info.id, None, id.0).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+// the invitations of an organization, signing up with one joins the organization
+pub async fn create_invitation(user_info: UserInfo, org_id: Path<(i32,)>, Json(body): Json<InviteCodeCreate>, admins: Data<Admins>, db: Data<PgPool>) -> Result<Json<InviteCode>, Error> {
+    let code = create_invite_code(PgSqlx::new(db.begin().await?), &admins, user_info.id, Some(org_id.0), body).await?;
+    Ok(Json(code))
+}
+
+pub async fn invitations(user_info: UserInfo, org_id: Path<(i32,)>, admins: Data<Admins>, db: Data<PgPool>) -> Result<Json<Vec<InviteCode>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let codes = invite_codes(&mut store, &admins, user_info.id, Some(org_id.0)).await?;
+    Ok(Json(codes))
+}
+
+pub async fn revoke_invitation(user_info: UserInfo, path: Path<(i32, i32)>, admins: Data<Admins>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, id) = path.into_inner();
+    revoke_invite_code(PgSqlx::new(db.begin().await?), &admins, user_info.id, Some(org_id), id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
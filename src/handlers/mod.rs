@@ -2,20 +2,28 @@ pub mod answer;
 pub mod application;
 pub mod authorizer;
 pub mod date;
+pub mod group;
+pub mod invite_code;
+pub mod oidc;
 pub mod option;
 pub mod organization;
+pub mod personal_token;
 pub mod question;
+pub mod share_link;
+pub mod two_factor;
 pub mod upload;
 pub mod user;
 pub mod vote;
 
 use actix_web::{
-    cookie::{time::OffsetDateTime, CookieBuilder},
-    http::StatusCode,
-    HttpResponseBuilder,
+    cookie::{
+        time::{Duration, OffsetDateTime},
+        CookieBuilder,
+    },
+    http::{header::USER_AGENT, StatusCode},
+    HttpRequest, HttpResponseBuilder,
 };
-use rand::Rng;
-use sqlx::{query, query_as, PgPool};
+use sqlx::{query_as, PgPool};
 use std::ops::Add;
 
 use crate::{
@@ -24,18 +32,23 @@ use crate::{
         web::{Data, Json},
         HttpResponse,
     },
-    core::ports::tokener::Tokener,
-    impls::tokener::jwt::JWT,
+    core::ports::{notifier::Notifier, tokener::Tokener},
+    impls::{hasher::argon2::Argon2id, tokener::jwt::JWT},
 };
 
+use crate::core::models::session::Client;
 use crate::core::models::user::User;
+use crate::core::models::verification::Policy;
+use crate::core::services::password::{request_password_reset as request_password_reset_, reset_password as reset_password_};
+use crate::core::services::session::{create_session, logout as logout_, refresh as refresh_, Refreshed, ACCESS_TOKEN_MINUTES, REFRESH_TOKEN_DAYS};
+use crate::core::services::two_factor::{create_challenge, is_enabled, verify_challenge};
+use crate::core::services::user::{signup as signup_, verify_password, Signup as SignupData};
+use crate::core::services::verification::{send_code, verify_code};
+use crate::database::sqlx::PgSqlx;
 use crate::dotenv;
 use crate::error::Error;
-use crate::hex::ToHex;
-use crate::middlewares::jwt::{Claim, JWT_SECRET, JWT_TOKEN};
-use crate::rand::thread_rng;
-use crate::serde::Deserialize;
-use crate::sha2::{Digest, Sha256};
+use crate::middlewares::jwt::{Claim, JWT_SECRET, JWT_TOKEN, REFRESH_TOKEN};
+use crate::serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct Login {
@@ -43,47 +56,83 @@ pub struct Login {
     pub password: String,
 }
 
-fn hash_password(pass: &str, slt: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(pass);
-    hasher.update(slt);
-    hasher.finalize().encode_hex()
+// where the request comes from, recorded on the session
+fn client(req: &HttpRequest) -> Client {
+    Client {
+        ip: req.connection_info().realip_remote_addr().map(|ip| ip.to_owned()),
+        user_agent: req.headers().get(USER_AGENT).and_then(|ua| ua.to_str().ok()).map(|ua| ua.to_owned()),
+    }
 }
 
-pub async fn login(Json(Login { username, password }): Json<Login>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+pub async fn login(req: HttpRequest, Json(Login { username, password }): Json<Login>, policy: Data<Policy>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
     let mut conn = db.acquire().await?;
     if let Some(user) = query_as::<_, User>(r#"SELECT * FROM users WHERE phone = $1 OR email = $1"#)
         .bind(&username)
         .fetch_optional(&mut conn)
         .await?
     {
-        if hash_password(&password, &user.salt) != user.password {
+        if !verify_password(PgSqlx::new(db.begin().await?), &Argon2id::default(), &user, &password).await? {
             return Ok(HttpResponse::build(StatusCode::FORBIDDEN).finish());
         }
-        let claim = Claim {
-            user: user.id.to_string(),
-            exp: chrono::Utc::now().add(chrono::Duration::days(30)).timestamp(),
-        };
-        let secret = dotenv::var(JWT_SECRET)?;
-        let tokener = JWT::new(secret.as_bytes().to_owned());
-        let token = tokener.gen_token(&claim)?;
-        return Ok(HttpResponse::build(StatusCode::OK).cookie(Cookie::new(JWT_TOKEN, token)).finish());
+        if policy.login && !user.is_verified(&username) {
+            return Err(Error::BusinessError(format!("{} has not been verified", username)));
+        }
+        if is_enabled(&mut PgSqlx::new(db.acquire().await?), user.id).await? {
+            let challenge = create_challenge(PgSqlx::new(db.begin().await?), user.id).await?;
+            return Ok(HttpResponse::Ok().json(TwoFactorChallenge { challenge }));
+        }
+        let refreshed = create_session(PgSqlx::new(db.begin().await?), user.id, client(&req)).await?;
+        return session_response(refreshed);
     }
     Err(Error::BusinessError("invalid username or password".into()))
 }
 
-fn random_salt() -> String {
-    let chars = vec![
-        '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B',
-        'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-    ];
-    let mut slt = String::new();
-    let mut rng = thread_rng();
-    for _ in 0..32 {
-        let i = rng.gen_range(0..61_usize);
-        slt.push(chars[i]);
-    }
-    slt
+#[derive(Debug, Serialize)]
+struct TwoFactorChallenge {
+    // answered with the second factor at `login/two_factor`
+    challenge: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLogin {
+    challenge: String,
+    // a TOTP code or a recovery code
+    code: String,
+}
+
+pub async fn login_two_factor(req: HttpRequest, Json(TwoFactorLogin { challenge, code }): Json<TwoFactorLogin>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let uid = verify_challenge(PgSqlx::new(db.begin().await?), &challenge, &code).await?;
+    let refreshed = create_session(PgSqlx::new(db.begin().await?), uid, client(&req)).await?;
+    session_response(refreshed)
+}
+
+// a short-lived access token along with the refresh token of the session
+fn session_response(refreshed: Refreshed) -> Result<HttpResponse, Error> {
+    let claim = Claim {
+        user: refreshed.user_id.to_string(),
+        exp: chrono::Utc::now().add(chrono::Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp(),
+        guest: None,
+        sid: Some(refreshed.session_id),
+    };
+    let secret = dotenv::var(JWT_SECRET)?;
+    let tokener = JWT::new(secret.as_bytes().to_owned());
+    let token = tokener.gen_token(&claim)?;
+    Ok(HttpResponse::build(StatusCode::OK)
+        .cookie(Cookie::new(JWT_TOKEN, token))
+        .cookie(
+            CookieBuilder::new(REFRESH_TOKEN, refreshed.refresh_token)
+                .path("/")
+                .http_only(true)
+                .max_age(Duration::days(REFRESH_TOKEN_DAYS))
+                .finish(),
+        )
+        .finish())
+}
+
+pub async fn refresh(req: HttpRequest, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let refresh_token = req.cookie(REFRESH_TOKEN).ok_or(Error::Unauthorized)?;
+    let refreshed = refresh_(PgSqlx::new(db.begin().await?), refresh_token.value(), client(&req)).await?;
+    session_response(refreshed)
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -105,33 +154,69 @@ pub async fn signup(
     }): Json<Signup>,
     db: Data<PgPool>,
 ) -> Result<HttpResponse, Error> {
-    let mut tx = db.begin().await?;
-    let (deleted,): (i64,) = query_as(
-        "
-    WITH deleted AS (DELETE FROM invite_codes WHERE code = $1 RETURNING *)
-    SELECT COUNT(*) FROM deleted",
+    signup_(
+        PgSqlx::new(db.begin().await?),
+        &Argon2id::default(),
+        SignupData {
+            nickname,
+            phone,
+            email,
+            password,
+            invite_code,
+        },
     )
-    .bind(invite_code)
-    .fetch_one(&mut tx)
     .await?;
-    if deleted == 0 {
-        return Err(Error::BusinessError("invalid invite code".into()));
-    }
-    let slt = random_salt();
-    query("INSERT INTO users (nickname, phone, email, password, salt) VALUES ($1, $2, $3, $4, $5)")
-        .bind(nickname)
-        .bind(phone)
-        .bind(email)
-        .bind(hash_password(&password, &slt))
-        .bind(slt)
-        .execute(&mut tx)
-        .await?;
-    tx.commit().await?;
     Ok(HttpResponse::build(StatusCode::OK).finish())
 }
 
-pub async fn logout() -> HttpResponse {
-    HttpResponseBuilder::new(StatusCode::OK)
+pub async fn logout(req: HttpRequest, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    if let Some(refresh_token) = req.cookie(REFRESH_TOKEN) {
+        logout_(PgSqlx::new(db.begin().await?), refresh_token.value()).await?;
+    }
+    Ok(HttpResponseBuilder::new(StatusCode::OK)
         .cookie(CookieBuilder::new(JWT_TOKEN, "").expires(OffsetDateTime::now_utc()).finish())
-        .finish()
+        .cookie(CookieBuilder::new(REFRESH_TOKEN, "").path("/").expires(OffsetDateTime::now_utc()).finish())
+        .finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetRequest {
+    username: String,
+}
+
+pub async fn request_password_reset<N: Notifier>(Json(PasswordResetRequest { username }): Json<PasswordResetRequest>, notifier: Data<N>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    request_password_reset_(PgSqlx::new(db.begin().await?), notifier.get_ref(), username).await?;
+    Ok(HttpResponse::build(StatusCode::OK).finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordReset {
+    token: String,
+    password: String,
+}
+
+pub async fn reset_password(Json(PasswordReset { token, password }): Json<PasswordReset>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    reset_password_(PgSqlx::new(db.begin().await?), &Argon2id::default(), &token, &password).await?;
+    Ok(HttpResponse::build(StatusCode::OK).finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerificationRequest {
+    username: String,
+}
+
+pub async fn request_verification<N: Notifier>(Json(VerificationRequest { username }): Json<VerificationRequest>, notifier: Data<N>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    send_code(PgSqlx::new(db.begin().await?), notifier.get_ref(), username).await?;
+    Ok(HttpResponse::build(StatusCode::OK).finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Verify {
+    username: String,
+    code: String,
+}
+
+pub async fn verify(Json(Verify { username, code }): Json<Verify>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    verify_code(PgSqlx::new(db.begin().await?), username, &code).await?;
+    Ok(HttpResponse::build(StatusCode::OK).finish())
 }
@@ -0,0 +1,49 @@
+use actix_web::cookie::{time::Duration, Cookie, CookieBuilder};
+use actix_web::http::header::LOCATION;
+use actix_web::web::{Data, Query};
+use actix_web::{HttpRequest, HttpResponse};
+use sqlx::PgPool;
+
+use super::{client, session_response, TwoFactorChallenge};
+use crate::core::ports::identity::IdentityProvider;
+use crate::core::services::oidc::{begin_login, finish_login, redeem, Authorization, LOGIN_MINUTES};
+use crate::core::services::session::create_session;
+use crate::core::services::two_factor::{create_challenge, is_enabled};
+use crate::database::sqlx::PgSqlx;
+use crate::error::Error;
+use crate::serde::Deserialize;
+
+pub static OIDC_STATE: &str = "OIDC_STATE";
+
+// the browser is sent to the provider, the state cookie comes back with the callback
+pub async fn login<P: IdentityProvider>(provider: Data<P>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let Authorization { state, url } = begin_login(PgSqlx::new(db.begin().await?), provider.get_ref()).await?;
+    Ok(HttpResponse::Found()
+        .insert_header((LOCATION, url))
+        .cookie(CookieBuilder::new(OIDC_STATE, state).path("/").http_only(true).max_age(Duration::minutes(LOGIN_MINUTES)).finish())
+        .finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Callback {
+    state: String,
+    code: String,
+}
+
+pub async fn callback<P: IdentityProvider>(req: HttpRequest, Query(Callback { state, code }): Query<Callback>, provider: Data<P>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    // a callback forged for another browser does not come with the state cookie
+    if req.cookie(OIDC_STATE).map(|c| c.value() != state).unwrap_or(true) {
+        return Err(Error::Unauthorized);
+    }
+    let identity = redeem(&mut PgSqlx::new(db.acquire().await?), provider.get_ref(), &state, &code).await?;
+    let uid = finish_login(PgSqlx::new(db.begin().await?), identity).await?;
+    let mut resp = if is_enabled(&mut PgSqlx::new(db.acquire().await?), uid).await? {
+        let challenge = create_challenge(PgSqlx::new(db.begin().await?), uid).await?;
+        HttpResponse::Ok().json(TwoFactorChallenge { challenge })
+    } else {
+        session_response(create_session(PgSqlx::new(db.begin().await?), uid, client(&req)).await?)?
+    };
+    resp.add_removal_cookie(&Cookie::build(OIDC_STATE, "").path("/").finish())
+        .map_err(|e| Error::HTTPError(format!("{e:?}")))?;
+    Ok(resp)
+}
@@ -9,7 +9,9 @@ use crate::serde::Serialize;
 use crate::sqlx::{FromRow, PgPool};
 use crate::{
     actix_web::web::{Data, Json, Path},
-    core::models::question::QuestionType,
+    core::models::{question::QuestionType, role::Permission, vote::VoteStatus},
+    core::services::permission::authorize_question,
+    database::sqlx::PgSqlx,
 };
 
 #[derive(Debug, Serialize, FromRow)]
@@ -68,10 +70,11 @@ pub struct OptAdd {
 
 pub async fn add_opts(user_info: UserInfo, qst_id: Path<(i32,)>, Json(options): Json<Vec<String>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
     let qst_id = qst_id.into_inner().0;
+    authorize_question(&mut PgSqlx::new(db.acquire().await?), qst_id, user_info.id, Permission::EditVote).await?;
     let mut tx = db.begin().await?;
-    let (org_id, vote_id): (i32, i32) = query_as(
+    let (org_id, vote_id, status, type_): (i32, i32, VoteStatus, String) = query_as(
         "
-        SELECT o.id, v.id
+        SELECT o.id, v.id, v.status, q.type_
         FROM users AS u
         JOIN organization_members AS uo ON u.id = uo.user_id
         JOIN organizations AS o ON uo.organization_id = o.id
@@ -84,6 +87,15 @@ pub async fn add_opts(user_info: UserInfo, qst_id: Path<(i32,)>, Json(options):
     .bind(qst_id)
     .fetch_one(&mut tx)
     .await?;
+    if !status.is_editable() {
+        tx.rollback().await?;
+        return Err(Error::BusinessError(format!("vote can not be modified(status: {})", status.as_str())));
+    }
+    let type_ = QuestionType::try_from(type_.as_str())?;
+    if !type_.is_option_based() {
+        tx.rollback().await?;
+        return Err(Error::BusinessError(format!("{} question can not have options", type_.as_str().to_lowercase())));
+    }
 
     QueryBuilder::new("INSERT INTO options (question_id, option)")
         .push_values(options.into_iter(), |mut b, o| {
@@ -127,7 +139,7 @@ pub async fn add_opts(user_info: UserInfo, qst_id: Path<(i32,)>, Json(options):
     Ok(HttpResponse::Ok().finish())
 }
 
-pub async fn delete(option_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+pub async fn delete(user_info: UserInfo, option_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
     let option_id = option_id.into_inner().0;
     let mut tx = db.begin().await?;
     let has_related_answer: bool = query_scalar("SELECT EXISTS(SELECT id FROM answers WHERE option_id = $1)").bind(option_id).fetch_one(&mut tx).await?;
@@ -135,8 +147,8 @@ pub async fn delete(option_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpRes
         tx.rollback().await?;
         return Err(Error::BusinessError("there is some answer related to this option".into()));
     }
-    let (oid, vid, qid): (i32, i32, i32) = query_as(
-        "SELECT o.id, v.id, q.id
+    let (oid, vid, qid, status): (i32, i32, i32, VoteStatus) = query_as(
+        "SELECT o.id, v.id, q.id, v.status
         FROM organizations AS o
         JOIN votes AS v ON o.id = v.organization_id
         JOIN questions AS q ON v.id = q.vote_id
@@ -147,6 +159,11 @@ pub async fn delete(option_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpRes
     .bind(option_id)
     .fetch_one(&mut tx)
     .await?;
+    authorize_question(&mut PgSqlx::new(db.acquire().await?), qid, user_info.id, Permission::EditVote).await?;
+    if !status.is_editable() {
+        tx.rollback().await?;
+        return Err(Error::BusinessError(format!("vote can not be modified(status: {})", status.as_str())));
+    }
 
     query("DELETE FROM options WHERE id = $1").bind(option_id).execute(&mut tx).await?;
     query("UPDATE organizations SET version = version + 1 WHERE id = $1").bind(oid).execute(&mut tx).await?;
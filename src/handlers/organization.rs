@@ -1,11 +1,12 @@
 use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
-use sqlx::{query, query_as, query_scalar, PgPool, QueryBuilder};
+use sqlx::{query_as, query_scalar, PgPool};
 
 use crate::actix_web::web::{Data, Json, Path, Query};
 use crate::context::UserInfo;
 use crate::core::models::{
-    organization::{Organization, OrganizationWithVoteInfo},
+    organization::{AnswerRetention, Organization, OrganizationWithVoteInfo},
+    role::Role,
     vote::{Vote, VoteQuery},
 };
 use crate::core::services::{organization::delete_organization as delete_organization_core, vote::query_votes};
@@ -16,13 +17,16 @@ use crate::request::Pagination;
 use crate::response::CreateResponse;
 use crate::serde::{Deserialize, Serialize};
 
-use crate::core::services::organization::{create_organization, get_organization, joined_organizations, update_organization, Create, Update};
+use crate::core::services::organization::{
+    add_users as add_users_, appoint_manager, create_organization, demote_manager as demote_manager_, get_organization, joined_organizations, leave_organization, remove_member as remove_member_,
+    set_member_role, transfer_ownership as transfer_ownership_, update_organization, Create, Update,
+};
 use crate::handlers::authorizer::Authorizer;
 use crate::response::List;
 
 pub async fn delete_organization(user_info: UserInfo, organization_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
-    let mut pg = PgSqlx::new(db.acquire().await?);
-    delete_organization_core(&mut pg, user_info.id, organization_id.0).await?;
+    let tx = PgSqlx::new(db.begin().await?);
+    delete_organization_core(tx, user_info.id, organization_id.0).await?;
     Ok(HttpResponse::new(StatusCode::OK))
 }
 
@@ -60,61 +64,7 @@ pub async fn update(user_info: UserInfo, org_id: Path<(i32,)>, Json(req): Json<U
 }
 
 pub async fn add_users(user_info: UserInfo, org_id: Path<(i32,)>, Json(user_ids): Json<Vec<i32>>, db: Data<PgPool>) -> Result<Json<()>, Error> {
-    let org_id = org_id.into_inner().0;
-    let mut tx = db.begin().await?;
-    query(
-        "
-        SELECT * 
-        FROM organization_members AS uo
-        WHERE user_id = $1
-        AND organization_id = $2
-        FOR UPDATE",
-    )
-    .bind(user_info.id)
-    .bind(org_id)
-    .execute(&mut tx)
-    .await?;
-    QueryBuilder::new(
-        "
-        INSERT INTO organization_members (user_id, organization_id)",
-    )
-    .push_values(user_ids.iter(), |mut b, u| {
-        b.push_bind(u);
-        b.push_bind(org_id);
-    })
-    .build()
-    .execute(&mut tx)
-    .await?;
-    for &uid in &user_ids {
-        query(
-            "
-            INSERT INTO vote_read_marks (vote_id, user_id, version)
-            SELECT (v.id, $1, 0)
-            FROM organizations AS o
-            JOIN votes AS v ON o.id = v.organization_id
-            WHERE o.id = $2",
-        )
-        .bind(uid)
-        .bind(org_id)
-        .execute(&mut tx)
-        .await?;
-    }
-
-    for &uid in &user_ids {
-        query(
-            "
-            INSERT INTO question_read_marks (question_id, user_id, version)
-            SELECT (q.id, $1, 0)
-            FROM organizations as o
-            JOIN votes AS v ON o.id = v.organization_id
-            JOIN questions AS q ON v.id = q.vote_id
-            WHERE o.id = $2",
-        )
-        .bind(uid)
-        .bind(org_id)
-        .execute(&mut tx)
-        .await?;
-    }
+    add_users_(PgSqlx::new(db.begin().await?), user_info.id, org_id.into_inner().0, user_ids).await?;
     Ok(Json(()))
 }
 
@@ -124,21 +74,53 @@ pub struct AddManager {
     organization_id: i32,
 }
 
-pub async fn add_manager(Json(AddManager { user_id, organization_id }): Json<AddManager>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
-    let mut tx = db.begin().await?;
-    if query_scalar("SELECT EXISTS(SELECT * FROM organization_managers WHERE user_id = $1 AND organization_id = $2)")
-        .bind(user_id)
-        .bind(organization_id)
-        .fetch_one(&mut tx)
-        .await?
-    {
-        return Ok(HttpResponse::Ok().finish());
-    }
-    query("INSERT INTO organization_managers (user_id, organization_id) VALUES ($1, $2)")
-        .bind(user_id)
-        .bind(organization_id)
-        .execute(&mut tx)
-        .await?;
+pub async fn add_manager(user_info: UserInfo, Json(AddManager { user_id, organization_id }): Json<AddManager>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    appoint_manager(PgSqlx::new(db.begin().await?), user_info.id, organization_id, user_id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn demote_manager(user_info: UserInfo, path: Path<(i32, i32)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, manager_id) = path.into_inner();
+    demote_manager_(PgSqlx::new(db.begin().await?), user_info.id, org_id, manager_id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleUpdation {
+    role: Role,
+}
+
+pub async fn update_role(user_info: UserInfo, path: Path<(i32, i32)>, Json(RoleUpdation { role }): Json<RoleUpdation>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, member_id) = path.into_inner();
+    set_member_role(PgSqlx::new(db.begin().await?), user_info.id, org_id, member_id, role).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Departure {
+    // `Keep` by default
+    #[serde(default)]
+    answers: AnswerRetention,
+}
+
+pub async fn remove_member(user_info: UserInfo, path: Path<(i32, i32)>, Query(Departure { answers }): Query<Departure>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (org_id, member_id) = path.into_inner();
+    remove_member_(PgSqlx::new(db.begin().await?), user_info.id, org_id, member_id, answers).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn leave(user_info: UserInfo, org_id: Path<(i32,)>, Query(Departure { answers }): Query<Departure>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    leave_organization(PgSqlx::new(db.begin().await?), user_info.id, org_id.0, answers).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferOwnership {
+    user_id: i32,
+}
+
+pub async fn transfer_ownership(user_info: UserInfo, org_id: Path<(i32,)>, Json(TransferOwnership { user_id }): Json<TransferOwnership>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    transfer_ownership_(PgSqlx::new(db.begin().await?), user_info.id, org_id.0, user_id).await?;
     Ok(HttpResponse::Ok().finish())
 }
 
@@ -0,0 +1,38 @@
+use actix_web::HttpResponse;
+
+use crate::actix_web::web::{Data, Json, Path};
+use crate::context::UserInfo;
+use crate::core::models::personal_token::{PersonalToken, PersonalTokenCreate};
+use crate::core::services::personal_token::{create_token, revoke_token, tokens};
+use crate::database::sqlx::PgSqlx;
+use crate::error::Error;
+use crate::handlers::user::current_session;
+use crate::serde::Serialize;
+use crate::sqlx::PgPool;
+
+#[derive(Debug, Serialize)]
+pub struct PersonalTokenCreated {
+    #[serde(flatten)]
+    personal_token: PersonalToken,
+    // only returned once, sent as `Authorization: Bearer {token}`
+    token: String,
+}
+
+pub async fn create(user_info: UserInfo, Json(body): Json<PersonalTokenCreate>, db: Data<PgPool>) -> Result<Json<PersonalTokenCreated>, Error> {
+    current_session(&user_info)?;
+    let (personal_token, token) = create_token(PgSqlx::new(db.begin().await?), user_info.id, body).await?;
+    Ok(Json(PersonalTokenCreated { personal_token, token }))
+}
+
+pub async fn list(user_info: UserInfo, db: Data<PgPool>) -> Result<Json<Vec<PersonalToken>>, Error> {
+    current_session(&user_info)?;
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let tokens = tokens(&mut store, user_info.id).await?;
+    Ok(Json(tokens))
+}
+
+pub async fn revoke(user_info: UserInfo, id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    current_session(&user_info)?;
+    revoke_token(PgSqlx::new(db.begin().await?), user_info.id, id.0).await?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -6,19 +6,21 @@ use crate::core::models::question::Create as QuestionCreate;
 use crate::database::sqlx::PgSqlx;
 use crate::error::Error;
 use crate::{
-    actix_web::web::{Data, Json, Path},
+    actix_web::web::{Data, Json, Path, Query},
     response::CreateResponse,
 };
 
-use crate::core::models::{option::Opt, question::Question};
+use crate::core::models::{answer::TextAnswer, option::Opt, question::Question};
 use crate::core::ports::repository::TxStore;
 use crate::core::{
-    models::question::QuestionType,
+    models::question::{QuestionType, TallyMethod},
     services::{
+        answer::text_answers,
         option::options_of_question,
-        question::{create_question, delete_question, question_detail},
+        question::{create_question, delete_question, question_detail, set_tally_method},
     },
 };
+use crate::request::{Pagination, ReportQuery};
 use crate::response::List;
 use crate::serde::{Deserialize, Serialize};
 use crate::sqlx::{query_as, query_scalar, FromRow, PgPool};
@@ -69,6 +71,18 @@ pub async fn delete(user_info: UserInfo, qst_id: Path<(i32,)>, db: Data<PgPool>)
     Ok(HttpResponse::new(StatusCode::OK))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TallyMethodUpdate {
+    method: TallyMethod,
+}
+
+pub async fn update_tally_method(user_info: UserInfo, qst_id: Path<(i32,)>, Json(TallyMethodUpdate { method }): Json<TallyMethodUpdate>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let mut storer = PgSqlx::new(db.begin().await?);
+    set_tally_method(&mut storer, user_info.id, qst_id.0, method).await?;
+    storer.commit().await?;
+    Ok(HttpResponse::new(StatusCode::OK))
+}
+
 #[derive(Debug, FromRow)]
 struct QuestionWithOptions {
     question_id: i32,
@@ -176,3 +190,15 @@ pub async fn options(question_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json
     let (opts, total) = options_of_question(&mut storer, question_id.0).await?;
     Ok(Json(List::new(opts, total)))
 }
+
+pub async fn texts(
+    user_info: UserInfo,
+    question_id: Path<(i32,)>,
+    Query(Pagination { page, size }): Query<Pagination>,
+    Query(ReportQuery { source }): Query<ReportQuery>,
+    db: Data<PgPool>,
+) -> Result<Json<List<TextAnswer>>, Error> {
+    let mut storer = PgSqlx::new(db.acquire().await?);
+    let (texts, total) = text_answers(&mut storer, user_info.id, question_id.0, source, page, size).await?;
+    Ok(Json(List::new(texts, total)))
+}
@@ -0,0 +1,83 @@
+use actix_web::cookie::Cookie;
+use actix_web::HttpResponse;
+
+use crate::actix_web::web::{Data, Json, Path};
+use crate::context::{GuestInfo, UserInfo};
+use crate::core::models::answer::Submit;
+use crate::core::models::question::Question;
+use crate::core::models::share_link::{ShareLink, ShareLinkCreate};
+use crate::core::models::vote::Vote;
+use crate::core::ports::tokener::Tokener;
+use crate::core::services::share_link::{create_share_link, guest_vote, join as join_, revoke_share_link, share_links, submit_guest_answers};
+use crate::database::sqlx::PgSqlx;
+use crate::dotenv;
+use crate::error::Error;
+use crate::impls::tokener::jwt::JWT;
+use crate::middlewares::jwt::{Claim, GUEST_TOKEN, JWT_SECRET};
+use crate::serde::Serialize;
+use crate::sqlx::PgPool;
+
+#[derive(Debug, Serialize)]
+pub struct ShareLinkCreated {
+    id: i32,
+    // only returned once, the link is `share/{token}`
+    token: String,
+}
+
+pub async fn create(user_info: UserInfo, vote_id: Path<(i32,)>, Json(body): Json<ShareLinkCreate>, db: Data<PgPool>) -> Result<Json<ShareLinkCreated>, Error> {
+    let (id, token) = create_share_link(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0, body).await?;
+    Ok(Json(ShareLinkCreated { id, token }))
+}
+
+pub async fn list(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json<Vec<ShareLink>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let links = share_links(&mut store, user_info.id, vote_id.0).await?;
+    Ok(Json(links))
+}
+
+pub async fn revoke(user_info: UserInfo, path: Path<(i32, i32)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let (vote_id, id) = path.into_inner();
+    revoke_share_link(PgSqlx::new(db.begin().await?), user_info.id, vote_id, id).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Serialize)]
+struct Joined {
+    vote_id: i32,
+}
+
+// no account is needed, the guest gets a token scoped to the shared vote
+pub async fn join(token: Path<(String,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    let joined = join_(PgSqlx::new(db.begin().await?), &token.0).await?;
+    let claim = Claim {
+        user: format!("guest:{}", joined.guest_id),
+        exp: joined.expires_at.timestamp(),
+        guest: Some(GuestInfo {
+            id: joined.guest_id,
+            vote_id: joined.vote_id,
+            share_link_id: joined.share_link_id,
+        }),
+        sid: None,
+    };
+    let secret = dotenv::var(JWT_SECRET)?;
+    let tokener = JWT::new(secret.as_bytes().to_owned());
+    let token = tokener.gen_token(&claim)?;
+    Ok(HttpResponse::Ok().cookie(Cookie::new(GUEST_TOKEN, token)).json(Joined { vote_id: joined.vote_id }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuestVote {
+    vote: Vote,
+    questions: Vec<Question>,
+}
+
+pub async fn vote(guest: GuestInfo, db: Data<PgPool>) -> Result<Json<GuestVote>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let (vote, questions) = guest_vote(&mut store, guest.id, guest.share_link_id, guest.vote_id).await?;
+    Ok(Json(GuestVote { vote, questions }))
+}
+
+pub async fn submit_answers(guest: GuestInfo, Json(answers): Json<Vec<Submit>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    submit_guest_answers(PgSqlx::new(db.begin().await?), guest.id, guest.share_link_id, guest.vote_id, answers).await?;
+    Ok(HttpResponse::Ok().finish())
+}
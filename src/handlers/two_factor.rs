@@ -0,0 +1,53 @@
+use actix_web::HttpResponse;
+
+use crate::actix_web::web::{Data, Json, Path};
+use crate::context::UserInfo;
+use crate::core::models::two_factor::Enrollment;
+use crate::core::services::two_factor::{
+    confirm as confirm_, disable as disable_, enroll as enroll_, regenerate_recovery_codes as regenerate_recovery_codes_, require_for_managers as require_for_managers_,
+};
+use crate::database::sqlx::PgSqlx;
+use crate::error::Error;
+use crate::handlers::user::current_session;
+use crate::serde::Deserialize;
+use crate::sqlx::PgPool;
+
+#[derive(Debug, Deserialize)]
+pub struct Code {
+    code: String,
+}
+
+pub async fn enroll(user_info: UserInfo, db: Data<PgPool>) -> Result<Json<Enrollment>, Error> {
+    current_session(&user_info)?;
+    let enrollment = enroll_(PgSqlx::new(db.begin().await?), user_info.id).await?;
+    Ok(Json(enrollment))
+}
+
+// returns the recovery codes, they are shown only once
+pub async fn confirm(user_info: UserInfo, Json(Code { code }): Json<Code>, db: Data<PgPool>) -> Result<Json<Vec<String>>, Error> {
+    current_session(&user_info)?;
+    let codes = confirm_(PgSqlx::new(db.begin().await?), user_info.id, &code).await?;
+    Ok(Json(codes))
+}
+
+pub async fn disable(user_info: UserInfo, Json(Code { code }): Json<Code>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    current_session(&user_info)?;
+    disable_(PgSqlx::new(db.begin().await?), user_info.id, &code).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn regenerate_recovery_codes(user_info: UserInfo, Json(Code { code }): Json<Code>, db: Data<PgPool>) -> Result<Json<Vec<String>>, Error> {
+    current_session(&user_info)?;
+    let codes = regenerate_recovery_codes_(PgSqlx::new(db.begin().await?), user_info.id, &code).await?;
+    Ok(Json(codes))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Requirement {
+    required: bool,
+}
+
+pub async fn require_for_managers(user_info: UserInfo, org_id: Path<(i32,)>, Json(Requirement { required }): Json<Requirement>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    require_for_managers_(PgSqlx::new(db.begin().await?), user_info.id, org_id.0, required).await?;
+    Ok(HttpResponse::Ok().finish())
+}
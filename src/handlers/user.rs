@@ -1,16 +1,22 @@
-use actix_web::web::Data;
+use actix_web::web::{Data, Path};
 use sqlx::{FromRow, PgPool, QueryBuilder};
 
 use crate::actix_web::{
     web::{Json, Query},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 use crate::context::UserInfo;
+use crate::core::models::session::SessionInfo;
 use crate::core::models::user::Profile;
 use crate::core::models::user::ProfileUpdate;
+use crate::core::models::verification::Policy;
+use crate::core::services::password::change_password as change_password_;
+use crate::core::services::session::{create_session, revoke_other_sessions as revoke_other_sessions_, revoke_session as revoke_session_, sessions as sessions_};
 use crate::core::services::user::{profile as profile_, search_by_phone, update_profile as update_profile_};
 use crate::database::sqlx::PgSqlx;
 use crate::error::Error;
+use crate::handlers::{client, session_response};
+use crate::impls::hasher::argon2::Argon2id;
 use crate::response::List;
 use crate::serde::{Deserialize, Serialize};
 
@@ -26,9 +32,9 @@ pub struct FindUserParams {
     exclude_org_id: Option<i32>,
 }
 
-pub async fn find(Query(FindUserParams { phone, exclude_org_id }): Query<FindUserParams>, db: Data<PgPool>) -> Result<Json<Option<User>>, Error> {
+pub async fn find(Query(FindUserParams { phone, exclude_org_id }): Query<FindUserParams>, policy: Data<Policy>, db: Data<PgPool>) -> Result<Json<Option<User>>, Error> {
     let store = PgSqlx::new(db.acquire().await?);
-    let user = search_by_phone(store, phone, exclude_org_id).await?.map(|u| User { id: u.id, nickname: u.nickname });
+    let user = search_by_phone(store, phone, exclude_org_id, policy.search).await?.map(|u| User { id: u.id, nickname: u.nickname });
     Ok(Json(user))
 }
 
@@ -122,3 +128,38 @@ pub async fn update_profile(me: UserInfo, Json(p): Json<Profile>, pool: Data<PgP
     .await?;
     Ok(HttpResponse::Ok().finish())
 }
+
+// the credentials can only be managed by signing in, not with a personal access token
+pub(crate) fn current_session(me: &UserInfo) -> Result<i32, Error> {
+    me.session_id.ok_or(Error::Forbidden("not allowed with a personal access token".into()))
+}
+
+pub async fn sessions(me: UserInfo, db: Data<PgPool>) -> Result<Json<Vec<SessionInfo>>, Error> {
+    let sessions = sessions_(PgSqlx::new(db.acquire().await?), me.id, current_session(&me)?).await?;
+    Ok(Json(sessions))
+}
+
+pub async fn revoke_session(me: UserInfo, session_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json<()>, Error> {
+    current_session(&me)?;
+    revoke_session_(PgSqlx::new(db.begin().await?), me.id, session_id.into_inner().0).await?;
+    Ok(Json(()))
+}
+
+pub async fn revoke_other_sessions(me: UserInfo, db: Data<PgPool>) -> Result<Json<i32>, Error> {
+    let revoked = revoke_other_sessions_(PgSqlx::new(db.begin().await?), me.id, current_session(&me)?).await?;
+    Ok(Json(revoked))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordChange {
+    current_password: String,
+    new_password: String,
+}
+
+// every session is revoked by the new password, a new one is started for the request
+pub async fn change_password(req: HttpRequest, me: UserInfo, Json(PasswordChange { current_password, new_password }): Json<PasswordChange>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    current_session(&me)?;
+    change_password_(PgSqlx::new(db.begin().await?), &Argon2id::default(), me.id, &current_password, &new_password).await?;
+    let refreshed = create_session(PgSqlx::new(db.begin().await?), me.id, client(&req)).await?;
+    session_response(refreshed)
+}
@@ -1,18 +1,25 @@
-use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
 use sqlx::{query, query_as, FromRow};
 
-use crate::actix_web::web::{Data, Json, Path};
+use crate::actix_web::web::{Data, Json, Path, Query};
 
 use crate::chrono::NaiveDate;
 use crate::context::UserInfo;
-use crate::core::models::answer::Submit;
-use crate::core::models::vote::VoteCreate;
+use crate::core::models::answer::{Source, Submit};
+use crate::core::models::vote::{VoteCreate, VoteStatus, VoteVisibility, WhiteListed};
 use crate::core::models::{date::Date, question::Question, vote::Vote};
 use crate::core::ports::repository::TxStore;
+use crate::core::services::answer::{value_report, ValueReport};
 use crate::core::services::question::{question_detail, questions_with_in_vote};
-use crate::core::services::vote::{create_vote, submit_answers as _submit_answers, vote_detail};
+use crate::core::services::vote::{
+    add_to_whitelist as add_to_whitelist_, archive_vote, close_vote, create_vote, delete_vote as delete_vote_, publish_vote, ranked_reports as ranked_reports_,
+    remove_from_whitelist as remove_from_whitelist_, reopen_vote, set_grace_period, set_visibility, submit_answers as _submit_answers, update_vote, vote_detail, whitelist as whitelist_, RankedReport,
+};
 use crate::database::sqlx::PgSqlx;
 use crate::error::Error;
+use crate::handlers::answer::{ballot_token, submitted};
+use crate::request::{Pagination, ReportQuery};
 use crate::response::{CreateResponse, DeleteResponse, List, UpdateResponse};
 use crate::serde::{Deserialize, Serialize};
 use crate::sqlx::PgPool;
@@ -30,29 +37,65 @@ pub struct VoteUpdation {
 }
 
 pub async fn update(user_info: UserInfo, vote_id: Path<(i32,)>, Json(VoteUpdation { name, deadline }): Json<VoteUpdation>, db: Data<PgPool>) -> Result<Json<UpdateResponse>, Error> {
-    let vote_id = vote_id.into_inner().0;
-    let mut conn = db.acquire().await?;
-    let (updated,): (i64,) = query_as(
-        "WITH updated AS (UPDATE votes
-    SET name = $1, deadline = $2, version = version + 1
-    WHERE id = $3
-    AND id IN (
-        SELECT v.id
-        FROM users AS u
-        JOIN organization_members AS uo ON u.id = uo.user_id
-        JOIN organizations AS o ON uo.organization_id = o.id
-        JOIN votes AS v ON o.id = v.organization_id
-        WHERE u.id = $4)
-    RETURNING *)
-    SELECT COUNT(*) FROM updated",
-    )
-    .bind(name)
-    .bind(deadline)
-    .bind(vote_id)
-    .bind(user_info.id)
-    .fetch_one(&mut conn)
-    .await?;
-    Ok(Json(UpdateResponse { updated: updated as usize }))
+    let updated = update_vote(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0, name, deadline).await?;
+    Ok(Json(UpdateResponse::new(updated as usize)))
+}
+
+pub async fn publish(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    publish_vote(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn close(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    close_vote(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn reopen(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    reopen_vote(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn archive(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    archive_vote(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GracePeriod {
+    days: i32,
+}
+
+pub async fn update_grace_period(user_info: UserInfo, vote_id: Path<(i32,)>, Json(GracePeriod { days }): Json<GracePeriod>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    set_grace_period(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0, days).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VisibilityUpdation {
+    visibility: VoteVisibility,
+}
+
+pub async fn update_visibility(user_info: UserInfo, vote_id: Path<(i32,)>, Json(VisibilityUpdation { visibility }): Json<VisibilityUpdation>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    set_visibility(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0, visibility).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn whitelist(user_info: UserInfo, vote_id: Path<(i32,)>, Query(Pagination { page, size }): Query<Pagination>, db: Data<PgPool>) -> Result<Json<List<WhiteListed>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let (list, total) = whitelist_(&mut store, user_info.id, vote_id.0, page, size).await?;
+    Ok(Json(List::new(list, total)))
+}
+
+pub async fn add_to_whitelist(user_info: UserInfo, vote_id: Path<(i32,)>, Json(user_ids): Json<Vec<i32>>, db: Data<PgPool>) -> Result<HttpResponse, Error> {
+    add_to_whitelist_(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0, user_ids).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn remove_from_whitelist(user_info: UserInfo, path: Path<(i32, i32)>, db: Data<PgPool>) -> Result<Json<DeleteResponse>, Error> {
+    let (vote_id, user_id) = path.into_inner();
+    let deleted = remove_from_whitelist_(PgSqlx::new(db.begin().await?), user_info.id, vote_id, user_id).await?;
+    Ok(Json(DeleteResponse::new(deleted)))
 }
 
 #[derive(Debug, Serialize, FromRow)]
@@ -61,7 +104,7 @@ pub struct Item {
     name: String,
     deadline: Option<NaiveDate>,
     version: i64,
-    status: String,
+    status: VoteStatus,
     has_updated: bool,
 }
 
@@ -85,82 +128,88 @@ pub async fn detail(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>
 pub struct OptionReport {
     option: String,
     percentage: i32,
+    late_count: i64,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct QuestionReport {
     question: String,
+    type_: String,
     options: Vec<OptionReport>,
+    // statistics of the value, rating and matrix questions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    values: Option<ValueReport>,
 }
 
-async fn gen_question_report(user_info: UserInfo, question_id: i32, db: &Data<PgPool>) -> Result<QuestionReport, Error> {
-    let mut conn = db.acquire().await?;
+async fn gen_question_report(user_info: UserInfo, question_id: i32, source: Source, db: &Data<PgPool>) -> Result<QuestionReport, Error> {
     let mut store = PgSqlx::new(db.acquire().await?);
     let question = question_detail(&mut store, user_info.id, question_id).await?;
-    let opts = query_as(
-        r#"
-    select o.option as option, (count(distinct a.id)::float / (count(distinct uo.user_id))::float * 10000)::int as percentage
-    from organization_members as uo
-    join organizations as org on uo.organization_id = org.id
-    join votes as v on org.id = v.organization_id
-    join questions as q on v.id = q.vote_id
+    let opts = match source {
+        // the percentage of the users is taken against the audience of the vote, the members of its groups if it has any
+        Source::Users => {
+            query_as(
+                r#"
+    select o.option as option, coalesce((count(a.id)::float / nullif((select count(distinct au.user_id) from vote_audiences as au where au.vote_id = q.vote_id), 0)::float * 10000)::int, 0) as percentage, count(a.id) filter (where a.late) as late_count
+    from questions as q
+    join options as o on q.id = o.question_id
+    left join answers as a on o.id = a.option_id and a.guest_id is null
+    where q.id = $1
+    group by option, q.vote_id"#,
+            )
+            .bind(question_id)
+            .fetch_all(store.executor())
+            .await?
+        }
+        // the percentage of the guests is taken against the guests joined through the links of the vote
+        Source::Guests => {
+            query_as(
+                r#"
+    select o.option as option, coalesce((count(distinct a.id)::float / nullif(count(distinct g.id), 0)::float * 10000)::int, 0) as percentage, count(distinct a.id) filter (where a.late) as late_count
+    from questions as q
     join options as o on q.id = o.question_id
-    left join answers as a on o.id = a.option_id
+    left join share_links as sl on q.vote_id = sl.vote_id
+    left join guests as g on sl.id = g.share_link_id
+    left join answers as a on o.id = a.option_id and a.guest_id is not null
     where q.id = $1
     group by option"#,
-    )
-    .bind(question_id)
-    .fetch_all(&mut conn)
-    .await?;
+            )
+            .bind(question_id)
+            .fetch_all(store.executor())
+            .await?
+        }
+    };
+    let values = value_report(&mut store, question_id, source).await?;
     Ok(QuestionReport {
         question: question.description,
+        type_: question.type_,
         options: opts,
+        values,
     })
 }
 
-pub async fn question_reports(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json<Vec<QuestionReport>>, Error> {
+pub async fn question_reports(user_info: UserInfo, vote_id: Path<(i32,)>, Query(ReportQuery { source }): Query<ReportQuery>, db: Data<PgPool>) -> Result<Json<Vec<QuestionReport>>, Error> {
     let vote_id = vote_id.into_inner().0;
-    let qids: Vec<(i32,)> = query_as(
-        "
-    SELECT q.id
-    FROM users AS u
-    JOIN organization_members AS uo ON u.id = uo.user_id
-    JOIN organizations AS o ON uo.organization_id = o.id
-    JOIN votes AS v ON o.id = v.organization_id
-    JOIN questions AS q ON v.id = q.vote_id
-    WHERE u.id = $1
-    AND v.id = $2",
-    )
-    .bind(user_info.id)
-    .bind(vote_id)
-    .fetch_all(&mut db.acquire().await?)
-    .await?;
+    // the access to the vote is checked by the authorizer of the scope
+    let qids: Vec<(i32,)> = query_as("SELECT id FROM questions WHERE vote_id = $1 ORDER BY id")
+        .bind(vote_id)
+        .fetch_all(&mut db.acquire().await?)
+        .await?;
     let mut reports = Vec::new();
     for (id,) in qids {
-        reports.push(gen_question_report(user_info.clone(), id, &db).await?)
+        reports.push(gen_question_report(user_info.clone(), id, source, &db).await?)
     }
 
     Ok(Json(reports))
 }
 
+pub async fn ranked_reports(user_info: UserInfo, vote_id: Path<(i32,)>, Query(ReportQuery { source }): Query<ReportQuery>, db: Data<PgPool>) -> Result<Json<Vec<RankedReport>>, Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    let reports = ranked_reports_(&mut store, user_info.id, vote_id.0, source).await?;
+    Ok(Json(reports))
+}
+
 pub async fn delete_vote(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPool>) -> Result<Json<DeleteResponse>, Error> {
-    let vote_id = vote_id.into_inner().0;
-    let (deleted,): (i32,) = query_as(
-        "
-    DELETE 
-    FROM votes 
-    WHERE id IN (
-        SELECT v.id 
-        FROM users AS u 
-        JOIN organization_members AS uo ON u.id = uo.user_id 
-        JOIN organizations AS o ON uo.organization_id = o.id 
-        JOIN votes AS v ON o.id = v.organization_id 
-        WHERE u.id = $1 AND v.id = $2)",
-    )
-    .bind(user_info.id)
-    .bind(vote_id)
-    .fetch_one(&mut db.acquire().await?)
-    .await?;
+    let deleted = delete_vote_(PgSqlx::new(db.begin().await?), user_info.id, vote_id.0).await?;
     Ok(Json(DeleteResponse::new(deleted)))
 }
 
@@ -176,8 +225,9 @@ pub async fn questions(user_info: UserInfo, vote_id: Path<(i32,)>, db: Data<PgPo
     Ok(Json(List::new(list, total)))
 }
 
-pub async fn submit_answers(user_info: UserInfo, db: Data<PgPool>, vote_id: Path<(i32,)>, Json(req): Json<Vec<Submit>>) -> Result<HttpResponse, Error> {
+pub async fn submit_answers(req: HttpRequest, user_info: UserInfo, db: Data<PgPool>, vote_id: Path<(i32,)>, Json(answers): Json<Vec<Submit>>) -> Result<HttpResponse, Error> {
     let mut store = PgSqlx::new(db.begin().await?);
-    _submit_answers(&mut store, user_info.id, vote_id.0, req).await?;
-    Ok(HttpResponse::Ok().finish())
+    let issued = _submit_answers(&mut store, user_info.id, vote_id.0, answers, ballot_token(&req)).await?;
+    store.commit().await?;
+    Ok(submitted(StatusCode::OK, issued))
 }
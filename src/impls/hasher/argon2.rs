@@ -0,0 +1,105 @@
+use crate::core::ports::hasher::PasswordHasher;
+use crate::error::Error;
+use actix_web::web;
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use hex::ToHex;
+use sha2::{Digest, Sha256};
+
+#[derive(Default)]
+pub struct Argon2id {
+    params: Params,
+}
+
+impl Argon2id {
+    pub fn new(params: Params) -> Self {
+        Self { params }
+    }
+
+    fn argon2(&self) -> Argon2<'_> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    fn hash_blocking(&self, password: &str) -> Result<String, argon2::password_hash::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(self.argon2().hash_password(password.as_bytes(), &salt)?.to_string())
+    }
+
+    fn verify_blocking(&self, password: &str, hash: &str, salt: Option<&str>) -> bool {
+        match PasswordHash::new(hash) {
+            Ok(parsed) => self.argon2().verify_password(password.as_bytes(), &parsed).is_ok(),
+            // not a PHC string, so it is a legacy hash
+            Err(_) => salt.map(|salt| legacy_hash(password, salt) == hash).unwrap_or(false),
+        }
+    }
+}
+
+// the hex encoded SHA-256 of the password followed by the salt
+fn legacy_hash(password: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password);
+    hasher.update(salt);
+    hasher.finalize().encode_hex()
+}
+
+// the hashing runs on the blocking thread pool
+impl PasswordHasher for Argon2id {
+    async fn hash(&self, password: &str) -> Result<String, Error> {
+        let (hasher, password) = (Self::new(self.params.clone()), password.to_owned());
+        web::block(move || hasher.hash_blocking(&password)).await?.map_err(|e| Error::ServerError(e.to_string()))
+    }
+
+    async fn verify(&self, password: &str, hash: &str, salt: Option<&str>) -> Result<bool, Error> {
+        let hasher = Self::new(self.params.clone());
+        let (password, hash, salt) = (password.to_owned(), hash.to_owned(), salt.map(str::to_owned));
+        Ok(web::block(move || hasher.verify_blocking(&password, &hash, salt.as_deref())).await?)
+    }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        match PasswordHash::new(hash) {
+            Ok(parsed) => {
+                parsed.algorithm != Algorithm::Argon2id.ident()
+                    || Params::try_from(&parsed)
+                        .map(|params| params.m_cost() != self.params.m_cost() || params.t_cost() != self.params.t_cost() || params.p_cost() != self.params.p_cost())
+                        .unwrap_or(true)
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hasher() -> Argon2id {
+        // cheap parameters to keep the tests fast
+        Argon2id::new(Params::new(1024, 1, 1, None).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn test_hash_and_verify() {
+        let hasher = hasher();
+        let hash = hasher.hash("secret").await.unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(hasher.verify("secret", &hash, None).await.unwrap());
+        assert!(!hasher.verify("wrong", &hash, None).await.unwrap());
+        assert!(!hasher.needs_rehash(&hash));
+    }
+
+    #[actix_web::test]
+    async fn test_legacy_hash() {
+        let hasher = hasher();
+        let hash = legacy_hash("secret", "salt");
+        assert!(hasher.verify("secret", &hash, Some("salt")).await.unwrap());
+        assert!(!hasher.verify("secret", &hash, Some("pepper")).await.unwrap());
+        assert!(!hasher.verify("secret", &hash, None).await.unwrap());
+        assert!(hasher.needs_rehash(&hash));
+    }
+
+    #[actix_web::test]
+    async fn test_rehash_on_changed_params() {
+        let hash = hasher().hash("secret").await.unwrap();
+        assert!(Argon2id::default().needs_rehash(&hash));
+    }
+}
@@ -0,0 +1,326 @@
+use crate::core::ports::identity::{Identity, IdentityProvider};
+use crate::error::Error;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    // the `oidc/callback` route as seen by the browser
+    pub redirect_uri: String,
+    pub scopes: String,
+    // the only algorithms the ID tokens may be signed with, whatever their header tells
+    pub algorithms: Vec<Algorithm>,
+}
+
+impl OidcConfig {
+    // single sign-on is disabled when `OIDC_ISSUER` is not set
+    pub fn from_env() -> Result<Option<Self>, Error> {
+        let issuer = match dotenv::var("OIDC_ISSUER") {
+            Ok(issuer) => issuer,
+            Err(_) => return Ok(None),
+        };
+        Ok(Some(Self {
+            issuer,
+            client_id: dotenv::var("OIDC_CLIENT_ID")?,
+            client_secret: dotenv::var("OIDC_CLIENT_SECRET")?,
+            redirect_uri: dotenv::var("OIDC_REDIRECT_URI")?,
+            scopes: dotenv::var("OIDC_SCOPES").unwrap_or_else(|_| "openid email profile".into()),
+            algorithms: parse_algorithms(&dotenv::var("OIDC_ALGORITHMS").unwrap_or_else(|_| "RS256".into()))?,
+        }))
+    }
+}
+
+// a comma separated list such as `RS256,ES256`
+fn parse_algorithms(algorithms: &str) -> Result<Vec<Algorithm>, Error> {
+    let algorithms = algorithms.split(',').map(str::trim).filter(|a| !a.is_empty()).map(Algorithm::from_str).collect::<Result<Vec<_>, _>>()?;
+    if algorithms.is_empty() {
+        return Err(Error::ServerError("no algorithm is allowed for the ID tokens".into()));
+    }
+    Ok(algorithms)
+}
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+}
+
+pub struct OidcProvider {
+    config: OidcConfig,
+    authorization_endpoint: Url,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+    client: Client,
+}
+
+fn provider_error(e: impl ToString) -> Error {
+    Error::ServerError(e.to_string())
+}
+
+impl OidcProvider {
+    // the endpoints are read from the discovery document of the issuer
+    pub async fn discover(config: OidcConfig) -> Result<Self, Error> {
+        let client = Client::new();
+        let discovery: Discovery = client
+            .get(format!("{}/.well-known/openid-configuration", config.issuer.trim_end_matches('/')))
+            .send()
+            .await
+            .map_err(provider_error)?
+            .error_for_status()
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+        if discovery.issuer != config.issuer {
+            return Err(Error::ServerError(format!("issuer mismatch: {}", discovery.issuer)));
+        }
+        Ok(Self {
+            authorization_endpoint: Url::parse(&discovery.authorization_endpoint).map_err(provider_error)?,
+            token_endpoint: discovery.token_endpoint,
+            jwks_uri: discovery.jwks_uri,
+            config,
+            client,
+        })
+    }
+
+    // Symmetric ID tokens are signed with the client secret, the others with a key of the issuer. The keys are
+    // fetched on every sign-in so the rotated ones are picked up.
+    async fn decoding_key(&self, alg: Algorithm, kid: Option<String>) -> Result<DecodingKey, Error> {
+        if matches!(alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Ok(DecodingKey::from_secret(self.config.client_secret.as_bytes()));
+        }
+        let jwks_uri = self.jwks_uri.as_ref().ok_or(Error::ServerError("the issuer publishes no keys".into()))?;
+        let jwks: JwkSet = self
+            .client
+            .get(jwks_uri)
+            .send()
+            .await
+            .map_err(provider_error)?
+            .error_for_status()
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+        let jwk = match kid {
+            Some(kid) => jwks.find(&kid),
+            None => jwks.keys.first(),
+        }
+        .ok_or(Error::Unauthorized)?;
+        Ok(DecodingKey::from_jwk(jwk)?)
+    }
+
+    async fn verify_id_token(&self, token: &str, nonce: &str) -> Result<IdTokenClaims, Error> {
+        let header = decode_header(token).map_err(|_| Error::Unauthorized)?;
+        if !self.config.algorithms.contains(&header.alg) {
+            return Err(Error::Unauthorized);
+        }
+        let key = self.decoding_key(header.alg, header.kid).await?;
+        let mut validation = Validation::new(header.alg);
+        validation.algorithms = self.config.algorithms.clone();
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        let claims: IdTokenClaims = decode(token, &key, &validation).map_err(|_| Error::Unauthorized)?.claims;
+        // a token replayed from another sign-in does not carry the nonce of this one
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(Error::Unauthorized);
+        }
+        Ok(claims)
+    }
+}
+
+impl IdentityProvider for OidcProvider {
+    fn authorization_url(&self, state: &str, nonce: &str, code_challenge: &str) -> String {
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_uri)
+            .append_pair("scope", &self.config.scopes)
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        url.into()
+    }
+
+    async fn exchange(&self, code: &str, code_verifier: &str, nonce: &str) -> Result<Identity, Error> {
+        let resp = self
+            .client
+            .post(&self.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.config.redirect_uri),
+                ("client_id", &self.config.client_id),
+                ("client_secret", &self.config.client_secret),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .map_err(provider_error)?;
+        // the code is rejected by the issuer when it is wrong, used or not issued for the verifier
+        if !resp.status().is_success() {
+            return Err(Error::Unauthorized);
+        }
+        let tokens: TokenResponse = resp.json().await.map_err(provider_error)?;
+        let claims = self.verify_id_token(&tokens.id_token, nonce).await?;
+        Ok(Identity {
+            issuer: claims.iss,
+            subject: claims.sub,
+            email: claims.email,
+            email_verified: claims.email_verified,
+            name: claims.name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::web::{get, post, Form};
+    use actix_web::{App, HttpResponse, HttpServer};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    const CLIENT_ID: &str = "juju";
+    const CLIENT_SECRET: &str = "secret";
+    const CODE: &str = "code";
+    const VERIFIER: &str = "verifier";
+
+    // a local issuer which hands out an ID token signed with the client secret for the known code and verifier
+    fn mock_issuer(nonce: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let base = issuer.clone();
+        let server = HttpServer::new(move || {
+            let discovery_issuer = base.clone();
+            let token_issuer = base.clone();
+            App::new()
+                .route(
+                    "/.well-known/openid-configuration",
+                    get().to(move || {
+                        let issuer = discovery_issuer.clone();
+                        async move {
+                            HttpResponse::Ok().json(json!({
+                                "issuer": issuer,
+                                "authorization_endpoint": format!("{}/authorize", issuer),
+                                "token_endpoint": format!("{}/token", issuer),
+                            }))
+                        }
+                    }),
+                )
+                .route(
+                    "/token",
+                    post().to(move |Form(form): Form<HashMap<String, String>>| {
+                        let issuer = token_issuer.clone();
+                        async move {
+                            if form.get("code").map(|c| c.as_str()) != Some(CODE) || form.get("code_verifier").map(|v| v.as_str()) != Some(VERIFIER) {
+                                return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+                            }
+                            let claims = json!({
+                                "iss": issuer,
+                                "aud": CLIENT_ID,
+                                "sub": "42",
+                                "exp": chrono::Utc::now().timestamp() + 300,
+                                "nonce": nonce,
+                                "email": "alice@example.com",
+                                "email_verified": true,
+                            });
+                            let id_token = encode(&Header::default(), &claims, &EncodingKey::from_secret(CLIENT_SECRET.as_bytes())).unwrap();
+                            HttpResponse::Ok().json(json!({ "access_token": "access", "token_type": "Bearer", "id_token": id_token }))
+                        }
+                    }),
+                )
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+        issuer
+    }
+
+    async fn provider_with(issuer: String, algorithms: Vec<Algorithm>) -> OidcProvider {
+        OidcProvider::discover(OidcConfig {
+            issuer,
+            client_id: CLIENT_ID.into(),
+            client_secret: CLIENT_SECRET.into(),
+            redirect_uri: "http://localhost:8000/oidc/callback".into(),
+            scopes: "openid email".into(),
+            algorithms,
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn provider(issuer: String) -> OidcProvider {
+        provider_with(issuer, vec![Algorithm::HS256]).await
+    }
+
+    #[test]
+    fn test_parse_algorithms() {
+        assert_eq!(parse_algorithms("RS256, ES256").unwrap(), vec![Algorithm::RS256, Algorithm::ES256]);
+        assert!(parse_algorithms("").is_err());
+        assert!(parse_algorithms("none").is_err());
+    }
+
+    #[actix_web::test]
+    async fn test_authorization_url() {
+        let issuer = mock_issuer("nonce");
+        let provider = provider(issuer.clone()).await;
+        let url = Url::parse(&provider.authorization_url("state", "nonce", "challenge")).unwrap();
+        let params: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(format!("{}{}", url.origin().ascii_serialization(), url.path()), format!("{}/authorize", issuer));
+        assert_eq!(params["code_challenge"], "challenge");
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["state"], "state");
+        assert_eq!(params["client_id"], CLIENT_ID);
+    }
+
+    #[actix_web::test]
+    async fn test_exchange() {
+        let issuer = mock_issuer("nonce");
+        let provider = provider(issuer.clone()).await;
+        let identity = provider.exchange(CODE, VERIFIER, "nonce").await.unwrap();
+        assert_eq!(identity.issuer, issuer);
+        assert_eq!(identity.subject, "42");
+        assert_eq!(identity.email.as_deref(), Some("alice@example.com"));
+        assert!(identity.email_verified);
+        assert!(matches!(provider.exchange(CODE, "other", "nonce").await, Err(Error::Unauthorized)));
+        assert!(matches!(provider.exchange(CODE, VERIFIER, "other").await, Err(Error::Unauthorized)));
+    }
+
+    #[actix_web::test]
+    async fn test_pinned_algorithms() {
+        // the mock issuer signs with HS256, which is not allowed here
+        let issuer = mock_issuer("nonce");
+        let provider = provider_with(issuer, vec![Algorithm::RS256]).await;
+        assert!(matches!(provider.exchange(CODE, VERIFIER, "nonce").await, Err(Error::Unauthorized)));
+    }
+}
@@ -1 +1,4 @@
+pub mod hasher;
+pub mod identity;
+pub mod notifier;
 pub mod tokener;
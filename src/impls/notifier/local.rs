@@ -0,0 +1,32 @@
+use crate::core::ports::notifier::{Notification, Notifier};
+use crate::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+// For local development, the notifications are appended to a file, or printed to stdout when no file is given.
+pub struct LocalNotifier {
+    path: Option<String>,
+}
+
+impl LocalNotifier {
+    pub fn new(path: Option<String>) -> Self {
+        Self { path }
+    }
+}
+
+fn format(notification: &Notification) -> String {
+    format!("To: {}\nSubject: {}\n\n{}\n\n", notification.to, notification.subject, notification.body)
+}
+
+impl Notifier for LocalNotifier {
+    async fn notify(&self, notification: Notification) -> Result<(), Error> {
+        match &self.path {
+            Some(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                file.write_all(format(&notification).as_bytes())?;
+            }
+            None => print!("{}", format(&notification)),
+        }
+        Ok(())
+    }
+}
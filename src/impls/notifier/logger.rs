@@ -0,0 +1,18 @@
+use crate::core::ports::notifier::{Notification, Notifier};
+use crate::error::Error;
+
+// writes the notifications to the log, usable without network
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    async fn notify(&self, notification: Notification) -> Result<(), Error> {
+        log::info!(
+            "notification by {} to {}: {}\n{}",
+            notification.channel.as_str(),
+            notification.to,
+            notification.subject,
+            notification.body
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+pub mod local;
+pub mod logger;
+pub mod smtp;
+
+use crate::core::ports::notifier::{Notification, Notifier};
+use crate::error::Error;
+use local::LocalNotifier;
+use logger::LogNotifier;
+use smtp::SmtpNotifier;
+
+// the notifier picked by the `NOTIFIER` environment variable: `smtp`, `log`, or `local` by default
+pub enum EnvNotifier {
+    Smtp(SmtpNotifier),
+    Log(LogNotifier),
+    Local(LocalNotifier),
+}
+
+impl EnvNotifier {
+    pub fn from_env() -> Result<Self, Error> {
+        let notifier = match dotenv::var("NOTIFIER").as_deref() {
+            Ok("smtp") => {
+                let port = match dotenv::var("SMTP_PORT") {
+                    Ok(port) => port.parse()?,
+                    Err(_) => 587,
+                };
+                EnvNotifier::Smtp(SmtpNotifier::new(
+                    &dotenv::var("SMTP_HOST")?,
+                    port,
+                    dotenv::var("SMTP_USERNAME")?,
+                    dotenv::var("SMTP_PASSWORD")?,
+                    &dotenv::var("SMTP_FROM")?,
+                    dotenv::var("SMTP_SMS_GATEWAY").ok(),
+                )?)
+            }
+            Ok("log") => EnvNotifier::Log(LogNotifier),
+            _ => EnvNotifier::Local(LocalNotifier::new(dotenv::var("NOTIFY_FILE").ok())),
+        };
+        Ok(notifier)
+    }
+}
+
+impl Notifier for EnvNotifier {
+    async fn notify(&self, notification: Notification) -> Result<(), Error> {
+        match self {
+            EnvNotifier::Smtp(notifier) => notifier.notify(notification).await,
+            EnvNotifier::Log(notifier) => notifier.notify(notification).await,
+            EnvNotifier::Local(notifier) => notifier.notify(notification).await,
+        }
+    }
+}
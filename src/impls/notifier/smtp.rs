@@ -0,0 +1,50 @@
+use crate::core::models::verification::Channel;
+use crate::core::ports::notifier::{Notification, Notifier};
+use crate::error::Error;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    // phones are reached through an email to SMS gateway, e.g. `sms.example.com` for `13800000000@sms.example.com`
+    sms_gateway: Option<String>,
+}
+
+fn smtp_error(e: impl ToString) -> Error {
+    Error::ServerError(e.to_string())
+}
+
+impl SmtpNotifier {
+    pub fn new(host: &str, port: u16, username: String, password: String, from: &str, sms_gateway: Option<String>) -> Result<Self, Error> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(smtp_error)?
+            .port(port)
+            .credentials(Credentials::new(username, password))
+            .build();
+        Ok(Self {
+            transport,
+            from: from.parse().map_err(smtp_error)?,
+            sms_gateway,
+        })
+    }
+}
+
+impl Notifier for SmtpNotifier {
+    async fn notify(&self, notification: Notification) -> Result<(), Error> {
+        let to = match (notification.channel, &self.sms_gateway) {
+            (Channel::Email, _) => notification.to,
+            (Channel::Phone, Some(gateway)) => format!("{}@{}", notification.to, gateway),
+            (Channel::Phone, None) => return Err(Error::BusinessError("notifications by phone are not supported".into())),
+        };
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse().map_err(smtp_error)?)
+            .subject(notification.subject)
+            .body(notification.body)
+            .map_err(smtp_error)?;
+        self.transport.send(message).await.map_err(smtp_error)?;
+        Ok(())
+    }
+}
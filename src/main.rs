@@ -3,6 +3,10 @@
 
 extern crate actix_multipart;
 extern crate actix_web;
+extern crate argon2;
+extern crate async_trait;
+extern crate base32;
+extern crate base64;
 extern crate bytes;
 extern crate casbin;
 extern crate chrono;
@@ -13,11 +17,15 @@ extern crate futures;
 extern crate futures_util;
 extern crate hex;
 extern crate hex_literal;
+extern crate hmac;
 extern crate itertools;
 extern crate jsonwebtoken;
+extern crate lettre;
 extern crate rand;
+extern crate reqwest;
 extern crate serde;
 extern crate serde_json;
+extern crate sha1;
 extern crate sha2;
 extern crate sqlx;
 extern crate sqlx_insert;
@@ -41,9 +49,13 @@ pub mod impls;
 
 use actix_web::web::{delete, get, post, put, scope, Data};
 use actix_web::HttpServer;
-use authorizer::PgAuthorizer;
-use middlewares::authorizer::Author;
-use middlewares::jwt::JWTMiddleware;
+use crate::core::models::invite_code::Admins;
+use crate::core::models::verification::Policy;
+use impls::identity::oidc::{OidcConfig, OidcProvider};
+use impls::notifier::EnvNotifier;
+use middlewares::authorizer::{Author, Permission};
+use middlewares::jwt::{GuestMiddleware, JWTMiddleware};
+use privilege::casbin::CasbinAuthorizer;
 use sqlx::postgres::PgPoolOptions;
 
 #[derive(Debug, Clone)]
@@ -61,25 +73,77 @@ async fn main() -> Result<(), std::io::Error> {
         .await
         .expect("failed to connect to database");
     let jwt_secret = dotenv::var("JWT_SECRET").expect("environment variable JWT_SECRET not been set").as_bytes().to_owned();
+    let notifier = Data::new(EnvNotifier::from_env().expect("failed to configure the notifier"));
+    let oidc_provider = match OidcConfig::from_env().expect("failed to configure single sign-on") {
+        Some(config) => Some(Data::new(OidcProvider::discover(config).await.expect("failed to discover the OpenID Connect issuer"))),
+        None => None,
+    };
+    // shared by the workers, the rules are loaded from the database
+    let authorizer = Data::new(CasbinAuthorizer::new(pool.clone()).await.expect("failed to load the authorization rules"));
+    let verification_policy = Policy {
+        login: dotenv::var("REQUIRE_VERIFIED_LOGIN").map(|v| v == "true").unwrap_or(false),
+        search: dotenv::var("REQUIRE_VERIFIED_SEARCH").map(|v| v == "true").unwrap_or(false),
+    };
+    let invite_code_admins = Data::new(Admins::parse(&dotenv::var("INVITE_CODE_ADMINS").unwrap_or_default()).expect("invalid user id in INVITE_CODE_ADMINS"));
     HttpServer::new(move || {
         actix_web::App::new()
             .wrap(actix_web::middleware::Logger::default())
             .app_data(Data::new(pool.clone()))
-            .app_data(Data::new(PgAuthorizer::new(pool.clone())))
+            .app_data(authorizer.clone())
             .app_data(Data::new(storer::LocalStorer::new(&upload_path)))
             .app_data(Data::new(UploadPath(upload_path.clone())))
+            .app_data(notifier.clone())
+            .app_data(Data::new(verification_policy))
+            .app_data(invite_code_admins.clone())
             .service(
                 scope("")
                     .route("login", post().to(handlers::login))
+                    .route("login/two_factor", post().to(handlers::login_two_factor))
                     .route("signup", post().to(handlers::signup))
                     .route("logout", get().to(handlers::logout))
+                    .route("refresh", post().to(handlers::refresh))
+                    .route("password_reset", post().to(handlers::request_password_reset::<EnvNotifier>))
+                    .route("password_reset", put().to(handlers::reset_password))
+                    .route("verifications", post().to(handlers::request_verification::<EnvNotifier>))
+                    .route("verifications", put().to(handlers::verify))
+                    .route("share/{token}", post().to(handlers::share_link::join))
+                    .configure(|cfg| {
+                        if let Some(provider) = &oidc_provider {
+                            cfg.app_data(provider.clone())
+                                .route("oidc/login", get().to(handlers::oidc::login::<OidcProvider>))
+                                .route("oidc/callback", get().to(handlers::oidc::callback::<OidcProvider>));
+                        }
+                    })
+                    .service(
+                        scope("guest")
+                            .wrap(GuestMiddleware::new(jwt_secret.clone()))
+                            .route("vote", get().to(handlers::share_link::vote))
+                            .route("answers", post().to(handlers::share_link::submit_answers)),
+                    )
                     .service(
                         scope("")
-                        .wrap(JWTMiddleware::new(jwt_secret.clone()))
+                        .wrap(JWTMiddleware::new(jwt_secret.clone(), pool.clone()))
+                            .service(
+                                scope("invite_codes")
+                                    .route("", get().to(handlers::invite_code::list))
+                                    .route("", post().to(handlers::invite_code::create))
+                                    .route("{invite_code_id}", delete().to(handlers::invite_code::revoke)),
+                            )
                             .service(
                                 scope("profile")
                                 .route("", get().to(handlers::user::profile))
                                 .route("", put().to(handlers::user::update_profile))
+                                .route("password", put().to(handlers::user::change_password))
+                                .route("two_factor", post().to(handlers::two_factor::enroll))
+                                .route("two_factor", put().to(handlers::two_factor::confirm))
+                                .route("two_factor", delete().to(handlers::two_factor::disable))
+                                .route("two_factor/recovery_codes", post().to(handlers::two_factor::regenerate_recovery_codes))
+                                .route("tokens", get().to(handlers::personal_token::list))
+                                .route("tokens", post().to(handlers::personal_token::create))
+                                .route("tokens/{token_id}", delete().to(handlers::personal_token::revoke))
+                                .route("sessions", get().to(handlers::user::sessions))
+                                .route("sessions", delete().to(handlers::user::revoke_other_sessions))
+                                .route("sessions/{session_id}", delete().to(handlers::user::revoke_session))
                             )
                             .service(
                                 scope("upload")
@@ -92,49 +156,73 @@ async fn main() -> Result<(), std::io::Error> {
                                     .route("", post().to(handlers::organization::create))
                                     .service(
                                         scope("{organization_id}")
-                                            .wrap(Author::new(
-                                                pool.clone(),
-                                                "SELECT EXISTS(SELECT id FROM organization_members WHERE user_id = $1 AND organization_id = $2)",
-                                                "organization_id",
-                                            ))
-                                            .route("", get().to(handlers::organization::detail))
-                                            .route("", put().to(handlers::organization::update))
-                                            .route("", delete().to(handlers::organization::delete_organization))
+                                            // any member can leave, the editors create the votes, the services check their roles
+                                            .service(
+                                                scope("membership")
+                                                .wrap(Author::<CasbinAuthorizer>::organization().writes(Permission::Read))
+                                                .route("", delete().to(handlers::organization::leave)))
                                             .service(
                                                 scope("votes")
+                                                .wrap(Author::<CasbinAuthorizer>::organization().writes(Permission::Read))
                                                 .route("", post().to(handlers::vote::create))
                                                 .route("", get().to(handlers::organization::votes)))
                                             .service(
-                                                scope("users")
-                                                    .route("", post().to(handlers::organization::add_users))
-                                                    .route("", get().to(handlers::organization::members::<PgAuthorizer>)),
-                                            )
-                                            .service(
-                                                scope("managers")
-                                                .wrap(
-                                                    Author::new(
-                                                        pool.clone(), 
-                                                        "SELECT EXISTS(SELECT id FROM organization_managers WHERE user_id = $1 AND organization_id = $2", 
-                                                        "organization_id"))
-                                                .route("", post().to(handlers::organization::add_manager))
+                                                scope("")
+                                                    .wrap(Author::<CasbinAuthorizer>::organization())
+                                                    .route("", get().to(handlers::organization::detail))
+                                                    .route("", put().to(handlers::organization::update))
+                                                    .route("", delete().to(handlers::organization::delete_organization))
+                                                    .route("two_factor", put().to(handlers::two_factor::require_for_managers))
+                                                    .route("owner", put().to(handlers::organization::transfer_ownership))
+                                                    .service(
+                                                        scope("users")
+                                                            .route("", post().to(handlers::organization::add_users))
+                                                            .route("", get().to(handlers::organization::members::<CasbinAuthorizer>))
+                                                            .route("{user_id}", delete().to(handlers::organization::remove_member))
+                                                            .route("{user_id}/role", put().to(handlers::organization::update_role)),
+                                                    )
+                                                    .service(
+                                                        scope("groups")
+                                                            .route("", get().to(handlers::group::list))
+                                                            .route("", post().to(handlers::group::create))
+                                                            .route("{group_id}", put().to(handlers::group::update))
+                                                            .route("{group_id}", delete().to(handlers::group::delete))
+                                                            .route("{group_id}/users", get().to(handlers::group::members))
+                                                            .route("{group_id}/users", post().to(handlers::group::add_members))
+                                                            .route("{group_id}/users/{user_id}", delete().to(handlers::group::remove_member)),
+                                                    )
+                                                    .service(
+                                                        scope("invitations")
+                                                            .route("", get().to(handlers::invite_code::invitations))
+                                                            .route("", post().to(handlers::invite_code::create_invitation))
+                                                            .route("{invite_code_id}", delete().to(handlers::invite_code::revoke_invitation)),
+                                                    )
+                                                    .service(
+                                                        scope("applications")
+                                                        .route("", get().to(handlers::application::list))
+                                                        .route("{application_id}/approve", put().to(handlers::application::approve_application))
+                                                        .route("{application_id}/reject", put().to(handlers::application::reject))
+                                                    )
+                                                    .service(
+                                                        scope("managers")
+                                                        .route("", post().to(handlers::organization::add_manager))
+                                                        .route("{user_id}", delete().to(handlers::organization::demote_manager))
+                                                    )
                                             )
                                     ),
                             )
                             .service(
                                 scope("votes").route("", post().to(handlers::vote::create)).service(
                                     scope("{vote_id}")
-                                        .wrap(Author::new(
-                                            pool.clone(),
-                                            "SELECT EXISTS(SELECT uo.id FROM organization_members AS uo JOIN votes AS v ON uo.organization_id = v.organization_id WHERE uo.user_id = $1 AND v.id = $2)",
-                                            "vote_id",
-                                        ))
-                                        .route("", get().to(handlers::vote::detail))
-                                        .route("", put().to(handlers::vote::update))
-                                        .route("", delete().to(handlers::vote::delete_vote))
-                                        .route("questions_with_options", get().to(handlers::question::questions_with_options_by_vote_id))
-                                        .route("question_ids", get().to(handlers::vote::question_ids))
+                                        // the voters only need to read the vote to answer it, the services check they take part in it
+                                        .service(
+                                            scope("answers")
+                                                .wrap(Author::<CasbinAuthorizer>::vote().writes(Permission::Read))
+                                                .route("", post().to(handlers::vote::submit_answers)),
+                                        )
                                         .service(
                                             scope("date_ranges")
+                                                .wrap(Author::<CasbinAuthorizer>::vote().writes(Permission::Read))
                                                 .route("", get().to(handlers::date::date_range_list))
                                                 .route("", put().to(handlers::date::submit_date_ranges))
                                                 .service(
@@ -144,37 +232,66 @@ async fn main() -> Result<(), std::io::Error> {
                                                 ),
                                         )
                                         .service(
-                                            scope("questions")
-                                                .route("", post().to(handlers::question::create))
-                                                .route("", get().to(handlers::vote::questions))
-                                                .route("report", get().to(handlers::vote::question_reports)),
-                                        )
-                                        .service(
-                                            scope("answers")
-                                            .route("", post().to(handlers::vote::submit_answers))
-                                        )
-                                        
+                                            scope("")
+                                                .wrap(Author::<CasbinAuthorizer>::vote())
+                                                .route("", get().to(handlers::vote::detail))
+                                                .route("", put().to(handlers::vote::update))
+                                                .route("", delete().to(handlers::vote::delete_vote))
+                                                .route("questions_with_options", get().to(handlers::question::questions_with_options_by_vote_id))
+                                                .route("question_ids", get().to(handlers::vote::question_ids))
+                                                .route("publish", put().to(handlers::vote::publish))
+                                                .route("close", put().to(handlers::vote::close))
+                                                .route("reopen", put().to(handlers::vote::reopen))
+                                                .route("archive", put().to(handlers::vote::archive))
+                                                .route("grace_period", put().to(handlers::vote::update_grace_period))
+                                                .route("visibility", put().to(handlers::vote::update_visibility))
+                                                .route("groups", get().to(handlers::group::of_vote))
+                                                .route("groups", put().to(handlers::group::target))
+                                                .service(
+                                                    scope("whitelist")
+                                                        .route("", get().to(handlers::vote::whitelist))
+                                                        .route("", post().to(handlers::vote::add_to_whitelist))
+                                                        .route("{user_id}", delete().to(handlers::vote::remove_from_whitelist)),
+                                                )
+                                                .service(
+                                                    scope("share_links")
+                                                        .route("", get().to(handlers::share_link::list))
+                                                        .route("", post().to(handlers::share_link::create))
+                                                        .route("{share_link_id}", delete().to(handlers::share_link::revoke)),
+                                                )
+                                                .service(
+                                                    scope("questions")
+                                                        .route("", post().to(handlers::question::create))
+                                                        .route("", get().to(handlers::vote::questions))
+                                                        .route("report", get().to(handlers::vote::question_reports))
+                                                        .route("ranked_report", get().to(handlers::vote::ranked_reports)),
+                                                ),
+                                        ),
                                 ),
                             )
                             .service(
                                 scope("questions").service(
                                     scope("{question_id}")
-                                        .wrap(Author::new(
-                                            pool.clone(),
-                                            "SELECT EXISTS(SELECT uo.id FROM organization_members AS uo JOIN votes AS v ON uo.organization_id = v.organization_id JOIN questions AS q ON v.id = q.vote_id WHERE uo.user_id = $1 AND q.id = $2)",
-                                            "question_id"
-                                        ))
-                                        .route("", get().to(handlers::question::detail))
-                                        .route("", delete().to(handlers::question::delete))
                                         .service(
-                                            scope("options")
-                                            .route("", post().to(handlers::option::add_opts))
-                                            .route("", get().to(handlers::question::options))
+                                            scope("answers")
+                                                .wrap(Author::<CasbinAuthorizer>::question().writes(Permission::Read))
+                                                .route("", get().to(handlers::question::answers))
+                                                .route("", put().to(handlers::answer::submit_answer))
+                                                .route("value", put().to(handlers::answer::submit_value))
+                                                .route("ratings", put().to(handlers::answer::submit_ratings)),
                                         )
                                         .service(
-                                            scope("answers")
-                                            .route("", get().to(handlers::question::answers))
-                                            .route("", put().to(handlers::answer::submit_answer))
+                                            scope("")
+                                                .wrap(Author::<CasbinAuthorizer>::question())
+                                                .route("", get().to(handlers::question::detail))
+                                                .route("", delete().to(handlers::question::delete))
+                                                .route("tally_method", put().to(handlers::question::update_tally_method))
+                                                .route("texts", get().to(handlers::question::texts))
+                                                .service(
+                                                    scope("options")
+                                                        .route("", post().to(handlers::option::add_opts))
+                                                        .route("", get().to(handlers::question::options)),
+                                                ),
                                         ),
                                 ),
                             )
@@ -182,24 +299,20 @@ async fn main() -> Result<(), std::io::Error> {
                                 scope("options")
                                 .service(
                                     scope("{option_id}")
-                                    .wrap(Author::new(
-                                        pool.clone(),
-                                        "SELECT EXISTS(
-                                            SELECT uo.id
-                                            FROM organization_members AS uo
-                                            JOIN votes AS v ON uo.organization_id = v.organization_id
-                                            JOIN questions AS q ON v.id = q.vote_id
-                                            JOIN options AS o ON q.id = o.question_id
-                                            WHERE uo.user_id = $1 AND o.id = $2)",
-                                        "option_id"
-                                    ))
+                                    .wrap(Author::<CasbinAuthorizer>::option())
                                     .route("", delete().to(handlers::option::delete))
                                 )
                             )
+                            .service(
+                                scope("applications")
+                                .route("", post().to(handlers::application::create_join_application))
+                                .route("{application_id}", delete().to(handlers::application::withdraw))
+                            )
                             .service(scope("users").route("", get().to(handlers::user::find)))
                             .service(
                                 scope("my")
                                 .route("organizations", get().to(handlers::organization::my_organizations))
+                                .route("applications", get().to(handlers::application::my_applications))
                             )
                     ),
             )
@@ -1,61 +1,183 @@
-use crate::{context::UserInfo, error::Error};
+use crate::core::ports::repository::{OptionCommon, OrganizationCommon, QuestionCommon, Store, VoteCommon};
+use crate::database::sqlx::PgSqlx;
+use crate::{context::UserInfo, handlers::authorizer::Authorizer};
 use actix_web::{
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    error::{ErrorBadRequest, ErrorForbidden, ErrorInternalServerError, ErrorNotFound, ErrorUnauthorized},
+    http::Method,
+    web::Data,
     HttpMessage,
 };
-use sqlx::{query::QueryScalar, query_scalar, PgPool};
+use sqlx::PgPool;
+use std::collections::HashMap;
 use std::future::Future;
 use std::future::{ready, Ready};
+use std::marker::PhantomData;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::Poll;
 
-pub struct Author {
-    db: PgPool,
-    sql_stmt: String,
-    path_arg_name: String,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    Read,
+    Write,
 }
 
-impl Author {
-    pub fn new(db: PgPool, sql_stmt: &str, path_arg_name: &str) -> Self {
+// the resource is identified by the path argument named after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Resource {
+    Organization,
+    Vote,
+    Question,
+    Option,
+}
+
+impl Resource {
+    fn path_arg_name(&self) -> &'static str {
+        match self {
+            Resource::Organization => "organization_id",
+            Resource::Vote => "vote_id",
+            Resource::Question => "question_id",
+            Resource::Option => "option_id",
+        }
+    }
+
+    async fn exists<S: Store>(&self, store: &mut S, id: i32) -> Result<bool, crate::error::Error> {
+        match self {
+            Resource::Organization => OrganizationCommon::exists_by_id(store, id).await,
+            Resource::Vote => VoteCommon::exists_by_id(store, id).await,
+            Resource::Question => QuestionCommon::exists_by_id(store, id).await,
+            Resource::Option => OptionCommon::exists_by_id(store, id).await,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Allowed,
+    Forbidden,
+    NotFound,
+}
+
+// Decisions already taken for the request, so the nested scopes checking the same resource do not ask the authorizer
+// again.
+#[derive(Debug, Default, Clone)]
+struct Decisions(HashMap<(Resource, i32, Permission), Decision>);
+
+async fn check<A: Authorizer, S: Store>(authorizer: &A, store: &mut S, uid: i32, resource: Resource, id: i32, permission: Permission) -> Result<bool, crate::error::Error> {
+    // an option is accessed through its question
+    let id = match resource {
+        Resource::Option => match OptionCommon::get_question_id(store, id).await? {
+            Some(question_id) => question_id,
+            None => return Ok(false),
+        },
+        _ => id,
+    };
+    Ok(match (resource, permission) {
+        (Resource::Organization, Permission::Read) => authorizer.check_organization_read(uid, id).await?,
+        (Resource::Organization, Permission::Write) => authorizer.check_organization_write(uid, id).await?,
+        (Resource::Vote, Permission::Read) => authorizer.check_vote_read(uid, id).await?,
+        (Resource::Vote, Permission::Write) => authorizer.check_vote_write(uid, id).await?,
+        (Resource::Question | Resource::Option, Permission::Read) => authorizer.check_question_read(uid, id).await?,
+        (Resource::Question | Resource::Option, Permission::Write) => authorizer.check_question_write(uid, id).await?,
+    })
+}
+
+async fn decide<A: Authorizer>(authorizer: &A, uid: i32, resource: Resource, id: i32, permission: Permission, db: &PgPool) -> Result<Decision, crate::error::Error> {
+    let mut store = PgSqlx::new(db.acquire().await?);
+    if check(authorizer, &mut store, uid, resource, id, permission).await? {
+        return Ok(Decision::Allowed);
+    }
+    // the missing resources are told apart from the forbidden ones only after the check failed
+    Ok(if resource.exists(&mut store, id).await? { Decision::Forbidden } else { Decision::NotFound })
+}
+
+// Checks the permission of the user on the resource of the scope through the registered `Data<A>`, e.g.
+// `Author::<CasbinAuthorizer>::vote()`. The reads require the read permission and the other methods the write
+// permission, unless the scope lets the readers write, e.g. `Author::<CasbinAuthorizer>::vote().writes(Permission::Read)`
+// for the voters answering a vote.
+pub struct Author<A> {
+    resource: Resource,
+    writes: Permission,
+    authorizer: PhantomData<A>,
+}
+
+impl<A> Author<A> {
+    fn new(resource: Resource) -> Self {
         Self {
-            db,
-            sql_stmt: sql_stmt.into(),
-            path_arg_name: path_arg_name.into(),
+            resource,
+            writes: Permission::Write,
+            authorizer: PhantomData,
         }
     }
+
+    pub fn organization() -> Self {
+        Self::new(Resource::Organization)
+    }
+
+    pub fn vote() -> Self {
+        Self::new(Resource::Vote)
+    }
+
+    pub fn question() -> Self {
+        Self::new(Resource::Question)
+    }
+
+    pub fn option() -> Self {
+        Self::new(Resource::Option)
+    }
+
+    // the permission required by the methods other than GET and HEAD
+    pub fn writes(mut self, permission: Permission) -> Self {
+        self.writes = permission;
+        self
+    }
+
+    fn required(&self, method: &Method) -> Permission {
+        required(method, self.writes)
+    }
 }
 
-impl<S> Transform<S, ServiceRequest> for Author
+fn required(method: &Method, writes: Permission) -> Permission {
+    match *method {
+        Method::GET | Method::HEAD => Permission::Read,
+        _ => writes,
+    }
+}
+
+impl<S, A> Transform<S, ServiceRequest> for Author<A>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse, Error = actix_web::Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = actix_web::Error> + 'static,
     S::Future: 'static,
+    A: Authorizer + 'static,
 {
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
     type Response = S::Response;
     type Error = S::Error;
     type InitError = ();
-    type Transform = AuthorMiddleware<S>;
+    type Transform = AuthorMiddleware<S, A>;
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(AuthorMiddleware {
-            db: self.db.clone(),
-            sql_stmt: self.sql_stmt.clone(),
-            path_arg_name: self.path_arg_name.clone(),
-            service,
+            resource: self.resource,
+            writes: self.writes,
+            service: Rc::new(service),
+            authorizer: PhantomData,
         }))
     }
 }
 
-pub struct AuthorMiddleware<S> {
-    db: PgPool,
-    sql_stmt: String,
-    path_arg_name: String,
-    service: S,
+pub struct AuthorMiddleware<S, A> {
+    resource: Resource,
+    writes: Permission,
+    service: Rc<S>,
+    authorizer: PhantomData<A>,
 }
 
-impl<S> Service<ServiceRequest> for AuthorMiddleware<S>
+impl<S, A> Service<ServiceRequest> for AuthorMiddleware<S, A>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse, Error = actix_web::Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = actix_web::Error> + 'static,
     S::Future: 'static,
+    A: Authorizer + 'static,
 {
     type Response = S::Response;
     type Error = S::Error;
@@ -64,40 +186,69 @@ where
         Poll::Ready(Ok(()))
     }
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let user_info = req.extensions_mut().remove::<UserInfo>();
-        let path = req.match_info().clone();
-        if let Some(user_info) = user_info {
-            req.extensions_mut().insert(user_info.clone());
-            let uid = user_info.id;
-            let next = self.service.call(req);
-            if let Some(oid) = path.get(&self.path_arg_name) {
-                if let Ok(oid) = oid.parse::<i32>() {
-                    let stmt = self.sql_stmt.clone();
-                    let db = self.db.clone();
-                    return Box::pin(async move {
-                        let q: QueryScalar<_, bool, _> = query_scalar(&stmt).bind(uid).bind(oid);
-                        match db.acquire().await {
-                            Ok(mut conn) => match q.fetch_one(&mut conn).await {
-                                Ok(is_valid) => {
-                                    if !is_valid {
-                                        return Err(actix_web::error::ErrorForbidden("forbidden"));
-                                    }
-                                    next.await
-                                }
-                                Err(err) => Err(actix_web::error::ErrorInternalServerError(err)),
-                            },
-                            Err(err) => Err(actix_web::error::ErrorInternalServerError(err)),
+        let uid = match req.extensions().get::<UserInfo>() {
+            Some(user_info) => user_info.id,
+            None => return Box::pin(async move { Err(ErrorUnauthorized("unauthorized")) }),
+        };
+        let id = match req.match_info().get(self.resource.path_arg_name()) {
+            Some(id) => match id.parse::<i32>() {
+                Ok(id) => id,
+                Err(_) => return Box::pin(async move { Err(ErrorBadRequest("invalid argument")) }),
+            },
+            None => return Box::pin(async move { Err(ErrorInternalServerError("authorizer middleware may mounted at a unsuitable position")) }),
+        };
+        let (authorizer, db) = match (req.app_data::<Data<A>>(), req.app_data::<Data<PgPool>>()) {
+            (Some(authorizer), Some(db)) => (authorizer.clone(), db.clone()),
+            _ => return Box::pin(async move { Err(ErrorInternalServerError("authorizer has not been registered")) }),
+        };
+        let key = (self.resource, id, required(req.method(), self.writes));
+        let cached = req.extensions().get::<Decisions>().and_then(|decisions| decisions.0.get(&key).copied());
+        let next = self.service.clone();
+        Box::pin(async move {
+            let decision = match cached {
+                Some(decision) => decision,
+                None => {
+                    let (resource, id, permission) = key;
+                    let decision = decide(authorizer.get_ref(), uid, resource, id, permission, db.get_ref()).await.map_err(ErrorInternalServerError)?;
+                    let mut extensions = req.extensions_mut();
+                    match extensions.get_mut::<Decisions>() {
+                        Some(decisions) => {
+                            decisions.0.insert(key, decision);
                         }
-                    });
+                        None => {
+                            let mut decisions = Decisions::default();
+                            decisions.0.insert(key, decision);
+                            extensions.insert(decisions);
+                        }
+                    }
+                    decision
                 }
-                return Box::pin(async move { Err(actix_web::error::ErrorBadRequest("invalid argument")) });
+            };
+            match decision {
+                Decision::Allowed => next.call(req).await,
+                Decision::Forbidden => Err(ErrorForbidden("forbidden")),
+                Decision::NotFound => Err(ErrorNotFound("not found")),
             }
-            return Box::pin(async move {
-                Err(actix_web::error::ErrorInternalServerError(Error::ServerError(
-                    "authorizer middleware may mounted at a unsuitable position".into(),
-                )))
-            });
-        }
-        Box::pin(async move { Err(actix_web::error::ErrorUnauthorized("unauthorized")) })
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Unused;
+
+    #[test]
+    fn test_required_permission() {
+        let organization = Author::<Unused>::organization();
+        assert_eq!(organization.required(&Method::GET), Permission::Read);
+        assert_eq!(organization.required(&Method::HEAD), Permission::Read);
+        assert_eq!(organization.required(&Method::PUT), Permission::Write);
+        // a scope can not be deleted with the read permission unless it says so
+        assert_eq!(organization.required(&Method::DELETE), Permission::Write);
+        let answers = Author::<Unused>::vote().writes(Permission::Read);
+        assert_eq!(answers.required(&Method::POST), Permission::Read);
+        assert_eq!(answers.required(&Method::GET), Permission::Read);
     }
 }
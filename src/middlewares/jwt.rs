@@ -2,22 +2,36 @@ use serde::{Deserialize, Serialize};
 
 use crate::actix_web::{
     dev::{Service, ServiceRequest, Transform},
-    error::ErrorUnauthorized,
+    error::{ErrorInternalServerError, ErrorUnauthorized},
+    http::{header::AUTHORIZATION, Method},
     Error, HttpMessage,
 };
-use crate::context::UserInfo;
+use crate::context::{GuestInfo, UserInfo};
+use crate::core::ports::repository::SessionCommon;
 use crate::core::ports::tokener::{Payload, Tokener};
+use crate::core::services::personal_token::authenticate;
+use crate::database::sqlx::PgSqlx;
 use crate::impls::tokener::jwt::JWT;
-use std::future::Future;
+use sqlx::PgPool;
+use std::future::{ready, Future, Ready};
 use std::pin::Pin;
+use std::rc::Rc;
 
 pub static JWT_TOKEN: &str = "JWT_TOKEN";
+pub static GUEST_TOKEN: &str = "GUEST_TOKEN";
+pub static REFRESH_TOKEN: &str = "REFRESH_TOKEN";
 pub static JWT_SECRET: &str = "JWT_SECRET";
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Claim {
     pub user: String,
     pub exp: i64,
+    // set for the tokens issued to the guests of a share link, `user` is not an user id then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest: Option<GuestInfo>,
+    // the server side session the token was issued for, checked on every request so it can be revoked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<i32>,
 }
 
 impl Payload for Claim {
@@ -28,11 +42,12 @@ impl Payload for Claim {
 
 pub(crate) struct JWTMiddleware {
     secret: Vec<u8>,
+    db: PgPool,
 }
 
 impl JWTMiddleware {
-    pub fn new(secret: Vec<u8>) -> Self {
-        Self { secret }
+    pub fn new(secret: Vec<u8>, db: PgPool) -> Self {
+        Self { secret, db }
     }
 }
 
@@ -49,10 +64,12 @@ where
     type Future = Pin<Box<dyn Future<Output = Result<Self::Transform, Self::InitError>>>>;
     fn new_transform(&self, service: S) -> Self::Future {
         let secret = self.secret.clone();
+        let db = self.db.clone();
         Box::pin(async move {
             Ok(JWTService {
                 tokener: JWT::new(secret),
-                next_service: service,
+                db,
+                next_service: Rc::new(service),
             })
         })
     }
@@ -60,12 +77,19 @@ where
 
 pub struct JWTService<S> {
     tokener: JWT,
-    next_service: S,
+    db: PgPool,
+    next_service: Rc<S>,
+}
+
+// personal access tokens are sent by the scripts in the header instead of the cookie
+fn bearer_token(req: &ServiceRequest) -> Option<String> {
+    let value = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    value.strip_prefix("Bearer ").map(|token| token.trim().to_owned())
 }
 
 impl<S> Service<ServiceRequest> for JWTService<S>
 where
-    S: Service<ServiceRequest>,
+    S: Service<ServiceRequest> + 'static,
     S::Future: 'static,
     S::Error: Into<Error>,
 {
@@ -77,24 +101,115 @@ where
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(token) = bearer_token(&req) {
+            let db = self.db.clone();
+            let next = self.next_service.clone();
+            let write = !matches!(*req.method(), Method::GET | Method::HEAD);
+            // the user must be known before the inner services are called
+            return Box::pin(async move {
+                let store = PgSqlx::new(db.acquire().await.map_err(ErrorInternalServerError)?);
+                match authenticate(store, &token, write).await.map_err(ErrorInternalServerError)? {
+                    None => Err(ErrorUnauthorized("invalid token or insufficient scope")),
+                    Some(id) => {
+                        req.extensions_mut().insert(UserInfo { id, session_id: None });
+                        let resp = next.call(req).await.map_err(|e| e.into())?;
+                        Ok(resp)
+                    }
+                }
+            });
+        }
         match req.cookie(JWT_TOKEN) {
-            None => {
-                return Box::pin(async move { Err(ErrorUnauthorized("no token in cookie")) });
-            }
+            None => Box::pin(async move { Err(ErrorUnauthorized("no token in cookie")) }),
             Some(cookie) => match <JWT as Tokener<Claim>>::verify_token(&self.tokener, cookie.value()) {
-                Err(e) => return Box::pin(async move { Err(ErrorUnauthorized(e)) }),
-                Ok(claim) => match claim.user.parse::<i32>() {
-                    Err(e) => return Box::pin(async move { Err(ErrorUnauthorized(e)) }),
+                Err(e) => Box::pin(async move { Err(ErrorUnauthorized(e)) }),
+                // the guest tokens are only accepted by the guest routes, see GuestMiddleware
+                Ok(Claim { guest: Some(_), .. }) => Box::pin(async move { Err(ErrorUnauthorized("guest token")) }),
+                Ok(Claim { sid: None, .. }) => Box::pin(async move { Err(ErrorUnauthorized("token without session")) }),
+                Ok(Claim { user, sid: Some(sid), .. }) => match user.parse::<i32>() {
+                    Err(e) => Box::pin(async move { Err(ErrorUnauthorized(e)) }),
                     Ok(id) => {
-                        req.extensions_mut().insert(UserInfo { id });
+                        req.extensions_mut().insert(UserInfo { id, session_id: Some(sid) });
+                        let db = self.db.clone();
+                        let next = self.next_service.clone();
+                        // the session is checked before the inner services are called
+                        Box::pin(async move {
+                            let mut store = PgSqlx::new(db.acquire().await.map_err(ErrorInternalServerError)?);
+                            if !SessionCommon::is_active(&mut store, sid).await.map_err(ErrorInternalServerError)? {
+                                return Err(ErrorUnauthorized("session has been revoked or expired"));
+                            }
+                            drop(store);
+                            let resp = next.call(req).await.map_err(|e| e.into())?;
+                            Ok(resp)
+                        })
                     }
                 },
             },
         }
-        let res_fut = self.next_service.call(req);
-        Box::pin(async move {
-            let resp = res_fut.await.map_err(|e| e.into())?;
-            Ok(resp)
-        })
+    }
+}
+
+// Identifies the guests joined through a share link. The guest token is kept in its own cookie, so answering a
+// shared vote does not log an user out, and it is read even when the browser also holds an user token.
+pub(crate) struct GuestMiddleware {
+    secret: Vec<u8>,
+}
+
+impl GuestMiddleware {
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self { secret }
+    }
+}
+
+impl<S> Transform<S, ServiceRequest> for GuestMiddleware
+where
+    S: Service<ServiceRequest> + 'static,
+    S::Future: 'static,
+    S::Error: Into<Error>,
+{
+    type Error = Error;
+    type Response = S::Response;
+    type Transform = GuestService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(GuestService {
+            tokener: JWT::new(self.secret.clone()),
+            next_service: service,
+        }))
+    }
+}
+
+pub struct GuestService<S> {
+    tokener: JWT,
+    next_service: S,
+}
+
+impl<S> Service<ServiceRequest> for GuestService<S>
+where
+    S: Service<ServiceRequest> + 'static,
+    S::Future: 'static,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+    fn poll_ready(&self, ctx: &mut core::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.next_service.poll_ready(ctx).map_err(|e| e.into())
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let cookie = match req.cookie(GUEST_TOKEN) {
+            None => return Box::pin(async move { Err(ErrorUnauthorized("no guest token in cookie")) }),
+            Some(cookie) => cookie,
+        };
+        match <JWT as Tokener<Claim>>::verify_token(&self.tokener, cookie.value()) {
+            Err(e) => Box::pin(async move { Err(ErrorUnauthorized(e)) }),
+            Ok(Claim { guest: None, .. }) => Box::pin(async move { Err(ErrorUnauthorized("not a guest token")) }),
+            Ok(Claim { guest: Some(guest), .. }) => {
+                req.extensions_mut().insert(guest);
+                let res_fut = self.next_service.call(req);
+                Box::pin(async move { res_fut.await.map_err(|e| e.into()) })
+            }
+        }
     }
 }
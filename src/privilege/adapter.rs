@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use casbin::{error::AdapterError, Adapter, Filter, Model, Result};
+use sqlx::{query_as, PgPool};
+
+use crate::core::models::role::{Permission, Role};
+use crate::database::sqlx::MEMBER_ROLE;
+
+// the objects of the rules with the permission needed to write them, every member of the organization reads them
+const OBJECTS: [(&str, Permission); 3] = [("organization", Permission::UpdateOrganization), ("vote", Permission::EditVote), ("question", Permission::EditVote)];
+
+fn adapter_error(e: sqlx::Error) -> casbin::Error {
+    AdapterError(Box::new(e)).into()
+}
+
+// the `p` rules of the roles, taken from the permission matrix of the roles so the two never disagree
+pub fn policies() -> Vec<Vec<String>> {
+    let mut rules = Vec::new();
+    for role in Role::ALL {
+        for (obj, write) in OBJECTS {
+            rules.push(vec![role.as_str().to_owned(), obj.to_owned(), "read".to_owned()]);
+            if role.can(write) {
+                rules.push(vec![role.as_str().to_owned(), obj.to_owned(), "write".to_owned()]);
+            }
+        }
+    }
+    rules
+}
+
+// an empty value of the filter matches any value
+fn matches(filter: &[&str], rule: &[String]) -> bool {
+    filter.iter().zip(rule).all(|(f, v)| f.is_empty() || *f == v.as_str())
+}
+
+// Gives the policies to the enforcer without storing them. The permissions of the roles are generated from
+// `Role::can` and the role assignments are the memberships of the organizations, neither can be changed through the
+// enforcer.
+#[derive(Debug, Clone)]
+pub struct PgAdapter {
+    pool: PgPool,
+    is_filtered: bool,
+}
+
+impl PgAdapter {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, is_filtered: false }
+    }
+
+    // the role of every member of the organization, or of every organization, as the `g` rules
+    pub async fn roles(&self, organization_id: Option<i32>) -> Result<Vec<Vec<String>>> {
+        let roles: Vec<(String, String, String)> = query_as(&format!(
+            "
+            SELECT m.user_id::VARCHAR, {}, m.organization_id::VARCHAR
+            FROM organization_members AS m
+            JOIN organizations AS o ON m.organization_id = o.id
+            WHERE $1::INTEGER IS NULL OR m.organization_id = $1",
+            MEMBER_ROLE
+        ))
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(adapter_error)?;
+        Ok(roles.into_iter().map(|(uid, role, domain)| vec![uid, role, domain]).collect())
+    }
+
+    async fn load(&self, m: &mut dyn Model, p: &[&str], g: &[&str]) -> Result<()> {
+        for rule in policies().into_iter().filter(|rule| matches(p, rule)) {
+            m.add_policy("p", "p", rule);
+        }
+        for rule in self.roles(None).await?.into_iter().filter(|rule| matches(g, rule)) {
+            m.add_policy("g", "g", rule);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Adapter for PgAdapter {
+    async fn load_policy(&self, m: &mut dyn Model) -> Result<()> {
+        self.load(m, &[], &[]).await
+    }
+
+    async fn load_filtered_policy<'a>(&mut self, m: &mut dyn Model, f: Filter<'a>) -> Result<()> {
+        self.load(m, &f.p, &f.g).await?;
+        self.is_filtered = !f.p.is_empty() || !f.g.is_empty();
+        Ok(())
+    }
+
+    async fn save_policy(&mut self, _m: &mut dyn Model) -> Result<()> {
+        Ok(())
+    }
+
+    async fn clear_policy(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.is_filtered
+    }
+
+    async fn add_policy(&mut self, _sec: &str, _ptype: &str, _rule: Vec<String>) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn add_policies(&mut self, _sec: &str, _ptype: &str, _rules: Vec<Vec<String>>) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn remove_policy(&mut self, _sec: &str, _ptype: &str, _rule: Vec<String>) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn remove_policies(&mut self, _sec: &str, _ptype: &str, _rules: Vec<Vec<String>>) -> Result<bool> {
+        Ok(false)
+    }
+
+    async fn remove_filtered_policy(&mut self, _sec: &str, _ptype: &str, _field_index: usize, _field_values: Vec<String>) -> Result<bool> {
+        Ok(false)
+    }
+}
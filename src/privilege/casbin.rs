@@ -1,39 +1,193 @@
+use actix_web::rt::time::sleep;
+use casbin::{CoreApi, DefaultModel, Enforcer, MgmtApi};
+use log::error;
+use sqlx::postgres::PgListener;
+use sqlx::{query_as, query_scalar, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+use crate::core::models::vote::VoteVisibility;
+use crate::error::Error;
+use crate::handlers::authorizer::Authorizer;
+use crate::privilege::adapter::PgAdapter;
+
+// the id of the organization whose roles have changed, sent by the triggers on the memberships
+const ROLES_CHANNEL: &str = "organization_roles";
+
+fn casbin_error(e: casbin::Error) -> Error {
+    Error::ServerError(e.to_string())
+}
+
+// Role based access with the organizations as the domains. The subjects and the domains are the ids of the users
+// and the organizations, the role assignments are the memberships of the organizations.
+pub struct CasbinAuthorizer {
+    pool: PgPool,
+    enforcer: Arc<RwLock<Enforcer>>,
+}
+
+impl CasbinAuthorizer {
+    // The roles of an organization are reloaded once the database notifies a change of its memberships, so the
+    // changes made by the other workers and instances are seen without a lookup on every check.
+    pub async fn new(pool: PgPool) -> Result<Self, Error> {
+        let model = DefaultModel::from_str(include_str!("casbin.conf")).await.map_err(casbin_error)?;
+        let adapter = PgAdapter::new(pool.clone());
+        let mut enforcer = Enforcer::new(model, adapter.clone()).await.map_err(casbin_error)?;
+        // the reloaded roles are only applied to the enforcer, they are never written back
+        enforcer.enable_auto_save(false);
+        let enforcer = Arc::new(RwLock::new(enforcer));
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(ROLES_CHANNEL).await?;
+        actix_web::rt::spawn(reload_roles(listener, adapter, enforcer.clone()));
+        Ok(Self { pool, enforcer })
+    }
+
+    async fn enforce(&self, uid: i32, org_id: Option<i32>, obj: &str, act: &str) -> Result<bool, Error> {
+        let domain = match org_id {
+            Some(org_id) => org_id.to_string(),
+            None => return Ok(false),
+        };
+        self.enforcer.read().await.enforce((uid.to_string(), domain, obj, act)).map_err(casbin_error)
+    }
+
+    // Public votes can be read by any user and WhiteList votes are only accessible to the whitelisted users, the
+    // managers of the organization can always access its votes.
+    async fn vote_access(&self, uid: i32, vote_id: Option<i32>, obj: &str, act: &str) -> Result<bool, Error> {
+        let vote_id = match vote_id {
+            Some(vote_id) => vote_id,
+            None => return Ok(false),
+        };
+        let vote: Option<(i32, String, bool)> = query_as(
+            "SELECT v.organization_id, v.visibility, EXISTS(SELECT 1 FROM vote_whitelists AS w WHERE w.vote_id = v.id AND w.user_id = $2)
+            FROM votes AS v
+            WHERE v.id = $1",
+        )
+        .bind(vote_id)
+        .bind(uid)
+        .fetch_optional(&self.pool)
+        .await?;
+        match vote {
+            None => Ok(false),
+            Some((_, visibility, _)) if act == "read" && visibility == VoteVisibility::Public.as_str() => Ok(true),
+            // the whitelisted users take part in the vote whether they are members of the organization or not
+            Some((_, visibility, true)) if act == "read" && visibility == VoteVisibility::WhiteList.as_str() => Ok(true),
+            Some((org_id, visibility, false)) if visibility == VoteVisibility::WhiteList.as_str() => self.enforce(uid, Some(org_id), "organization", "write").await,
+            Some((org_id, ..)) => self.enforce(uid, Some(org_id), obj, act).await,
+        }
+    }
+
+    async fn question_vote(&self, question_id: i32) -> Result<Option<i32>, Error> {
+        let vote_id = query_scalar("SELECT vote_id FROM questions WHERE id = $1").bind(question_id).fetch_optional(&self.pool).await?;
+        Ok(vote_id)
+    }
+}
+
+async fn reload_domain(adapter: &PgAdapter, enforcer: &RwLock<Enforcer>, org_id: i32) -> casbin::Result<()> {
+    let roles = adapter.roles(Some(org_id)).await?;
+    let mut enforcer = enforcer.write().await;
+    enforcer.remove_filtered_grouping_policy(2, vec![org_id.to_string()]).await?;
+    if !roles.is_empty() {
+        enforcer.add_grouping_policies(roles).await?;
+    }
+    Ok(())
+}
+
+// The notifications sent while the connection is lost are missed, all the rules are reloaded once it is back.
+async fn reload_roles(mut listener: PgListener, adapter: PgAdapter, enforcer: Arc<RwLock<Enforcer>>) {
+    loop {
+        let reloaded = match listener.try_recv().await {
+            Ok(Some(notification)) => match notification.payload().parse() {
+                Ok(org_id) => reload_domain(&adapter, &enforcer, org_id).await,
+                Err(_) => continue,
+            },
+            Ok(None) => enforcer.write().await.load_policy().await,
+            Err(e) => {
+                error!("failed to receive the changes of the roles: {}", e);
+                sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        if let Err(e) = reloaded {
+            error!("failed to reload the roles: {}", e);
+        }
+    }
+}
+
+impl Authorizer for CasbinAuthorizer {
+    async fn check_organization_read(&self, uid: i32, org_id: i32) -> Result<bool, Error> {
+        self.enforce(uid, Some(org_id), "organization", "read").await
+    }
+
+    async fn check_organization_write(&self, uid: i32, org_id: i32) -> Result<bool, Error> {
+        self.enforce(uid, Some(org_id), "organization", "write").await
+    }
+
+    async fn check_vote_read(&self, uid: i32, vote_id: i32) -> Result<bool, Error> {
+        self.vote_access(uid, Some(vote_id), "vote", "read").await
+    }
+
+    async fn check_vote_write(&self, uid: i32, vote_id: i32) -> Result<bool, Error> {
+        self.vote_access(uid, Some(vote_id), "vote", "write").await
+    }
+
+    async fn check_question_read(&self, uid: i32, question_id: i32) -> Result<bool, Error> {
+        let vote_id = self.question_vote(question_id).await?;
+        self.vote_access(uid, vote_id, "question", "read").await
+    }
+
+    async fn check_question_write(&self, uid: i32, question_id: i32) -> Result<bool, Error> {
+        let vote_id = self.question_vote(question_id).await?;
+        self.vote_access(uid, vote_id, "question", "write").await
+    }
+}
+
+#[cfg(test)]
 mod test {
-    use crate::serde::Serialize;
-    #[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
-    struct User {
-        id: i32,
-        role: String,
+    use crate::privilege::adapter::policies;
+    use casbin::{CoreApi, Enforcer, MgmtApi};
+
+    // the rules of the tests are the ones generated from the permission matrix of the roles
+    #[test]
+    fn test_policies_agree_with_roles() {
+        let mut rules: Vec<Vec<String>> = include_str!("policies.csv")
+            .lines()
+            .filter_map(|line| line.strip_prefix("p, "))
+            .map(|rule| rule.split(", ").map(str::to_owned).collect())
+            .collect();
+        let mut generated = policies();
+        rules.sort();
+        generated.sort();
+        assert_eq!(rules, generated);
     }
 
-    #[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
-    struct Resource {
-        id: i32,
-        owner: User,
+    #[tokio::test]
+    async fn test_roles_in_domains() {
+        let e = Enforcer::new("src/privilege/casbin.conf", "src/privilege/policies.csv").await.unwrap();
+        // the owner and the editor of the first organization
+        assert!(e.enforce(("1", "1", "organization", "write")).unwrap());
+        assert!(e.enforce(("2", "1", "vote", "write")).unwrap());
+        assert!(!e.enforce(("2", "1", "organization", "write")).unwrap());
+        // an observer of the first organization manages the second one
+        assert!(e.enforce(("3", "1", "vote", "read")).unwrap());
+        assert!(!e.enforce(("3", "1", "vote", "write")).unwrap());
+        assert!(e.enforce(("3", "2", "organization", "write")).unwrap());
+        // no role out of the organizations joined
+        assert!(!e.enforce(("2", "2", "vote", "read")).unwrap());
+        assert!(!e.enforce(("1", "2", "question", "read")).unwrap());
     }
+
     #[tokio::test]
-    async fn test_casbin() {
-        use crate::casbin::prelude::*;
-        let mut e = Enforcer::new("src/privilege/casbin.conf", "src/privilege/policies.csv")
+    async fn test_reload_domain() {
+        let mut e = Enforcer::new("src/privilege/casbin.conf", "src/privilege/policies.csv").await.unwrap();
+        e.enable_auto_save(false);
+        // the editor of the first organization is promoted, the observer has left it
+        e.remove_filtered_grouping_policy(2, vec!["1".into()]).await.unwrap();
+        e.add_grouping_policies(vec![vec!["1".into(), "Owner".into(), "1".into()], vec!["2".into(), "Manager".into(), "1".into()]])
             .await
             .unwrap();
-        e.enable_log(true);
-        let res = e
-            .enforce((
-                User {
-                    id: 1,
-                    role: "admin".to_owned(),
-                },
-                Resource {
-                    id: 1,
-                    owner: User {
-                        id: 3,
-                        role: "user".to_owned(),
-                    },
-                },
-                "read",
-            ))
-            .unwrap();
-        println!("{}", res);
+        assert!(e.enforce(("2", "1", "organization", "write")).unwrap());
+        assert!(!e.enforce(("3", "1", "vote", "read")).unwrap());
+        // the other organizations are left as they are
+        assert!(e.enforce(("3", "2", "organization", "write")).unwrap());
     }
 }
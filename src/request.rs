@@ -1,3 +1,4 @@
+use crate::core::models::answer::Source;
 use crate::serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -5,3 +6,10 @@ pub struct Pagination {
     pub page: i64,
     pub size: i64,
 }
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReportQuery {
+    // users or guests, their answers are never mixed in a report
+    #[serde(default)]
+    pub source: Source,
+}
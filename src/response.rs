@@ -38,3 +38,9 @@ impl UpdateResponse {
 pub struct CreateResponse {
     pub id: i32,
 }
+
+// issued by the first submission to an anonymous vote, the only way to change the answers later
+#[derive(Debug, Clone, Serialize)]
+pub struct BallotResponse {
+    pub ballot_token: String,
+}